@@ -0,0 +1,557 @@
+//! A small expression language for picking features by their properties, e.g.
+//! `population > 1e6 AND name LIKE 'New%'`.
+//!
+//! Expressions combine comparisons (`=`, `!=`, `<>`, `<`, `<=`, `>`, `>=`), `[NOT] LIKE` patterns
+//! (`%` matches any run of characters, `_` a single one) and `IS [NOT] NULL` checks with `AND`,
+//! `OR`, `NOT` and parentheses. Property names containing spaces or clashing with keywords can
+//! be written in double quotes, strings in single quotes. As in SQL, comparing against a missing
+//! or null property is neither true nor false, so such features never match.
+
+use crate::{Properties, Value};
+use std::{cmp, fmt};
+
+/// A parsed filter expression, along with the text it was parsed from.
+#[derive(Clone, Debug)]
+pub struct Filter {
+    source: String,
+    expression: Expression,
+}
+
+impl Filter {
+    pub fn parse(source: &str) -> Result<Self, FilterError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+            end: source.chars().count(),
+        };
+        let expression = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(FilterError::new(
+                format!("unexpected {}", token.kind),
+                token.position,
+            ));
+        }
+        Ok(Filter {
+            source: source.to_owned(),
+            expression,
+        })
+    }
+
+    pub fn matches(&self, properties: &Properties) -> bool {
+        self.expression.evaluate(properties) == Some(true)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct FilterError {
+    message: String,
+    /// Character offset into the expression.
+    position: usize,
+}
+
+impl FilterError {
+    fn new(message: String, position: usize) -> Self {
+        FilterError { message, position }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.position + 1)
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+#[derive(Clone, Debug)]
+enum Expression {
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+    Compare(Operand, Comparison, Operand),
+    Like {
+        operand: Operand,
+        pattern: Operand,
+        negated: bool,
+    },
+    IsNull {
+        operand: Operand,
+        negated: bool,
+    },
+    /// A lone operand, true when it's a `true` boolean.
+    Operand(Operand),
+}
+
+impl Expression {
+    /// `None` when the outcome is unknown because a property is missing or null, or the values
+    /// can't be compared.
+    fn evaluate(&self, properties: &Properties) -> Option<bool> {
+        match self {
+            Expression::And(a, b) => match (a.evaluate(properties), b.evaluate(properties)) {
+                (Some(false), _) | (_, Some(false)) => Some(false),
+                (Some(true), Some(true)) => Some(true),
+                _ => None,
+            },
+            Expression::Or(a, b) => match (a.evaluate(properties), b.evaluate(properties)) {
+                (Some(true), _) | (_, Some(true)) => Some(true),
+                (Some(false), Some(false)) => Some(false),
+                _ => None,
+            },
+            Expression::Not(expression) => expression.evaluate(properties).map(|b| !b),
+            Expression::Compare(a, comparison, b) => {
+                let ordering = compare(a.evaluate(properties)?, b.evaluate(properties)?)?;
+                Some(comparison.holds(ordering))
+            }
+            Expression::Like {
+                operand,
+                pattern,
+                negated,
+            } => {
+                let text = operand.evaluate(properties)?.to_string();
+                let pattern = pattern.evaluate(properties)?.to_string();
+                let text = text.chars().collect::<Vec<_>>();
+                let pattern = pattern.chars().collect::<Vec<_>>();
+                Some(is_like(&text, &pattern) != *negated)
+            }
+            Expression::IsNull { operand, negated } => {
+                Some(operand.evaluate(properties).is_none() != *negated)
+            }
+            Expression::Operand(operand) => match operand.evaluate(properties)? {
+                Value::Boolean(b) => Some(*b),
+                _ => None,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Operand {
+    Property(String),
+    Literal(Value),
+}
+
+impl Operand {
+    /// Missing properties and nulls are both `None`.
+    fn evaluate<'a>(&'a self, properties: &'a Properties) -> Option<&'a Value> {
+        let value = match self {
+            Operand::Property(name) => properties.get(name)?,
+            Operand::Literal(value) => value,
+        };
+        (*value != Value::Null).then_some(value)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn holds(self, ordering: cmp::Ordering) -> bool {
+        match self {
+            Comparison::Equal => ordering.is_eq(),
+            Comparison::NotEqual => ordering.is_ne(),
+            Comparison::Less => ordering.is_lt(),
+            Comparison::LessOrEqual => ordering.is_le(),
+            Comparison::Greater => ordering.is_gt(),
+            Comparison::GreaterOrEqual => ordering.is_ge(),
+        }
+    }
+}
+
+/// Numbers are compared numerically, also against text that parses as a number, since many
+/// formats store numbers as text.
+fn compare(a: &Value, b: &Value) -> Option<cmp::Ordering> {
+    match (a, b) {
+        (Value::Integer(a), Value::Integer(b)) => Some(a.cmp(b)),
+        (Value::Boolean(a), Value::Boolean(b)) => Some(a.cmp(b)),
+        (
+            Value::String(a) | Value::Date(a) | Value::DateTime(a),
+            Value::String(b) | Value::Date(b) | Value::DateTime(b),
+        ) => Some(a.cmp(b)),
+        _ => as_number(a)?.partial_cmp(&as_number(b)?),
+    }
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(n) => Some(*n as f64),
+        Value::Number(n) => Some(*n),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn is_like(text: &[char], pattern: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('%', rest)) => (0..=text.len()).any(|skip| {
+            text.get(skip..)
+                .is_some_and(|remaining| is_like(remaining, rest))
+        }),
+        Some((expected, rest)) => match text.split_first() {
+            Some((c, remaining)) => (*expected == '_' || c == expected) && is_like(remaining, rest),
+            None => false,
+        },
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum TokenKind {
+    Identifier(String),
+    /// Keywords are matched case-insensitively and stored uppercase.
+    Keyword(&'static str),
+    Literal(Value),
+    Comparison(&'static str),
+    OpenParen,
+    CloseParen,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenKind::Identifier(name) => write!(f, "property \"{}\"", name),
+            TokenKind::Keyword(keyword) => write!(f, "'{}'", keyword),
+            TokenKind::Literal(value) => write!(f, "value {}", value),
+            TokenKind::Comparison(operator) => write!(f, "'{}'", operator),
+            TokenKind::OpenParen => write!(f, "'('"),
+            TokenKind::CloseParen => write!(f, "')'"),
+        }
+    }
+}
+
+struct Token {
+    kind: TokenKind,
+    position: usize,
+}
+
+const KEYWORDS: [&str; 8] = ["AND", "OR", "NOT", "LIKE", "IS", "NULL", "TRUE", "FALSE"];
+
+const COMPARISONS: [&str; 8] = ["<=", ">=", "<>", "!=", "==", "=", "<", ">"];
+
+fn tokenize(source: &str) -> Result<Vec<Token>, FilterError> {
+    let chars = source.chars().collect::<Vec<_>>();
+    let mut tokens = vec![];
+    let mut position = 0;
+    while let Some(&c) = chars.get(position) {
+        let start = position;
+        let kind = if c.is_whitespace() {
+            position += 1;
+            continue;
+        } else if c == '(' {
+            position += 1;
+            TokenKind::OpenParen
+        } else if c == ')' {
+            position += 1;
+            TokenKind::CloseParen
+        } else if c == '\'' || c == '"' {
+            let (text, end) = quoted(&chars, position)?;
+            position = end;
+            if c == '\'' {
+                TokenKind::Literal(Value::String(text))
+            } else {
+                TokenKind::Identifier(text)
+            }
+        } else if c.is_ascii_digit()
+            || ((c == '-' || c == '.')
+                && chars
+                    .get(position + 1)
+                    .is_some_and(|next| next.is_ascii_digit() || *next == '.'))
+        {
+            position += 1;
+            while let Some(&c) = chars.get(position) {
+                let is_exponent_sign = (c == '-' || c == '+')
+                    && chars
+                        .get(position - 1)
+                        .is_some_and(|previous| matches!(previous, 'e' | 'E'));
+                if !(c.is_ascii_alphanumeric() || c == '.' || is_exponent_sign) {
+                    break;
+                }
+                position += 1;
+            }
+            let text = chars.get(start..position).unwrap_or_default();
+            TokenKind::Literal(parse_number(&text.iter().collect::<String>(), start)?)
+        } else if c.is_alphabetic() || c == '_' {
+            while chars
+                .get(position)
+                .is_some_and(|c| c.is_alphanumeric() || *c == '_')
+            {
+                position += 1;
+            }
+            let word = chars
+                .get(start..position)
+                .unwrap_or_default()
+                .iter()
+                .collect::<String>();
+            match KEYWORDS
+                .iter()
+                .find(|keyword| keyword.eq_ignore_ascii_case(&word))
+            {
+                Some(&"NULL") => TokenKind::Literal(Value::Null),
+                Some(&"TRUE") => TokenKind::Literal(Value::Boolean(true)),
+                Some(&"FALSE") => TokenKind::Literal(Value::Boolean(false)),
+                Some(keyword) => TokenKind::Keyword(keyword),
+                None => TokenKind::Identifier(word),
+            }
+        } else if let Some(operator) = COMPARISONS.iter().find(|operator| {
+            operator
+                .chars()
+                .enumerate()
+                .all(|(i, c)| chars.get(position + i) == Some(&c))
+        }) {
+            position += operator.len();
+            TokenKind::Comparison(operator)
+        } else {
+            return Err(FilterError::new(
+                format!("unexpected character '{}'", c),
+                position,
+            ));
+        };
+        tokens.push(Token {
+            kind,
+            position: start,
+        });
+    }
+    Ok(tokens)
+}
+
+/// The text between the quotes starting at `start`, where a doubled quote stands for a single
+/// one, and the position after the closing quote.
+fn quoted(chars: &[char], start: usize) -> Result<(String, usize), FilterError> {
+    let quote = chars.get(start).copied().unwrap_or('\'');
+    let mut text = String::new();
+    let mut position = start + 1;
+    loop {
+        match chars.get(position) {
+            None => return Err(FilterError::new("unterminated quote".into(), start)),
+            Some(&c) if c == quote => {
+                if chars.get(position + 1) == Some(&quote) {
+                    text.push(quote);
+                    position += 2;
+                } else {
+                    return Ok((text, position + 1));
+                }
+            }
+            Some(&c) => {
+                text.push(c);
+                position += 1;
+            }
+        }
+    }
+}
+
+fn parse_number(text: &str, position: usize) -> Result<Value, FilterError> {
+    if let Ok(n) = text.parse::<i64>() {
+        return Ok(Value::Integer(n));
+    }
+    text.parse::<f64>()
+        .map(Value::Number)
+        .map_err(|_| FilterError::new(format!("invalid number '{}'", text), position))
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    /// Length of the expression, reported when it ends too early.
+    end: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    /// Consumes the next token if it's of the given kind.
+    fn next_is(&mut self, kind: &TokenKind) -> bool {
+        let is_kind = self.peek().is_some_and(|token| token.kind == *kind);
+        if is_kind {
+            self.position += 1;
+        }
+        is_kind
+    }
+
+    fn next_is_keyword(&mut self, keyword: &'static str) -> bool {
+        self.next_is(&TokenKind::Keyword(keyword))
+    }
+
+    fn unexpected(&self, expected: &str) -> FilterError {
+        match self.peek() {
+            Some(token) => FilterError::new(
+                format!("expected {}, found {}", expected, token.kind),
+                token.position,
+            ),
+            None => FilterError::new(format!("expected {}", expected), self.end),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expression, FilterError> {
+        let mut expression = self.parse_and()?;
+        while self.next_is_keyword("OR") {
+            expression = Expression::Or(Box::new(expression), Box::new(self.parse_and()?));
+        }
+        Ok(expression)
+    }
+
+    fn parse_and(&mut self) -> Result<Expression, FilterError> {
+        let mut expression = self.parse_not()?;
+        while self.next_is_keyword("AND") {
+            expression = Expression::And(Box::new(expression), Box::new(self.parse_not()?));
+        }
+        Ok(expression)
+    }
+
+    fn parse_not(&mut self) -> Result<Expression, FilterError> {
+        if self.next_is_keyword("NOT") {
+            return Ok(Expression::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_predicate()
+    }
+
+    fn parse_predicate(&mut self) -> Result<Expression, FilterError> {
+        if self.next_is(&TokenKind::OpenParen) {
+            let expression = self.parse_or()?;
+            if !self.next_is(&TokenKind::CloseParen) {
+                return Err(self.unexpected("')'"));
+            }
+            return Ok(expression);
+        }
+
+        let operand = self.parse_operand()?;
+        if self.next_is_keyword("IS") {
+            let negated = self.next_is_keyword("NOT");
+            if !self.next_is(&TokenKind::Literal(Value::Null)) {
+                return Err(self.unexpected("'NULL'"));
+            }
+            return Ok(Expression::IsNull { operand, negated });
+        }
+        let negated = self.next_is_keyword("NOT");
+        if self.next_is_keyword("LIKE") {
+            return Ok(Expression::Like {
+                operand,
+                pattern: self.parse_operand()?,
+                negated,
+            });
+        } else if negated {
+            return Err(self.unexpected("'LIKE'"));
+        }
+
+        let comparison = match self.peek().map(|token| &token.kind) {
+            Some(TokenKind::Comparison(operator)) => match *operator {
+                "=" | "==" => Comparison::Equal,
+                "!=" | "<>" => Comparison::NotEqual,
+                "<" => Comparison::Less,
+                "<=" => Comparison::LessOrEqual,
+                ">" => Comparison::Greater,
+                _ => Comparison::GreaterOrEqual,
+            },
+            _ => return Ok(Expression::Operand(operand)),
+        };
+        self.position += 1;
+        Ok(Expression::Compare(
+            operand,
+            comparison,
+            self.parse_operand()?,
+        ))
+    }
+
+    fn parse_operand(&mut self) -> Result<Operand, FilterError> {
+        let operand = match self.peek().map(|token| &token.kind) {
+            Some(TokenKind::Identifier(name)) => Operand::Property(name.clone()),
+            Some(TokenKind::Literal(value)) => Operand::Literal(value.clone()),
+            _ => return Err(self.unexpected("a property or value")),
+        };
+        self.position += 1;
+        Ok(operand)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    fn properties() -> Properties {
+        Properties::from([
+            ("name".to_owned(), Value::String("New York".into())),
+            ("population".to_owned(), Value::Integer(8_336_817)),
+            ("area km2".to_owned(), Value::Number(783.8)),
+            ("is_capital".to_owned(), Value::Boolean(false)),
+            ("zip".to_owned(), Value::String("10001".into())),
+            ("motto".to_owned(), Value::Null),
+        ])
+    }
+
+    fn matches(source: &str) -> bool {
+        Filter::parse(source).unwrap().matches(&properties())
+    }
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("population > 1e6 AND name LIKE 'New%'"));
+        assert!(matches("population > 1e6 and name like 'New Yor_'"));
+        assert!(!matches("population > 1e6 AND name LIKE 'new%'"));
+        assert!(matches("name NOT LIKE '%Jersey'"));
+        assert!(matches("\"area km2\" >= 783.8 OR population < 0"));
+        assert!(matches("NOT is_capital"));
+        assert!(matches("is_capital = FALSE"));
+        assert!(matches("zip > 10000 AND zip <> '10002'"));
+        assert!(matches(
+            "(population < 0 OR name = 'New York') AND NOT (zip = '0')"
+        ));
+        assert!(matches(
+            "motto IS NULL AND missing IS NULL AND name IS NOT NULL"
+        ));
+        assert!(matches("-3.5 < 0"));
+        assert!(matches("'It''s' = 'It''s'"));
+    }
+
+    #[test]
+    fn test_unknown_never_matches() {
+        assert!(!matches("missing = 1"));
+        assert!(!matches("NOT missing = 1"));
+        assert!(!matches("motto LIKE '%'"));
+        assert!(!matches("name > 3"));
+        assert!(matches("missing = 1 OR population > 0"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |source: &str| Filter::parse(source).unwrap_err().to_string();
+        assert_eq!(
+            error("population >"),
+            "expected a property or value at column 13"
+        );
+        assert_eq!(error("(population > 1"), "expected ')' at column 16");
+        assert_eq!(error("name = 'New"), "unterminated quote at column 8");
+        assert_eq!(
+            error("name ~ 'New'"),
+            "unexpected character '~' at column 6"
+        );
+        assert_eq!(error("name = 'a' 'b'"), "unexpected value b at column 12");
+        assert_eq!(
+            error("name NOT = 'a'"),
+            "expected 'LIKE', found '=' at column 10"
+        );
+        assert_eq!(error(""), "expected a property or value at column 1");
+    }
+}
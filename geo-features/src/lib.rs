@@ -6,13 +6,16 @@
     clippy::expect_used
 )]
 
-use geo::{BoundingRect, Contains};
-use std::{collections, fmt, iter, num, sync};
+pub mod filter;
+
+use geo::{dimensions::Dimensions, BoundingRect, Centroid, Contains, HasDimensions};
+use std::{collections, fmt, iter};
 
 #[derive(Default)]
 pub struct FeatureBuilder<Scalar: geo::CoordNum> {
     geometry: Option<geo::Geometry<Scalar>>,
     properties: Properties,
+    source_id: Option<SourceId>,
 }
 
 impl<Scalar: geo::CoordNum> FeatureBuilder<Scalar> {
@@ -20,6 +23,7 @@ impl<Scalar: geo::CoordNum> FeatureBuilder<Scalar> {
         FeatureBuilder {
             geometry: None,
             properties: Default::default(),
+            source_id: None,
         }
     }
 
@@ -34,13 +38,19 @@ impl<Scalar: geo::CoordNum> FeatureBuilder<Scalar> {
         FeatureBuilder { properties, ..self }
     }
 
+    pub fn with_source_id(self, source_id: Option<SourceId>) -> Self {
+        FeatureBuilder { source_id, ..self }
+    }
+
+    /// The feature's ID is assigned once it's added to a [`FeatureCollection`].
     pub fn build(self) -> Feature<Scalar> {
         let bounding_rect = self
             .geometry
             .as_ref()
             .and_then(|geometry| geometry.bounding_rect());
         Feature {
-            id: FeatureId::new(),
+            id: FeatureId::default(),
+            source_id: self.source_id,
             geometry: self.geometry,
             properties: self.properties,
             bounding_rect,
@@ -51,6 +61,8 @@ impl<Scalar: geo::CoordNum> FeatureBuilder<Scalar> {
 #[derive(Clone, Debug, Default)]
 pub struct Feature<Scalar: geo::CoordNum> {
     pub id: FeatureId,
+    /// The feature's identifier in the file or service it was loaded from, if it had one.
+    pub source_id: Option<SourceId>,
     pub geometry: Option<geo::Geometry<Scalar>>,
     pub properties: Properties,
     pub bounding_rect: Option<geo::Rect<Scalar>>,
@@ -89,14 +101,71 @@ impl<Scalar: geo::CoordNum> geo::CoordsIter for Feature<Scalar> {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Value {
     String(String),
+    /// Kept apart from `Number` so 64-bit identifiers don't lose precision.
+    Integer(i64),
     Number(f64),
     Boolean(bool),
+    /// ISO 8601 calendar date, e.g. `2024-03-01`.
+    Date(String),
+    /// ISO 8601 date and time, e.g. `2024-03-01T12:30:00Z`.
+    DateTime(String),
+    Binary(Vec<u8>),
+    Array(Vec<Value>),
+    Object(collections::BTreeMap<String, Value>),
     Null,
 }
 
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::String(s) | Value::Date(s) | Value::DateTime(s) => write!(f, "{}", s),
+            _ => self.fmt_nested(f),
+        }
+    }
+}
+
+impl Value {
+    /// Like `Display`, but with strings quoted so they can be told apart inside arrays and
+    /// objects.
+    fn fmt_nested(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::String(s) | Value::Date(s) | Value::DateTime(s) => write!(f, "{:?}", s),
+            Value::Integer(n) => write!(f, "{}", n),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Boolean(b) => write!(f, "{}", b),
+            Value::Binary(bytes) => {
+                write!(f, "0x")?;
+                bytes.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+            }
+            Value::Array(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    value.fmt_nested(f)?;
+                }
+                write!(f, "]")
+            }
+            Value::Object(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{:?}: ", key)?;
+                    value.fmt_nested(f)?;
+                }
+                write!(f, "}}")
+            }
+            Value::Null => write!(f, "null"),
+        }
+    }
+}
+
 pub type Properties = collections::HashMap<String, Value>;
 
 impl<Scalar: geo::CoordNum> Feature<Scalar> {
@@ -129,8 +198,11 @@ where
 
 #[derive(Default, Clone, Debug)]
 pub struct FeatureCollection<Scalar: geo::CoordNum> {
-    pub features: Vec<Feature<Scalar>>,
+    /// Sorted by ID, which lookups by ID rely on.
+    features: Vec<Feature<Scalar>>,
     pub bounding_rect: Option<geo::Rect<Scalar>>,
+    /// ID of the next pushed feature, kept so IDs of removed features aren't handed out again.
+    next_id: u64,
 }
 
 impl<Scalar: geo::CoordNum + Default> FeatureCollection<Scalar> {
@@ -142,11 +214,11 @@ impl<Scalar: geo::CoordNum + Default> FeatureCollection<Scalar> {
 impl<Scalar: geo::CoordNum> geo::CoordsIter for FeatureCollection<Scalar> {
     type Scalar = Scalar;
     type Iter<'a>
-        = iter::Empty<geo::Coord<Self::Scalar>>
+        = Box<dyn Iterator<Item = geo::Coord<Self::Scalar>> + 'a>
     where
         Scalar: 'a;
     type ExteriorIter<'a>
-        = iter::Empty<geo::Coord<Self::Scalar>>
+        = Box<dyn Iterator<Item = geo::Coord<Self::Scalar>> + 'a>
     where
         Scalar: 'a;
 
@@ -155,11 +227,54 @@ impl<Scalar: geo::CoordNum> geo::CoordsIter for FeatureCollection<Scalar> {
     }
 
     fn coords_iter(&self) -> Self::Iter<'_> {
-        todo!()
+        Box::new(self.features.iter().flat_map(|f| f.coords_iter()))
     }
 
     fn exterior_coords_iter(&self) -> Self::ExteriorIter<'_> {
-        todo!()
+        Box::new(self.features.iter().flat_map(|f| f.exterior_coords_iter()))
+    }
+}
+
+// `ConvexHull` comes from geo's blanket implementation for `CoordsIter`
+
+impl<Scalar: geo::CoordNum> BoundingRect<Scalar> for FeatureCollection<Scalar> {
+    type Output = Option<geo::Rect<Scalar>>;
+
+    /// Unlike the `bounding_rect` field, this is always computed from the features' geometries.
+    fn bounding_rect(&self) -> Self::Output {
+        rect_merge_many(
+            self.geometry_iter()
+                .filter_map(|geometry| geometry.bounding_rect()),
+        )
+    }
+}
+
+impl<Scalar: geo::GeoFloat> Centroid for FeatureCollection<Scalar> {
+    type Output = Option<geo::Point<Scalar>>;
+
+    /// Same as the centroid of a `GeometryCollection` of all the features' geometries.
+    fn centroid(&self) -> Self::Output {
+        self.to_geometry_collection().centroid()
+    }
+}
+
+impl<Scalar: geo::GeoNum> HasDimensions for FeatureCollection<Scalar> {
+    fn is_empty(&self) -> bool {
+        self.geometry_iter().all(HasDimensions::is_empty)
+    }
+
+    fn dimensions(&self) -> Dimensions {
+        self.geometry_iter()
+            .map(HasDimensions::dimensions)
+            .max()
+            .unwrap_or(Dimensions::Empty)
+    }
+
+    fn boundary_dimensions(&self) -> Dimensions {
+        self.geometry_iter()
+            .map(HasDimensions::boundary_dimensions)
+            .max()
+            .unwrap_or(Dimensions::Empty)
     }
 }
 
@@ -183,17 +298,6 @@ where
     }
 }
 
-#[derive(Debug)]
-pub struct BoundingRectError;
-
-impl fmt::Display for BoundingRectError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "could not generate bounding rect")
-    }
-}
-
-impl std::error::Error for BoundingRectError {}
-
 impl<Scalar: geo::CoordNum> FeatureCollection<Scalar> {
     pub fn from_geometry(geometry: geo::Geometry<Scalar>) -> Self {
         let feature = FeatureBuilder::new().with_geometry(geometry).build();
@@ -201,19 +305,81 @@ impl<Scalar: geo::CoordNum> FeatureCollection<Scalar> {
     }
 
     pub fn from_feature(feature: Feature<Scalar>) -> Self {
+        Self::from_features(vec![feature])
+    }
+
+    /// Features are numbered in order, starting from zero.
+    pub fn from_features(mut features: Vec<Feature<Scalar>>) -> Self {
+        for (id, feature) in (0..).zip(features.iter_mut()) {
+            feature.id = FeatureId(id);
+        }
         FeatureCollection {
-            bounding_rect: feature.bounding_rect,
-            features: vec![feature],
+            bounding_rect: bounding_rect_from_features(&features),
+            next_id: features.len() as u64,
+            features,
         }
     }
 
-    pub fn from_features(features: Vec<Feature<Scalar>>) -> Self {
+    /// Converts every feature, keeping their IDs and the IDs that were already handed out.
+    pub fn map_features<To: geo::CoordNum>(
+        self,
+        map_feature: impl FnMut(Feature<Scalar>) -> Feature<To>,
+    ) -> FeatureCollection<To> {
+        let features: Vec<_> = self.features.into_iter().map(map_feature).collect();
         FeatureCollection {
             bounding_rect: bounding_rect_from_features(&features),
+            next_id: self.next_id,
             features,
         }
     }
 
+    /// Adds `feature` with an ID that no feature in this collection has had before, even if it
+    /// was removed since.
+    pub fn push(&mut self, mut feature: Feature<Scalar>) -> FeatureId {
+        feature.id = FeatureId(self.next_id);
+        self.next_id += 1;
+        self.bounding_rect = option_rect_merge(self.bounding_rect, feature.bounding_rect);
+        let id = feature.id;
+        self.features.push(feature);
+        id
+    }
+
+    /// Removes the feature with the ID `id`, whose ID isn't handed out again.
+    pub fn remove(&mut self, id: FeatureId) -> Option<Feature<Scalar>> {
+        let feature = self.features.remove(self.position(id)?);
+        self.recalculate_bounding_rect();
+        Some(feature)
+    }
+
+    /// The features, sorted by ID.
+    pub fn features(&self) -> &[Feature<Scalar>] {
+        &self.features
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Feature<Scalar>> {
+        self.features.iter()
+    }
+
+    /// The features' IDs must be left as they are, lookups by ID rely on their order.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Feature<Scalar>> {
+        self.features.iter_mut()
+    }
+
+    /// Index in `features()` of the feature with the ID `id`.
+    pub fn position(&self, id: FeatureId) -> Option<usize> {
+        // IDs are assigned in increasing order, so the features are sorted by ID
+        self.features.binary_search_by_key(&id, |f| f.id).ok()
+    }
+
+    pub fn get(&self, id: FeatureId) -> Option<&Feature<Scalar>> {
+        self.features.get(self.position(id)?)
+    }
+
+    pub fn get_mut(&mut self, id: FeatureId) -> Option<&mut Feature<Scalar>> {
+        let index = self.position(id)?;
+        self.features.get_mut(index)
+    }
+
     pub fn geometry_iter(&self) -> impl Iterator<Item = &geo::Geometry<Scalar>> {
         self.features.iter().filter_map(|f| f.geometry.as_ref())
     }
@@ -222,18 +388,20 @@ impl<Scalar: geo::CoordNum> FeatureCollection<Scalar> {
         geo::GeometryCollection(self.geometry_iter().cloned().collect())
     }
 
-    pub fn bounding_rect(&self) -> Result<geo::Rect<Scalar>, BoundingRectError> {
-        rect_merge_many(
-            self.geometry_iter()
-                .filter_map(|geometry| geometry.bounding_rect()),
-        )
-    }
-
     pub fn recalculate_bounding_rect(&mut self) {
         self.bounding_rect = bounding_rect_from_features(&self.features);
     }
 }
 
+impl<Scalar: geo::CoordNum> IntoIterator for FeatureCollection<Scalar> {
+    type Item = Feature<Scalar>;
+    type IntoIter = std::vec::IntoIter<Feature<Scalar>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.features.into_iter()
+    }
+}
+
 fn bounding_rect_from_features<Scalar: geo::CoordNum>(
     features: &[Feature<Scalar>],
 ) -> Option<geo::Rect<Scalar>> {
@@ -243,12 +411,10 @@ fn bounding_rect_from_features<Scalar: geo::CoordNum>(
         .fold(None, option_rect_merge)
 }
 
-// TODO: this assumes the iterator has one item. is that okay?
 fn rect_merge_many<Scalar: geo::CoordNum>(
-    mut iter: impl Iterator<Item = geo::Rect<Scalar>>,
-) -> Result<geo::Rect<Scalar>, BoundingRectError> {
-    let first = iter.next().ok_or(BoundingRectError)?;
-    Ok(iter.fold(first, rect_merge))
+    iter: impl Iterator<Item = geo::Rect<Scalar>>,
+) -> Option<geo::Rect<Scalar>> {
+    iter.reduce(rect_merge)
 }
 
 fn option_rect_merge<Scalar: geo::CoordNum>(
@@ -297,25 +463,120 @@ fn max<Scalar: geo::CoordNum>(a: Scalar, b: Scalar) -> Scalar {
     }
 }
 
-// The starting value is `1` so we can utilize `NonZeroU16`.
-static NEXT_ID: sync::atomic::AtomicU16 = sync::atomic::AtomicU16::new(1);
+/// Identifies a feature within its layer. IDs are kept when the layer is reprojected, and
+/// aren't reused after a feature is removed.
+#[derive(Copy, Clone, Debug, Default, Eq, Ord, PartialEq, PartialOrd, Hash)]
+pub struct FeatureId(u64);
 
-#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd, Hash)]
-pub struct FeatureId(num::NonZeroU16);
-
-impl Default for FeatureId {
-    fn default() -> Self {
-        Self::new()
+impl fmt::Display for FeatureId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
     }
 }
 
-impl FeatureId {
-    pub fn new() -> Self {
-        FeatureId(new_id())
+/// A feature's identifier in its source, e.g. the GeoJSON `id` member or the record number of a
+/// Shapefile.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SourceId {
+    Integer(i64),
+    String(String),
+}
+
+impl fmt::Display for SourceId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SourceId::Integer(n) => write!(f, "{}", n),
+            SourceId::String(s) => write!(f, "{}", s),
+        }
     }
 }
 
-fn new_id() -> num::NonZeroU16 {
-    // Unsafety: The starting ID is 1 and we always increment.
-    unsafe { num::NonZeroU16::new_unchecked(NEXT_ID.fetch_add(1, sync::atomic::Ordering::SeqCst)) }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_display() {
+        let value = Value::Object(collections::BTreeMap::from([
+            ("id".to_owned(), Value::Integer(9_007_199_254_740_993)),
+            (
+                "tags".to_owned(),
+                Value::Array(vec![Value::String("a".into()), Value::Null]),
+            ),
+        ]));
+        assert_eq!(
+            value.to_string(),
+            r#"{"id": 9007199254740993, "tags": ["a", null]}"#
+        );
+        assert_eq!(Value::String("a".into()).to_string(), "a");
+        assert_eq!(Value::Binary(vec![0xca, 0xfe]).to_string(), "0xcafe");
+    }
+
+    #[test]
+    fn test_feature_collection_geo_traits() {
+        use geo::{ConvexHull, CoordsIter};
+
+        let feature_collection = FeatureCollection::from_features(vec![
+            FeatureBuilder::new()
+                .with_geometry(geo::point!(x: 0., y: 0.).into())
+                .build(),
+            FeatureBuilder::new().build(),
+            FeatureBuilder::new()
+                .with_geometry(geo::Rect::new((1., 1.), (3., 3.)).into())
+                .build(),
+        ]);
+
+        assert_eq!(feature_collection.coords_iter().count(), 5);
+        assert_eq!(
+            feature_collection.bounding_rect(),
+            Some(geo::Rect::new((0., 0.), (3., 3.)))
+        );
+        assert_eq!(
+            feature_collection.centroid(),
+            Some(geo::point!(x: 2., y: 2.))
+        );
+        assert_eq!(feature_collection.convex_hull().exterior().0.len(), 5);
+        assert_eq!(feature_collection.dimensions(), Dimensions::TwoDimensional);
+        assert!(FeatureCollection::<f64>::new().is_empty());
+    }
+
+    #[test]
+    fn test_feature_ids() {
+        let feature = || {
+            FeatureBuilder::new()
+                .with_geometry(geo::point!(x: 1., y: 2.).into())
+                .build()
+        };
+        let mut feature_collection = FeatureCollection::from_features(vec![feature(), feature()]);
+        feature_collection.remove(FeatureId(0));
+
+        let id = feature_collection.push(feature());
+
+        assert_eq!(id, FeatureId(2));
+        assert_eq!(
+            feature_collection.get(FeatureId(1)).map(|f| f.id),
+            Some(FeatureId(1))
+        );
+        assert!(feature_collection.get(FeatureId(0)).is_none());
+    }
+
+    #[test]
+    fn test_feature_ids_after_removing_last_feature() {
+        let feature = || {
+            FeatureBuilder::new()
+                .with_geometry(geo::point!(x: 1., y: 2.).into())
+                .build()
+        };
+        let mut feature_collection = FeatureCollection::from_features(vec![feature(), feature()]);
+        let pushed_id = feature_collection.push(feature());
+        feature_collection.remove(pushed_id);
+        feature_collection.remove(FeatureId(1));
+
+        let id = feature_collection.push(feature());
+
+        assert_eq!(pushed_id, FeatureId(2));
+        assert_eq!(id, FeatureId(3));
+        assert!(feature_collection.get(FeatureId(1)).is_none());
+        assert_eq!(feature_collection.get(id).map(|f| f.id), Some(id));
+    }
 }
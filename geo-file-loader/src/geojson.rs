@@ -13,7 +13,62 @@ impl crate::FileLoader for GeoJsonSource {
     fn load(self) -> Result<crate::Features, crate::Error> {
         let bytes_cursor = io::Cursor::new(&self.bytes);
         let mut geojson_reader = geozero::geojson::GeoJsonReader(bytes_cursor);
-        let features = geojson_reader.to_geo_features()?.collect();
+        let mut features: crate::Features = geojson_reader
+            .to_geo_features()
+            .map_err(|e| locate(&self.bytes, e.into()))?
+            .map(crate::Feature::from)
+            .collect();
+        // geozero drops features without a geometry, so only pair IDs up when nothing was dropped
+        if let Some(ids) = source_ids(&self.bytes).filter(|ids| ids.len() == features.len()) {
+            for (feature, id) in features.iter_mut().zip(ids) {
+                feature.source_id = id;
+            }
+        }
         Ok(features)
     }
 }
+
+#[derive(serde::Deserialize)]
+struct IdsOnly {
+    features: Option<Vec<IdOnly>>,
+    id: Option<serde_json::Value>,
+}
+
+#[derive(serde::Deserialize)]
+struct IdOnly {
+    id: Option<serde_json::Value>,
+}
+
+/// The `id` members of a GeoJSON feature collection or feature, which geozero doesn't expose.
+fn source_ids(bytes: &[u8]) -> Option<Vec<Option<geo_features::SourceId>>> {
+    let ids_only: IdsOnly = serde_json::from_slice(bytes).ok()?;
+    Some(match ids_only.features {
+        Some(features) => features
+            .into_iter()
+            .map(|feature| feature.id.and_then(json_to_source_id))
+            .collect(),
+        None => vec![ids_only.id.and_then(json_to_source_id)],
+    })
+}
+
+fn json_to_source_id(id: serde_json::Value) -> Option<geo_features::SourceId> {
+    match id {
+        serde_json::Value::Number(n) => Some(match n.as_i64() {
+            Some(n) => geo_features::SourceId::Integer(n),
+            None => geo_features::SourceId::String(n.to_string()),
+        }),
+        serde_json::Value::String(s) => Some(geo_features::SourceId::String(s)),
+        _ => None,
+    }
+}
+
+/// geozero doesn't report where parsing failed, so parse again with `serde_json` to find out.
+/// Only syntax errors can be located this way.
+fn locate(bytes: &[u8], error: crate::Error) -> crate::Error {
+    match serde_json::from_slice::<serde::de::IgnoredAny>(bytes) {
+        Err(e) if e.line() > 0 => {
+            error.at_text_position(&String::from_utf8_lossy(bytes), e.line(), e.column())
+        }
+        _ => error,
+    }
+}
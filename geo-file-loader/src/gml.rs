@@ -0,0 +1,326 @@
+use roxmltree::Node;
+use std::str::FromStr;
+
+const GML_NAMESPACES: [&str; 2] = [
+    "http://www.opengis.net/gml/3.2",
+    "http://www.opengis.net/gml",
+];
+
+/// GML feature collections, as returned by WFS `GetFeature` requests.
+///
+/// Coordinates are read in the order they appear in the document; no axis swapping is done
+/// for coordinate reference systems with a latitude-first axis order.
+pub struct GmlSource {
+    pub bytes: bytes::Bytes,
+}
+
+impl crate::FileLoader for GmlSource {
+    fn from_bytes(bytes: bytes::Bytes) -> Self {
+        GmlSource { bytes }
+    }
+
+    fn load(self) -> Result<crate::Features, crate::Error> {
+        let text = std::str::from_utf8(&self.bytes)
+            .map_err(|_| crate::Error::InvalidGml("file is not valid UTF-8".into()))?;
+        let document = roxmltree::Document::parse(text).map_err(|e| {
+            let pos = e.pos();
+            crate::Error::from(e).at_text_position(text, pos.row as usize, pos.col as usize)
+        })?;
+        let mut features = vec![];
+        for node in feature_nodes(document.root_element()) {
+            if let Some(feature) = parse_feature(node)? {
+                features.push(feature);
+            }
+        }
+        Ok(features)
+    }
+}
+
+fn is_gml(node: Node) -> bool {
+    node.tag_name()
+        .namespace()
+        .is_some_and(|namespace| GML_NAMESPACES.contains(&namespace))
+}
+
+/// Members of a `wfs:FeatureCollection` (WFS 2.0) or `gml:FeatureCollection` (WFS 1.x).
+fn feature_nodes<'a, 'input>(root: Node<'a, 'input>) -> Vec<Node<'a, 'input>> {
+    if !root.tag_name().name().ends_with("FeatureCollection") {
+        return vec![root];
+    }
+    root.children()
+        .filter(|member| {
+            matches!(
+                member.tag_name().name(),
+                "member" | "featureMember" | "featureMembers"
+            )
+        })
+        .flat_map(|member| member.children().filter(Node::is_element))
+        .collect()
+}
+
+/// Features without a geometry are skipped.
+fn parse_feature(node: Node) -> Result<Option<crate::Feature>, crate::Error> {
+    let mut geometry = None;
+    let mut properties = vec![];
+
+    for property in node.children().filter(Node::is_element) {
+        if is_gml(property) && property.tag_name().name() == "boundedBy" {
+            continue;
+        }
+        match property.children().find(Node::is_element) {
+            Some(child) if is_gml(child) => {
+                if geometry.is_none() {
+                    geometry = Some(parse_geometry(child).map_err(|e| locate(e, child))?);
+                }
+            }
+            // Complex properties aren't supported
+            Some(_) => {}
+            None => properties.push((
+                property.tag_name().name().to_owned(),
+                crate::Value::String(property.text().unwrap_or_default().trim().into()),
+            )),
+        }
+    }
+
+    let Some(geometry) = geometry else {
+        return Ok(None);
+    };
+    // `fid` is used by WFS 1.0
+    let source_id = GML_NAMESPACES
+        .iter()
+        .find_map(|namespace| node.attribute((*namespace, "id")))
+        .or_else(|| node.attribute("fid"))
+        .map(|id| geo_features::SourceId::String(id.to_owned()));
+    Ok(Some(crate::Feature {
+        geometry,
+        properties: properties.into_iter().collect(),
+        source_id,
+    }))
+}
+
+fn parse_geometry(node: Node) -> Result<geo::Geometry, crate::Error> {
+    let descendants = |names: &'static [&'static str]| {
+        node.descendants()
+            .skip(1)
+            .filter(move |n| is_gml(*n) && names.contains(&n.tag_name().name()))
+    };
+
+    Ok(match node.tag_name().name() {
+        "Point" => geo::Point(
+            parse_coords(node)?
+                .into_iter()
+                .next()
+                .ok_or_else(|| crate::Error::InvalidGml("point without coordinates".into()))?,
+        )
+        .into(),
+        "LineString" | "LinearRing" | "Curve" => geo::LineString(parse_coords(node)?).into(),
+        "Polygon" => parse_polygon(node)?.into(),
+        "Surface" => geo::MultiPolygon(
+            descendants(&["PolygonPatch"])
+                .map(parse_polygon)
+                .collect::<Result<_, _>>()?,
+        )
+        .into(),
+        "MultiPoint" => geo::MultiPoint(
+            descendants(&["Point"])
+                .map(|point| {
+                    parse_coords(point)?
+                        .into_iter()
+                        .next()
+                        .map(geo::Point)
+                        .ok_or_else(|| crate::Error::InvalidGml("point without coordinates".into()))
+                })
+                .collect::<Result<_, _>>()?,
+        )
+        .into(),
+        "MultiLineString" | "MultiCurve" => geo::MultiLineString(
+            descendants(&["LineString", "Curve"])
+                .map(|line_string| parse_coords(line_string).map(geo::LineString))
+                .collect::<Result<_, _>>()?,
+        )
+        .into(),
+        "MultiPolygon" | "MultiSurface" => geo::MultiPolygon(
+            descendants(&["Polygon", "PolygonPatch"])
+                .map(parse_polygon)
+                .collect::<Result<_, _>>()?,
+        )
+        .into(),
+        "MultiGeometry" => geo::Geometry::GeometryCollection(geo::GeometryCollection(
+            node.children()
+                .filter(|n| matches!(n.tag_name().name(), "geometryMember" | "geometryMembers"))
+                .flat_map(|member| member.children().filter(Node::is_element))
+                .map(parse_geometry)
+                .collect::<Result<_, _>>()?,
+        )),
+        name => return Err(crate::Error::UnsupportedGmlGeometry(name.to_owned())),
+    })
+}
+
+/// Attaches the position of `node` to errors that weren't located more precisely.
+fn locate(error: crate::Error, node: Node) -> crate::Error {
+    let document = node.document();
+    let pos = document.text_pos_at(node.range().start);
+    error.at_text_position(document.input_text(), pos.row as usize, pos.col as usize)
+}
+
+fn parse_polygon(node: Node) -> Result<geo::Polygon, crate::Error> {
+    let mut exterior = None;
+    let mut interiors = vec![];
+    for ring in node.children().filter(Node::is_element) {
+        match ring.tag_name().name() {
+            "exterior" | "outerBoundaryIs" => exterior = Some(geo::LineString(parse_coords(ring)?)),
+            "interior" | "innerBoundaryIs" => interiors.push(geo::LineString(parse_coords(ring)?)),
+            _ => {}
+        }
+    }
+    let exterior =
+        exterior.ok_or_else(|| crate::Error::InvalidGml("polygon without exterior".into()))?;
+    Ok(geo::Polygon::new(exterior, interiors))
+}
+
+/// All coordinates of `gml:pos`, `gml:posList` and `gml:coordinates` elements under `node`,
+/// in document order.
+fn parse_coords(node: Node) -> Result<Vec<geo::Coord>, crate::Error> {
+    let mut coords = vec![];
+    for element in node.descendants().filter(|n| is_gml(*n)) {
+        parse_coords_element(element, &mut coords).map_err(|e| locate(e, element))?;
+    }
+    Ok(coords)
+}
+
+fn parse_coords_element(element: Node, coords: &mut Vec<geo::Coord>) -> Result<(), crate::Error> {
+    let text = element.text().unwrap_or_default();
+    match element.tag_name().name() {
+        "pos" | "posList" => {
+            let dimension = element
+                .ancestors()
+                .find_map(|n| n.attribute("srsDimension"))
+                .map_or(Ok(2), usize::from_str)
+                .map_err(|_| crate::Error::InvalidGml("invalid `srsDimension`".into()))?
+                .max(2);
+            let values = text
+                .split_whitespace()
+                .map(parse_number)
+                .collect::<Result<Vec<_>, _>>()?;
+            for chunk in values.chunks(dimension) {
+                let &[x, y, ..] = chunk else {
+                    return Err(crate::Error::InvalidGml(
+                        "incomplete coordinate tuple".into(),
+                    ));
+                };
+                coords.push(geo::coord! { x: x, y: y });
+            }
+        }
+        "coordinates" => {
+            let decimal = element.attribute("decimal").unwrap_or(".");
+            let cs = element.attribute("cs").unwrap_or(",");
+            let ts = element.attribute("ts").unwrap_or(" ");
+            let tuples = if ts.trim().is_empty() {
+                text.split_whitespace().collect::<Vec<_>>()
+            } else {
+                text.split(ts)
+                    .filter(|tuple| !tuple.trim().is_empty())
+                    .collect()
+            };
+            for tuple in tuples {
+                let mut values = tuple
+                    .split(cs)
+                    .map(|value| parse_number(&value.replace(decimal, ".")));
+                let (Some(x), Some(y)) = (values.next(), values.next()) else {
+                    return Err(crate::Error::InvalidGml(
+                        "incomplete coordinate tuple".into(),
+                    ));
+                };
+                coords.push(geo::coord! { x: x?, y: y? });
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn parse_number(value: &str) -> Result<f64, crate::Error> {
+    f64::from_str(value.trim())
+        .map_err(|_| crate::Error::InvalidGml(format!("invalid number `{value}`")))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::FileLoader;
+
+    #[test]
+    fn test_load_wfs_2_feature_collection() {
+        let gml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<wfs:FeatureCollection xmlns:wfs="http://www.opengis.net/wfs/2.0"
+    xmlns:gml="http://www.opengis.net/gml/3.2" xmlns:city="https://example.com/city">
+  <wfs:member>
+    <city:parcels gml:id="parcels.1">
+      <gml:boundedBy><gml:Envelope><gml:lowerCorner>0 0</gml:lowerCorner></gml:Envelope></gml:boundedBy>
+      <city:owner>Ada</city:owner>
+      <city:geom>
+        <gml:Polygon srsDimension="3">
+          <gml:exterior><gml:LinearRing>
+            <gml:posList>0 0 1 4 0 1 4 4 1 0 0 1</gml:posList>
+          </gml:LinearRing></gml:exterior>
+        </gml:Polygon>
+      </city:geom>
+    </city:parcels>
+  </wfs:member>
+  <wfs:member>
+    <city:parcels gml:id="parcels.2"><city:owner>Unmapped</city:owner></city:parcels>
+  </wfs:member>
+  <wfs:member>
+    <city:parcels gml:id="parcels.3">
+      <city:geom><gml:Point><gml:coordinates>1.5,2.5</gml:coordinates></gml:Point></city:geom>
+    </city:parcels>
+  </wfs:member>
+</wfs:FeatureCollection>"#;
+
+        let features = GmlSource::from_bytes(gml.into()).load().unwrap();
+
+        let [parcel, point] = features.as_slice() else {
+            panic!("expected 2 features, got {}", features.len());
+        };
+        let polygon = geo::Polygon::new(
+            geo::LineString::from(vec![(0., 0.), (4., 0.), (4., 4.), (0., 0.)]),
+            vec![],
+        );
+        assert_eq!(parcel.geometry, polygon.into());
+        assert!(matches!(
+            parcel.properties.get("owner"),
+            Some(crate::Value::String(owner)) if owner == "Ada"
+        ));
+        assert_eq!(point.geometry, geo::point!(x: 1.5, y: 2.5).into());
+        assert_eq!(
+            point.source_id,
+            Some(geo_features::SourceId::String("parcels.3".into()))
+        );
+    }
+
+    #[test]
+    fn test_error_location() {
+        let gml = r#"<gml:FeatureCollection xmlns:gml="http://www.opengis.net/gml">
+  <gml:featureMember>
+    <parcel>
+      <geom><gml:Point><gml:pos>1.5 north</gml:pos></gml:Point></geom>
+    </parcel>
+  </gml:featureMember>
+</gml:FeatureCollection>"#;
+
+        let error = GmlSource::from_bytes(gml.into()).load().unwrap_err();
+
+        assert_eq!(
+            error.location(),
+            Some(crate::Location::LineColumn {
+                line: 4,
+                column: 24
+            })
+        );
+        assert_eq!(
+            error.snippet(),
+            Some("<geom><gml:Point><gml:pos>1.5 north</gml:pos></gml:Point></geom>")
+        );
+    }
+}
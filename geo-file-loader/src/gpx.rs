@@ -15,8 +15,23 @@ impl crate::FileLoader for GpxSource {
     fn load(self) -> Result<crate::Features, crate::Error> {
         let bytes_cursor = io::Cursor::new(&self.bytes);
         let features = geozero::gpx::GpxReader(bytes_cursor)
-            .to_geo_features()?
+            .to_geo_features()
+            .map_err(|e| locate(&self.bytes, e.into()))?
+            .map(crate::Feature::from)
             .collect();
         Ok(features)
     }
 }
+
+/// geozero doesn't report where parsing failed, so parse again with `roxmltree` to find out.
+/// Only XML syntax errors can be located this way.
+fn locate(bytes: &[u8], error: crate::Error) -> crate::Error {
+    let text = String::from_utf8_lossy(bytes);
+    match roxmltree::Document::parse(&text) {
+        Err(e) => {
+            let pos = e.pos();
+            error.at_text_position(&text, pos.row as usize, pos.col as usize)
+        }
+        Ok(_) => error,
+    }
+}
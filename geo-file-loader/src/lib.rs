@@ -7,28 +7,109 @@
 )]
 
 mod geojson;
+mod gml;
 mod gpx;
 mod shapefile;
 mod wkt;
 
 pub use crate::geojson::GeoJsonSource;
+pub use crate::gml::GmlSource;
 pub use crate::gpx::GpxSource;
 pub use crate::shapefile::ShapefileSource;
 pub use crate::wkt::WktSource;
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
 pub enum FileFormat {
     GeoJson,
     Shapefile,
     Wkt,
     Gpx,
+    Gml,
 }
 
 #[derive(Debug)]
 pub enum Error {
     Geozero(geozero::error::GeozeroError),
     Shapefile(geozero::shp::Error),
+    Xml(roxmltree::Error),
+    InvalidGml(String),
+    UnsupportedGmlGeometry(String),
     NoGeometry,
+    Located {
+        error: Box<Error>,
+        location: Location,
+        /// The offending input, shortened to fit in an error message.
+        snippet: Option<String>,
+    },
+}
+
+/// Where in the input parsing failed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Location {
+    /// One-based line and column, for text formats.
+    LineColumn { line: usize, column: usize },
+    /// Zero-based index of the record, for binary formats.
+    Record(usize),
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Location::LineColumn { line, column } => write!(f, "line {}, column {}", line, column),
+            Location::Record(index) => write!(f, "record index {}", index),
+        }
+    }
+}
+
+impl Error {
+    pub fn location(&self) -> Option<Location> {
+        match self {
+            Error::Located { location, .. } => Some(*location),
+            _ => None,
+        }
+    }
+
+    pub fn snippet(&self) -> Option<&str> {
+        match self {
+            Error::Located { snippet, .. } => snippet.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Errors that already have a location keep the innermost, most precise one.
+    pub(crate) fn at(self, location: Location, snippet: Option<String>) -> Self {
+        match self {
+            Error::Located { .. } => self,
+            error => Error::Located {
+                error: Box::new(error),
+                location,
+                snippet,
+            },
+        }
+    }
+
+    pub(crate) fn at_text_position(self, text: &str, line: usize, column: usize) -> Self {
+        self.at(
+            Location::LineColumn { line, column },
+            line_snippet(text, line),
+        )
+    }
+}
+
+/// The one-based `line` of `text`, trimmed and shortened to fit in an error message.
+fn line_snippet(text: &str, line: usize) -> Option<String> {
+    const MAX_CHARS: usize = 80;
+    let line = text.lines().nth(line.checked_sub(1)?)?.trim();
+    if line.is_empty() {
+        None
+    } else if line.chars().count() > MAX_CHARS {
+        Some(format!(
+            "{}…",
+            line.chars().take(MAX_CHARS).collect::<String>()
+        ))
+    } else {
+        Some(line.to_owned())
+    }
 }
 
 impl std::fmt::Display for Error {
@@ -36,7 +117,35 @@ impl std::fmt::Display for Error {
         match self {
             Error::Geozero(err) => write!(f, "{}", err),
             Error::Shapefile(err) => write!(f, "{}", err),
+            Error::Xml(err) => write!(f, "{}", err),
+            Error::InvalidGml(reason) => write!(f, "Invalid GML: {}", reason),
+            Error::UnsupportedGmlGeometry(name) => {
+                write!(f, "Unsupported GML geometry type: {}", name)
+            }
             Error::NoGeometry => write!(f, "No geometry found in file"),
+            Error::Located {
+                error,
+                location,
+                snippet: Some(snippet),
+            } => write!(f, "{} at {}: `{}`", error, location, snippet),
+            Error::Located {
+                error,
+                location,
+                snippet: None,
+            } => write!(f, "{} at {}", error, location),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    // Wrapped errors are already part of `Display`, so skip to their sources
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Geozero(err) => std::error::Error::source(err),
+            Error::Shapefile(err) => std::error::Error::source(err),
+            Error::Xml(err) => std::error::Error::source(err),
+            Error::Located { error, .. } => std::error::Error::source(&**error),
+            Error::InvalidGml(_) | Error::UnsupportedGmlGeometry(_) | Error::NoGeometry => None,
         }
     }
 }
@@ -53,6 +162,12 @@ impl From<geozero::shp::Error> for Error {
     }
 }
 
+impl From<roxmltree::Error> for Error {
+    fn from(err: roxmltree::Error) -> Self {
+        Error::Xml(err)
+    }
+}
+
 impl FileFormat {
     pub const fn is_plaintext(self) -> bool {
         match self {
@@ -60,6 +175,7 @@ impl FileFormat {
             Self::Gpx => true,
             Self::Shapefile => false,
             Self::Wkt => true,
+            Self::Gml => true,
         }
     }
 
@@ -69,30 +185,130 @@ impl FileFormat {
             Self::Gpx => "GPX",
             Self::Shapefile => "Shapefile",
             Self::Wkt => "WKT",
+            Self::Gml => "GML",
+        }
+    }
+
+    /// Guess the format from the value of an HTTP `Content-Type` header.
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let media_type = content_type.split(';').next()?.trim().to_ascii_lowercase();
+        match media_type.as_str() {
+            "application/geo+json" | "application/vnd.geo+json" | "application/json" => {
+                Some(Self::GeoJson)
+            }
+            "application/gpx+xml" => Some(Self::Gpx),
+            "application/wkt" | "text/wkt" => Some(Self::Wkt),
+            "application/gml+xml" => Some(Self::Gml),
+            _ => None,
+        }
+    }
+
+    /// Guess the format from the extension of a file name, path or URL.
+    pub fn from_extension(path: &str) -> Option<Self> {
+        let path = path.split(['?', '#']).next()?;
+        let file_name = path.rsplit('/').next()?;
+        let (_, extension) = file_name.rsplit_once('.')?;
+        match extension.to_ascii_lowercase().as_str() {
+            "geojson" | "json" => Some(Self::GeoJson),
+            "gpx" => Some(Self::Gpx),
+            "shp" => Some(Self::Shapefile),
+            "wkt" => Some(Self::Wkt),
+            "gml" => Some(Self::Gml),
+            _ => None,
         }
     }
 }
 
-#[derive(Clone, Debug)]
-pub enum Value {
-    String(String),
-    Number(f64),
-    Boolean(bool),
-    Null,
+pub struct Feature {
+    pub geometry: geo::Geometry,
+    pub properties: geo_features::Properties,
+    pub source_id: Option<geo_features::SourceId>,
 }
 
-pub type Feature = geozero::geo_types::GeoFeature;
+impl From<geozero::geo_types::GeoFeature> for Feature {
+    fn from(feature: geozero::geo_types::GeoFeature) -> Self {
+        Feature {
+            geometry: feature.geometry,
+            properties: feature
+                .properties
+                .into_iter()
+                .map(|(name, value)| (name, owned_column_value_to_value(value)))
+                .collect(),
+            source_id: None,
+        }
+    }
+}
 
 pub type Features = Vec<Feature>;
 
 pub type OwnedColumnValue = geozero::geo_types::OwnedColumnValue;
 
+pub use geo_features::Value;
+
+fn owned_column_value_to_value(value: OwnedColumnValue) -> Value {
+    match value {
+        OwnedColumnValue::String(s) => Value::String(s),
+        OwnedColumnValue::Byte(n) => Value::Integer(n.into()),
+        OwnedColumnValue::UByte(n) => Value::Integer(n.into()),
+        OwnedColumnValue::Short(n) => Value::Integer(n.into()),
+        OwnedColumnValue::UShort(n) => Value::Integer(n.into()),
+        OwnedColumnValue::Int(n) => Value::Integer(n.into()),
+        OwnedColumnValue::UInt(n) => Value::Integer(n.into()),
+        OwnedColumnValue::Long(n) => Value::Integer(n),
+        OwnedColumnValue::ULong(n) => {
+            i64::try_from(n).map_or(Value::Number(n as f64), Value::Integer)
+        }
+        OwnedColumnValue::Float(n) => Value::Number(n.into()),
+        OwnedColumnValue::Double(n) => Value::Number(n),
+        OwnedColumnValue::Bool(b) => Value::Boolean(b),
+        OwnedColumnValue::Json(s) => match serde_json::from_str(&s) {
+            Ok(json) => json_to_value(json),
+            Err(_) => Value::String(s),
+        },
+        OwnedColumnValue::DateTime(s) if is_date(&s) => Value::Date(s),
+        OwnedColumnValue::DateTime(s) => Value::DateTime(s),
+        OwnedColumnValue::Binary(bytes) => Value::Binary(bytes),
+    }
+}
+
+fn json_to_value(json: serde_json::Value) -> Value {
+    match json {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Boolean(b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(n) => Value::Integer(n),
+            None => n.as_f64().map_or(Value::Null, Value::Number),
+        },
+        serde_json::Value::String(s) => Value::String(s),
+        serde_json::Value::Array(values) => {
+            Value::Array(values.into_iter().map(json_to_value).collect())
+        }
+        serde_json::Value::Object(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key, json_to_value(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Whether `s` is a date without a time, e.g. `2024-03-01`.
+fn is_date(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    bytes.len() == 10
+        && bytes.iter().enumerate().all(|(i, byte)| match i {
+            4 | 7 => *byte == b'-',
+            _ => byte.is_ascii_digit(),
+        })
+}
+
 pub fn load_file(file_format: FileFormat, bytes: bytes::Bytes) -> Result<Features, Error> {
     match file_format {
         FileFormat::GeoJson => Ok(GeoJsonSource::from_bytes(bytes).load()?),
         FileFormat::Gpx => Ok(GpxSource::from_bytes(bytes).load()?),
         FileFormat::Shapefile => Ok(ShapefileSource::from_bytes(bytes).load()?),
         FileFormat::Wkt => Ok(WktSource::from_bytes(bytes).load()?),
+        FileFormat::Gml => GmlSource::from_bytes(bytes).load(),
     }
 }
 
@@ -100,3 +316,54 @@ trait FileLoader {
     fn from_bytes(bytes: bytes::Bytes) -> Self;
     fn load(self) -> Result<Features, Error>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_content_type() {
+        assert_eq!(
+            FileFormat::from_content_type("application/geo+json; charset=utf-8"),
+            Some(FileFormat::GeoJson)
+        );
+        assert_eq!(FileFormat::from_content_type("text/plain"), None);
+    }
+
+    #[test]
+    fn test_owned_column_value_to_value() {
+        let json = r#"{"id": 9007199254740993, "tags": ["a", 1.5]}"#;
+        let Value::Object(object) =
+            owned_column_value_to_value(OwnedColumnValue::Json(json.into()))
+        else {
+            panic!("expected an object");
+        };
+        assert_eq!(
+            object.get("id"),
+            Some(&Value::Integer(9_007_199_254_740_993))
+        );
+        assert_eq!(
+            object.get("tags"),
+            Some(&Value::Array(vec![
+                Value::String("a".into()),
+                Value::Number(1.5)
+            ]))
+        );
+        assert_eq!(
+            owned_column_value_to_value(OwnedColumnValue::DateTime("2024-03-01".into())),
+            Value::Date("2024-03-01".into())
+        );
+    }
+
+    #[test]
+    fn test_from_extension() {
+        assert_eq!(
+            FileFormat::from_extension("https://example.com/a/tracks.GPX?token=1"),
+            Some(FileFormat::Gpx)
+        );
+        assert_eq!(
+            FileFormat::from_extension("https://example.com/data.v2/items"),
+            None
+        );
+    }
+}
@@ -13,11 +13,30 @@ impl crate::FileLoader for ShapefileSource {
         let mut bytes_cursor = io::Cursor::new(&self.bytes);
         let shapefile_reader = geozero::shp::ShpReader::new(&mut bytes_cursor)?;
         let mut geo_writer = geozero::geo_types::GeoWriter::new();
-        for _ in shapefile_reader.iter_geometries(&mut geo_writer) {}
+        let mut record_count = 0;
+        for (index, result) in shapefile_reader
+            .iter_geometries(&mut geo_writer)
+            .enumerate()
+        {
+            result.map_err(|e| crate::Error::from(e).at(crate::Location::Record(index), None))?;
+            record_count += 1;
+        }
         let geometry = geo_writer.take_geometry().ok_or(crate::Error::NoGeometry)?;
-        Ok(vec![crate::Feature {
-            geometry: geometry,
-            properties: Default::default(),
-        }])
+        // The writer collects the geometry of each record into a single geometry collection
+        let geometries = match geometry {
+            geo::Geometry::GeometryCollection(collection) => collection.0,
+            geometry => vec![geometry],
+        };
+        // Records without a shape have no geometry, which throws off the record numbers
+        let has_record_numbers = geometries.len() == record_count;
+        Ok((1..)
+            .zip(geometries)
+            .map(|(record_number, geometry)| crate::Feature {
+                geometry,
+                properties: Default::default(),
+                source_id: has_record_numbers
+                    .then_some(geo_features::SourceId::Integer(record_number)),
+            })
+            .collect())
     }
 }
@@ -14,8 +14,250 @@ impl crate::FileLoader for WktSource {
     fn load(self) -> Result<crate::Features, crate::Error> {
         let mut bytes_cursor = io::Cursor::new(&self.bytes);
         let features = geozero::wkt::WktReader(&mut bytes_cursor)
-            .to_geo_features()?
+            .to_geo_features()
+            .map_err(|e| locate(&self.bytes, e.into()))?
+            .map(crate::Feature::from)
             .collect();
         Ok(features)
     }
 }
+
+/// geozero doesn't report where parsing failed, so check the syntax here to find the offending
+/// token.
+fn locate(bytes: &[u8], error: crate::Error) -> crate::Error {
+    let text = String::from_utf8_lossy(bytes);
+    match Parser::new(&text).check() {
+        Err(Position { line, column }) => error.at_text_position(&text, line, column),
+        Ok(()) => error,
+    }
+}
+
+/// One-based line and column of a token.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Token<'a> {
+    Word(&'a str),
+    Number(&'a str),
+    LeftParen,
+    RightParen,
+    Comma,
+    Other,
+    End,
+}
+
+fn tokenize(text: &str) -> Vec<(Token<'_>, Position)> {
+    let mut tokens = vec![];
+    let mut position = Position { line: 1, column: 1 };
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let token_position = position;
+        let mut end = start + c.len_utf8();
+        let mut length = 1;
+        let is_word = c.is_ascii_alphabetic();
+        let is_number = c.is_ascii_digit() || matches!(c, '-' | '+' | '.');
+        if is_word || is_number {
+            while let Some(&(i, next)) = chars.peek() {
+                let is_part = if is_word {
+                    next.is_ascii_alphanumeric() || next == '_'
+                } else {
+                    next.is_ascii_digit() || matches!(next, '-' | '+' | '.' | 'e' | 'E')
+                };
+                if !is_part {
+                    break;
+                }
+                chars.next();
+                end = i + next.len_utf8();
+                length += 1;
+            }
+        }
+        position.column += length;
+        let token = match c {
+            '\n' => {
+                position = Position {
+                    line: position.line + 1,
+                    column: 1,
+                };
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            ',' => Token::Comma,
+            _ => match text.get(start..end) {
+                Some(word) if is_word => Token::Word(word),
+                Some(number) if is_number => Token::Number(number),
+                _ => Token::Other,
+            },
+        };
+        tokens.push((token, token_position));
+    }
+    tokens.push((Token::End, position));
+    tokens
+}
+
+/// Checks WKT syntax, for the position of the first token that doesn't fit.
+struct Parser<'a> {
+    tokens: Vec<(Token<'a>, Position)>,
+    index: usize,
+}
+
+type ParseResult = Result<(), Position>;
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        Parser {
+            tokens: tokenize(text),
+            index: 0,
+        }
+    }
+
+    fn check(&mut self) -> ParseResult {
+        self.geometry()?;
+        self.expect(Token::End)
+    }
+
+    fn peek(&self) -> (Token<'a>, Position) {
+        self.tokens
+            .get(self.index)
+            .or(self.tokens.last())
+            .copied()
+            .unwrap_or((Token::End, Position { line: 1, column: 1 }))
+    }
+
+    fn next(&mut self) -> (Token<'a>, Position) {
+        let next = self.peek();
+        self.index += 1;
+        next
+    }
+
+    fn expect(&mut self, expected: Token<'_>) -> ParseResult {
+        match self.next() {
+            (token, _) if token == expected => Ok(()),
+            (_, position) => Err(position),
+        }
+    }
+
+    fn is_word(&self, words: &[&str]) -> bool {
+        matches!(self.peek().0, Token::Word(word) if words.iter().any(|w| w.eq_ignore_ascii_case(word)))
+    }
+
+    fn geometry(&mut self) -> ParseResult {
+        let (tag, position) = match self.next() {
+            (Token::Word(tag), position) => (tag, position),
+            (_, position) => return Err(position),
+        };
+        let body: fn(&mut Self) -> ParseResult = match tag.to_ascii_uppercase().as_str() {
+            "POINT" | "LINESTRING" => |p| p.list(Self::coord),
+            "POLYGON" | "MULTILINESTRING" => |p| p.list(|p| p.list(Self::coord)),
+            "MULTIPOLYGON" => |p| p.list(|p| p.list(|p| p.list(Self::coord))),
+            "MULTIPOINT" => |p| p.list(Self::multi_point_member),
+            "GEOMETRYCOLLECTION" => |p| p.list(Self::geometry),
+            _ => return Err(position),
+        };
+        if self.is_word(&["Z", "M", "ZM"]) {
+            self.next();
+        }
+        body(self)
+    }
+
+    /// `EMPTY`, or `item`s separated by commas in parentheses.
+    fn list(&mut self, item: fn(&mut Self) -> ParseResult) -> ParseResult {
+        if self.is_word(&["EMPTY"]) {
+            self.next();
+            return Ok(());
+        }
+        self.expect(Token::LeftParen)?;
+        item(self)?;
+        while self.peek().0 == Token::Comma {
+            self.next();
+            item(self)?;
+        }
+        self.expect(Token::RightParen)
+    }
+
+    /// Multi points allow their points with or without parentheses.
+    fn multi_point_member(&mut self) -> ParseResult {
+        if self.peek().0 == Token::LeftParen || self.is_word(&["EMPTY"]) {
+            self.list(Self::coord)
+        } else {
+            self.coord()
+        }
+    }
+
+    /// Two to four numbers, for X, Y and optionally Z and M.
+    fn coord(&mut self) -> ParseResult {
+        self.number()?;
+        self.number()?;
+        for _ in 0..2 {
+            if matches!(self.peek().0, Token::Comma | Token::RightParen) {
+                break;
+            }
+            self.number()?;
+        }
+        Ok(())
+    }
+
+    fn number(&mut self) -> ParseResult {
+        match self.next() {
+            (Token::Number(number), _) if number.parse::<f64>().is_ok() => Ok(()),
+            (_, position) => Err(position),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileLoader;
+
+    #[test]
+    fn test_check() {
+        for wkt in [
+            "POINT (1 2)",
+            "point z (1 2 3)",
+            "LINESTRING (0 0, 1.5 -2e3)",
+            "POLYGON ((0 0, 1 0, 1 1, 0 0), (0.1 0.1, 0.2 0.1, 0.2 0.2, 0.1 0.1))",
+            "MULTIPOINT ((0 0), 1 1, EMPTY)",
+            "MULTIPOLYGON (((0 0, 1 0, 1 1, 0 0)), EMPTY)",
+            "GEOMETRYCOLLECTION (POINT (1 2),\n  LINESTRING EMPTY)",
+        ] {
+            assert_eq!(Parser::new(wkt).check(), Ok(()), "{wkt}");
+        }
+
+        for (wkt, line, column) in [
+            ("POINT (1 north)", 1, 10),
+            ("LINESTRING (0 0, 1 1", 1, 21),
+            ("CIRCLE (0 0)", 1, 1),
+            ("GEOMETRYCOLLECTION (\n  POINT (1 2)\n  POINT (3 4))", 3, 3),
+            ("POINT (1 2) POINT (3 4)", 1, 13),
+            ("POLYGON ((0 0; 1 1))", 1, 14),
+        ] {
+            assert_eq!(
+                Parser::new(wkt).check(),
+                Err(Position { line, column }),
+                "{wkt}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_error_location() {
+        let wkt = "GEOMETRYCOLLECTION (\n  POINT (1 2),\n  LINESTRING (0 0, 1 north)\n)";
+
+        let error = WktSource::from_bytes(wkt.into()).load().unwrap_err();
+
+        assert_eq!(
+            error.location(),
+            Some(crate::Location::LineColumn {
+                line: 3,
+                column: 22
+            })
+        );
+        assert_eq!(error.snippet(), Some("LINESTRING (0 0, 1 north)"));
+    }
+}
@@ -60,6 +60,7 @@ impl<Scalar: geo::CoordNum> WrapTo<Scalar> for geo_features::Feature<Scalar> {
     fn wrap<To: Debug>(self) -> Self::Output<To> {
         geo_features::Feature {
             id: self.id,
+            source_id: self.source_id,
             properties: self.properties,
             bounding_rect: self.bounding_rect.map(|rect| rect.wrap()),
             geometry: self.geometry.map(|geometry| geometry.wrap()),
@@ -71,14 +72,7 @@ impl<Scalar: geo::CoordNum> WrapTo<Scalar> for geo_features::FeatureCollection<S
     type Output<To: Debug> = geo_features::FeatureCollection<TypedNum<Scalar, To>>;
     #[inline]
     fn wrap<To: Debug>(self) -> Self::Output<To> {
-        geo_features::FeatureCollection {
-            features: self
-                .features
-                .into_iter()
-                .map(|feature| feature.wrap())
-                .collect(),
-            bounding_rect: self.bounding_rect.map(|rect| rect.wrap()),
-        }
+        self.map_features(|feature| feature.wrap())
     }
 }
 
@@ -128,6 +122,7 @@ impl<Scalar: geo::CoordNum, From: Debug> CastTo<Scalar, From>
     fn cast<To: Debug>(self) -> Self::Output<To> {
         geo_features::Feature {
             id: self.id,
+            source_id: self.source_id,
             properties: self.properties,
             bounding_rect: self.bounding_rect.map(|rect| rect.cast()),
             geometry: self.geometry.map(|geometry| geometry.cast()),
@@ -141,13 +136,6 @@ impl<Scalar: geo::CoordNum, From: Debug> CastTo<Scalar, From>
     type Output<To: Debug> = geo_features::FeatureCollection<TypedNum<Scalar, To>>;
     #[inline]
     fn cast<To: Debug>(self) -> Self::Output<To> {
-        geo_features::FeatureCollection {
-            features: self
-                .features
-                .into_iter()
-                .map(|feature| feature.cast())
-                .collect(),
-            bounding_rect: self.bounding_rect.map(|rect| rect.cast()),
-        }
+        self.map_features(|feature| feature.cast())
     }
 }
@@ -1,15 +1,19 @@
 use bevy::{prelude::*, window::PrimaryWindow};
+use geo::BoundingRect;
 
 pub fn configure(app: &mut App) {
+    app.init_resource::<rgis_units::VisibleExtent>();
     app.add_systems(Startup, init_camera);
     app.add_systems(
         Update,
         (
             center_camera,
+            center_camera_on_feature,
             pan_camera_system,
             handle_meshes_spawned_events,
             zoom_camera_system,
             handle_change_crs_event.pipe(log_error),
+            update_visible_extent,
         ),
     );
 }
@@ -58,6 +62,28 @@ fn handle_change_crs_event(
     Ok(())
 }
 
+fn update_visible_extent(
+    query: Query<
+        &bevy::transform::components::Transform,
+        bevy::ecs::query::With<bevy::render::camera::Camera>,
+    >,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    ui_margins: rgis_units::UiMargins,
+    mut visible_extent: ResMut<rgis_units::VisibleExtent>,
+) {
+    let (Ok(transform), Ok(window)) = (query.single(), windows.single()) else {
+        return;
+    };
+    let map_area = rgis_units::MapArea {
+        window,
+        left_offset_px: ui_margins.left.0,
+        right_offset_px: 0.,
+        top_offset_px: ui_margins.top.0,
+        bottom_offset_px: ui_margins.bottom.0,
+    };
+    visible_extent.0 = Some(map_area.projected_geo_rect(transform, window));
+}
+
 fn pan_camera_system(
     mut pan_camera_event_reader: bevy::ecs::event::EventReader<rgis_events::PanCameraEvent>,
     mut query: Query<
@@ -164,7 +190,7 @@ fn center_camera(
         .filter_map(|event| layers.get(event.0))
         .filter_map(|layer| layer.get_projected_feature_collection_or_log())
     {
-        let Ok(bounding_rect) = projected_feature.bounding_rect() else {
+        let Some(bounding_rect) = projected_feature.bounding_rect() else {
             continue;
         };
         let Ok(mut transform) = query.single_mut() else {
@@ -186,3 +212,42 @@ fn center_camera(
         );
     }
 }
+
+fn center_camera_on_feature(
+    layers: Res<rgis_layers::Layers>,
+    mut event_reader: EventReader<rgis_events::CenterCameraOnFeatureEvent>,
+    mut query: Query<
+        &mut bevy::transform::components::Transform,
+        bevy::ecs::query::With<bevy::render::camera::Camera>,
+    >,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    ui_margins: rgis_units::UiMargins,
+) {
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    for bounding_rect in event_reader.read().filter_map(|event| {
+        layers
+            .get(event.0)?
+            .get_projected_feature(event.1)?
+            .bounding_rect
+    }) {
+        let Ok(mut transform) = query.single_mut() else {
+            continue;
+        };
+
+        debug!("Moving camera to look at feature");
+        let map_area = rgis_units::MapArea {
+            window,
+            right_offset_px: 0.,
+            left_offset_px: ui_margins.left.0,
+            bottom_offset_px: ui_margins.bottom.0,
+            top_offset_px: ui_margins.top.0,
+        };
+        crate::utils::center_camera_on_projected_world_rect(
+            bounding_rect,
+            &mut transform,
+            map_area,
+        );
+    }
+}
@@ -7,7 +7,12 @@ pub(crate) fn center_camera_on_projected_world_rect(
 ) {
     let layer_center = bounding_rect.center();
     let scale = determine_scale(bounding_rect, map_area.size());
-    let camera_scale = crate::CameraScale(scale);
+    // A single point has no extent to fit, so keep the current zoom level
+    let camera_scale = if scale > 0. {
+        crate::CameraScale(scale)
+    } else {
+        crate::CameraScale::from_transform(camera_transform)
+    };
     let mut camera_offset = match crate::CameraOffset::from_coord(layer_center) {
         Ok(offset) => offset,
         Err(e) => {
@@ -7,7 +7,7 @@
 )]
 
 use bevy::prelude::*;
-use geo_projected::{ProjectedCoord, UnprojectedScalar};
+use geo_projected::{ProjectedCoord, ProjectedScalar, UnprojectedScalar};
 
 // Magic number used to normalize the host's scroll value.
 const ZOOM_FACTOR: f32 = 500.;
@@ -18,6 +18,9 @@ pub struct LayerCreatedEvent(pub rgis_layer_id::LayerId);
 #[derive(Debug, Event)]
 pub struct ShowManageLayerWindowEvent(pub rgis_layer_id::LayerId);
 
+#[derive(Debug, Event)]
+pub struct ShowAttributeTableWindowEvent(pub rgis_layer_id::LayerId);
+
 #[derive(Event, Debug)]
 pub struct ToggleLayerVisibilityEvent(pub rgis_layer_id::LayerId);
 
@@ -31,8 +34,116 @@ impl From<rgis_layer_id::LayerId> for CenterCameraEvent {
     }
 }
 
-#[derive(Clone, Copy, Event, Debug)]
-pub struct FeatureSelectedEvent(pub rgis_layer_id::LayerId, pub geo_features::FeatureId);
+#[derive(Event, Debug)]
+pub struct CenterCameraOnFeatureEvent(pub rgis_layer_id::LayerId, pub geo_features::FeatureId);
+
+/// How picked features combine with the current selection
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SelectionMode {
+    /// Select only the picked features, deselecting features in every other layer
+    #[default]
+    Replace,
+    Add,
+    /// Select the picked features that aren't selected, and deselect the ones that are
+    Toggle,
+}
+
+impl SelectionMode {
+    /// Shift adds to the selection, and Ctrl (or ⌘) toggles.
+    pub fn from_keyboard(keyboard: &ButtonInput<KeyCode>) -> Self {
+        if keyboard.any_pressed([
+            KeyCode::ControlLeft,
+            KeyCode::ControlRight,
+            KeyCode::SuperLeft,
+            KeyCode::SuperRight,
+        ]) {
+            SelectionMode::Toggle
+        } else if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            SelectionMode::Add
+        } else {
+            SelectionMode::Replace
+        }
+    }
+}
+
+/// Change which of a `Layer`'s features are selected
+#[derive(Event, Debug)]
+pub enum UpdateSelectionEvent {
+    Features {
+        layer_id: rgis_layer_id::LayerId,
+        feature_ids: Vec<geo_features::FeatureId>,
+        mode: SelectionMode,
+    },
+    /// Select every feature matching the layer's filter
+    All(rgis_layer_id::LayerId),
+    /// Select the features matching the layer's filter that aren't selected, and deselect the
+    /// ones that are
+    Invert(rgis_layer_id::LayerId),
+    Clear(rgis_layer_id::LayerId),
+    /// Deselect the features of every layer
+    ClearAll,
+}
+
+/// Which features an area selection picks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AreaSelectionPredicate {
+    Intersects,
+    /// Only features entirely inside the area
+    Within,
+}
+
+/// An area drawn on the map with the rectangle or lasso selection tools
+#[derive(Event, Debug)]
+pub struct MapAreaSelectedEvent {
+    pub area: geo::Polygon<ProjectedScalar>,
+    pub predicate: AreaSelectionPredicate,
+    pub mode: SelectionMode,
+}
+
+/// A shape finished with one of the drawing tools, before it's added to the scratch layer
+#[derive(Event, Debug)]
+pub struct FeatureDrawnEvent(pub geo::Geometry<ProjectedScalar>);
+
+/// Add a feature to the scratch layer, creating the layer if needed. The geometry is in the
+/// target CRS.
+#[derive(Event, Debug)]
+pub struct AddFeatureEvent {
+    pub geometry: geo::Geometry<ProjectedScalar>,
+    pub properties: geo_features::Properties,
+}
+
+/// Switch to the vertex editing tool, editing the `Layer`'s selected feature
+#[derive(Event, Debug)]
+pub struct EditLayerVerticesEvent(pub rgis_layer_id::LayerId);
+
+/// Replace the geometry of a `Layer`'s feature with one in the target CRS
+#[derive(Event, Debug)]
+pub struct UpdateFeatureGeometryEvent {
+    pub layer_id: rgis_layer_id::LayerId,
+    pub feature_id: geo_features::FeatureId,
+    pub geometry: geo::Geometry<ProjectedScalar>,
+}
+
+/// Replace the properties of a `Layer`'s feature
+#[derive(Event, Debug)]
+pub struct UpdateFeaturePropertiesEvent {
+    pub layer_id: rgis_layer_id::LayerId,
+    pub feature_id: geo_features::FeatureId,
+    pub properties: geo_features::Properties,
+}
+
+/// After features of a `Layer` were added or their properties changed
+#[derive(Event, Debug)]
+pub struct LayerFeaturesUpdatedEvent(pub rgis_layer_id::LayerId);
+
+/// After the geometry of a single feature of a `Layer` was edited, so only what depends on that
+/// feature needs updating
+#[derive(Event, Debug)]
+pub struct FeatureGeometryUpdatedEvent(pub rgis_layer_id::LayerId, pub geo_features::FeatureId);
+
+/// After a `Layer`'s selected features changed
+#[derive(Event, Debug)]
+pub struct SelectionUpdatedEvent(pub rgis_layer_id::LayerId);
 
 #[derive(Event, Debug)]
 pub struct LayerBecameHiddenEvent(pub rgis_layer_id::LayerId);
@@ -53,9 +164,31 @@ pub enum LayerColorUpdatedEvent {
     Stroke(rgis_layer_id::LayerId),
 }
 
+/// Show only the `Layer`'s features matching the filter, or all of them when it's `None`
+#[derive(Event)]
+pub struct UpdateLayerFilterEvent(
+    pub rgis_layer_id::LayerId,
+    pub Option<geo_features::filter::Filter>,
+);
+
+/// After a `Layer`'s filter is changed
+#[derive(Event, Debug)]
+pub struct LayerFilterUpdatedEvent(pub rgis_layer_id::LayerId);
+
 #[derive(Event)]
 pub struct DeleteLayerEvent(pub rgis_layer_id::LayerId);
 
+#[derive(Event)]
+pub struct RenameLayerEvent(pub rgis_layer_id::LayerId, pub String);
+
+/// Revert the last change to the layers or the target CRS
+#[derive(Default, Event)]
+pub struct UndoEvent;
+
+/// Apply the last undone change again
+#[derive(Default, Event)]
+pub struct RedoEvent;
+
 #[derive(Event)]
 pub struct DespawnMeshesEvent(pub rgis_layer_id::LayerId);
 
@@ -69,6 +202,7 @@ impl From<rgis_layer_id::LayerId> for MeshesSpawnedEvent {
     }
 }
 
+#[derive(Clone, Copy, Debug)]
 pub enum MoveDirection {
     Up,
     Down,
@@ -81,20 +215,21 @@ pub struct MoveLayerEvent(pub rgis_layer_id::LayerId, pub MoveDirection);
 pub struct LayerZIndexUpdatedEvent(pub rgis_layer_id::LayerId);
 
 #[derive(Event)]
-pub struct MapClickedEvent(pub ProjectedCoord);
-
-#[derive(Event)]
-pub struct FeaturesDeselectedEvent;
+pub struct MapClickedEvent(pub ProjectedCoord, pub SelectionMode);
 
 #[derive(Default, Event)]
 pub struct OpenChangeCrsWindow;
 
-#[derive(Event, Debug)]
+#[derive(Clone, Event, Debug)]
 pub enum LoadFileEvent {
     FromNetwork {
         name: String,
         url: String,
         crs_epsg_code: u16,
+        /// `None` to detect the format from the response's `Content-Type` or the URL's extension.
+        file_format: Option<geo_file_loader::FileFormat>,
+        /// Extra HTTP headers sent with the request, as `(name, value)` pairs.
+        headers: Vec<(String, String)>,
     },
     FromBytes {
         file_name: String,
@@ -102,6 +237,125 @@ pub enum LoadFileEvent {
         bytes: bytes::Bytes,
         crs_epsg_code: u16,
     },
+    /// A file on the local file system, read when the event is handled.
+    FromPath {
+        file_name: String,
+        path: std::path::PathBuf,
+        file_format: geo_file_loader::FileFormat,
+        crs_epsg_code: u16,
+    },
+    /// Every page of an OGC API – Features collection's `/items` endpoint.
+    FromOgcApiFeatures {
+        name: String,
+        items_url: String,
+        /// `[min_lon, min_lat, max_lon, max_lat]` in CRS84.
+        bbox: Option<[f64; 4]>,
+    },
+    /// Every feature of an ArcGIS REST FeatureServer or MapServer layer.
+    FromArcGisFeatureLayer { name: String, layer_url: String },
+    /// Every page of a WFS 2.0 feature type, fetched with `GetFeature`.
+    FromWfs {
+        name: String,
+        /// The service's `GetCapabilities` URL.
+        service_url: String,
+        type_name: String,
+        /// `None` for the service's default format, GML.
+        output_format: Option<String>,
+        crs_epsg_code: u16,
+        /// `[min_x, min_y, max_x, max_y]` in `crs_epsg_code`.
+        bbox: Option<[f64; 4]>,
+        /// Features per page, `None` if the service doesn't page its responses.
+        page_size: Option<u32>,
+    },
+}
+
+impl LoadFileEvent {
+    pub fn name(&self) -> &str {
+        match self {
+            LoadFileEvent::FromNetwork { name, .. } => name,
+            LoadFileEvent::FromBytes { file_name, .. }
+            | LoadFileEvent::FromPath { file_name, .. } => file_name,
+            LoadFileEvent::FromOgcApiFeatures { name, .. }
+            | LoadFileEvent::FromArcGisFeatureLayer { name, .. }
+            | LoadFileEvent::FromWfs { name, .. } => name,
+        }
+    }
+
+    pub fn crs_epsg_code(&self) -> u16 {
+        match self {
+            LoadFileEvent::FromNetwork { crs_epsg_code, .. }
+            | LoadFileEvent::FromBytes { crs_epsg_code, .. }
+            | LoadFileEvent::FromPath { crs_epsg_code, .. }
+            | LoadFileEvent::FromWfs { crs_epsg_code, .. } => *crs_epsg_code,
+            LoadFileEvent::FromOgcApiFeatures { .. }
+            | LoadFileEvent::FromArcGisFeatureLayer { .. } => 4326,
+        }
+    }
+
+    /// Whether both events load the same dataset, ignoring the CRS they were loaded with.
+    pub fn is_same_source(&self, other: &LoadFileEvent) -> bool {
+        match (self, other) {
+            (
+                LoadFileEvent::FromNetwork { url: a, .. },
+                LoadFileEvent::FromNetwork { url: b, .. },
+            ) => a == b,
+            (
+                LoadFileEvent::FromBytes {
+                    file_name: a,
+                    file_format: a_format,
+                    ..
+                },
+                LoadFileEvent::FromBytes {
+                    file_name: b,
+                    file_format: b_format,
+                    ..
+                },
+            ) => a == b && a_format == b_format,
+            (
+                LoadFileEvent::FromPath {
+                    path: a,
+                    file_format: a_format,
+                    ..
+                },
+                LoadFileEvent::FromPath {
+                    path: b,
+                    file_format: b_format,
+                    ..
+                },
+            ) => a == b && a_format == b_format,
+            (
+                LoadFileEvent::FromOgcApiFeatures {
+                    items_url: a,
+                    bbox: a_bbox,
+                    ..
+                },
+                LoadFileEvent::FromOgcApiFeatures {
+                    items_url: b,
+                    bbox: b_bbox,
+                    ..
+                },
+            ) => a == b && a_bbox == b_bbox,
+            (
+                LoadFileEvent::FromArcGisFeatureLayer { layer_url: a, .. },
+                LoadFileEvent::FromArcGisFeatureLayer { layer_url: b, .. },
+            ) => a == b,
+            (
+                LoadFileEvent::FromWfs {
+                    service_url: a,
+                    type_name: a_type_name,
+                    bbox: a_bbox,
+                    ..
+                },
+                LoadFileEvent::FromWfs {
+                    service_url: b,
+                    type_name: b_type_name,
+                    bbox: b_bbox,
+                    ..
+                },
+            ) => a == b && a_type_name == b_type_name && a_bbox == b_bbox,
+            _ => false,
+        }
+    }
 }
 
 pub struct Plugin;
@@ -174,6 +428,7 @@ pub struct RenderMessageEvent(pub String);
 #[derive(Event)]
 pub struct RenderFeaturePropertiesEvent {
     pub layer_id: rgis_layer_id::LayerId,
+    pub feature_id: geo_features::FeatureId,
     pub properties: geo_features::Properties,
 }
 
@@ -184,6 +439,17 @@ pub struct CreateLayerEvent {
     pub source_crs_epsg_code: u16,
 }
 
+/// A page of features fetched from a service that pages its responses. The first page creates
+/// the `Layer` with `layer_id`, the following ones are added to it as they arrive.
+#[derive(Event)]
+pub struct FeaturesPageLoadedEvent {
+    pub layer_id: rgis_layer_id::LayerId,
+    pub feature_collection: geo_features::FeatureCollection<UnprojectedScalar>,
+    pub name: String,
+    pub source_crs_epsg_code: u16,
+    pub is_first_page: bool,
+}
+
 #[derive(Event)]
 pub struct LayerReprojectedEvent(pub rgis_layer_id::LayerId);
 
@@ -193,19 +459,89 @@ pub struct ShowAddLayerWindow;
 #[derive(Default, Event)]
 pub struct HideAddLayerWindow;
 
+#[derive(Default, Event)]
+pub struct ClearHttpCacheEvent;
+
+/// Reproject a layer's geometries to the target CRS again.
+#[derive(Event, Debug)]
+pub struct ReprojectLayerEvent(pub rgis_layer_id::LayerId);
+
+/// Fetch again the page of features that failed to load, adding it to the layer it was loading.
+#[derive(Event, Debug)]
+pub struct RetryPageEvent(pub rgis_layer_id::LayerId);
+
+/// How to start a failed job again.
+#[derive(Clone, Debug)]
+pub enum RetryJob {
+    LoadFile(LoadFileEvent),
+    /// Fetch the page of features the layer's loading stopped at.
+    LoadPage(rgis_layer_id::LayerId),
+    ReprojectLayer(rgis_layer_id::LayerId),
+}
+
+/// Something failed and the user should be told, e.g. on the web build where there's no
+/// console to read the logs from.
+#[derive(Clone, Event, Debug)]
+pub struct ErrorNotificationEvent {
+    /// What failed, e.g. "Could not load 'parcels.geojson'".
+    pub summary: String,
+    /// The layer the failure concerns, if it already exists.
+    pub layer_id: Option<rgis_layer_id::LayerId>,
+    /// The error followed by each of its sources.
+    pub details: Vec<String>,
+}
+
+impl ErrorNotificationEvent {
+    pub fn new(summary: impl Into<String>, error: &dyn std::error::Error) -> Self {
+        let mut details = vec![error.to_string()];
+        let mut source = error.source();
+        while let Some(error) = source {
+            let message = error.to_string();
+            // Wrapping errors often display their source as is
+            if details.last() != Some(&message) {
+                details.push(message);
+            }
+            source = error.source();
+        }
+        ErrorNotificationEvent {
+            summary: summary.into(),
+            layer_id: None,
+            details,
+        }
+    }
+
+    pub fn with_layer_id(mut self, layer_id: rgis_layer_id::LayerId) -> Self {
+        self.layer_id = Some(layer_id);
+        self
+    }
+}
+
+#[derive(Clone, Event, Debug)]
+pub struct JobFailedEvent {
+    pub name: String,
+    /// The error, formatted for display.
+    pub error: String,
+    pub retry: RetryJob,
+}
+
 impl bevy::app::Plugin for Plugin {
     fn build(&self, app: &mut App) {
         app.add_event::<LoadFileEvent>()
             .add_event::<CreateLayerEvent>()
             .add_event::<LayerCreatedEvent>()
+            .add_event::<FeaturesPageLoadedEvent>()
+            .add_event::<RetryPageEvent>()
             .add_event::<ToggleLayerVisibilityEvent>()
             .add_event::<LayerBecameHiddenEvent>()
             .add_event::<LayerBecameVisibleEvent>()
             .add_event::<PanCameraEvent>()
             .add_event::<ZoomCameraEvent>()
             .add_event::<CenterCameraEvent>()
+            .add_event::<CenterCameraOnFeatureEvent>()
             .add_event::<LayerColorUpdatedEvent>()
             .add_event::<UpdateLayerColorEvent>()
+            .add_event::<UpdateLayerFilterEvent>()
+            .add_event::<LayerFilterUpdatedEvent>()
             .add_event::<MoveLayerEvent>()
             .add_event::<LayerZIndexUpdatedEvent>()
             .add_event::<DeleteLayerEvent>()
@@ -220,8 +556,24 @@ impl bevy::app::Plugin for Plugin {
             .add_event::<HideAddLayerWindow>()
             .add_event::<LayerReprojectedEvent>()
             .add_event::<DespawnMeshesEvent>()
-            .add_event::<FeatureSelectedEvent>()
-            .add_event::<FeaturesDeselectedEvent>()
-            .add_event::<ShowManageLayerWindowEvent>();
+            .add_event::<UpdateSelectionEvent>()
+            .add_event::<SelectionUpdatedEvent>()
+            .add_event::<MapAreaSelectedEvent>()
+            .add_event::<FeatureDrawnEvent>()
+            .add_event::<AddFeatureEvent>()
+            .add_event::<EditLayerVerticesEvent>()
+            .add_event::<RenameLayerEvent>()
+            .add_event::<UndoEvent>()
+            .add_event::<RedoEvent>()
+            .add_event::<UpdateFeatureGeometryEvent>()
+            .add_event::<UpdateFeaturePropertiesEvent>()
+            .add_event::<LayerFeaturesUpdatedEvent>()
+            .add_event::<FeatureGeometryUpdatedEvent>()
+            .add_event::<ShowManageLayerWindowEvent>()
+            .add_event::<ShowAttributeTableWindowEvent>()
+            .add_event::<ClearHttpCacheEvent>()
+            .add_event::<ReprojectLayerEvent>()
+            .add_event::<JobFailedEvent>()
+            .add_event::<ErrorNotificationEvent>();
     }
 }
@@ -37,39 +37,100 @@ impl bevy_jobs::Job for LoadFileJob {
     }
 }
 
+/// Fetches a page of features for the layer with `layer_id`, which the first page creates.
+#[derive(Clone)]
+pub struct LayerPageJob<J> {
+    pub job: J,
+    pub layer_id: rgis_layer_id::LayerId,
+}
+
+pub struct LayerPageJobOutcome<J> {
+    pub feature_collection: FeatureCollection<geo_projected::UnprojectedScalar>,
+    pub layer_id: rgis_layer_id::LayerId,
+    pub is_first_page: bool,
+    pub next_job: Option<J>,
+}
+
+/// A page that failed to load, with the job fetching it again into the same layer.
+pub struct FailedPage<J> {
+    pub error: rgis_network::Error,
+    pub job: LayerPageJob<J>,
+}
+
+impl<J: rgis_network::PageJob + Clone> bevy_jobs::Job for LayerPageJob<J> {
+    type Outcome = Result<LayerPageJobOutcome<J>, FailedPage<J>>;
+    const JOB_TYPE: bevy_jobs::JobType = bevy_jobs::JobType::Io;
+
+    fn name(&self) -> String {
+        self.job.name()
+    }
+
+    fn perform(self, ctx: bevy_jobs::Context) -> bevy_jobs::AsyncReturn<Self::Outcome> {
+        Box::pin(async move {
+            let is_first_page = self.job.is_first_page();
+            let retry = self.clone();
+            let page = match self.job.perform(ctx).await {
+                Ok(page) => page,
+                Err(error) => return Err(FailedPage { error, job: retry }),
+            };
+            let features = page
+                .features
+                .into_iter()
+                .map(geo_file_laoder_feature_to_geo_features_feature)
+                .collect();
+            Ok(LayerPageJobOutcome {
+                feature_collection: FeatureCollection::from_features(features).wrap(),
+                layer_id: self.layer_id,
+                is_first_page,
+                next_job: page.next_job,
+            })
+        })
+    }
+}
+
+pub struct ReadFileJob {
+    pub path: std::path::PathBuf,
+}
+
+impl bevy_jobs::Job for ReadFileJob {
+    type Outcome = std::io::Result<bytes::Bytes>;
+    const JOB_TYPE: bevy_jobs::JobType = bevy_jobs::JobType::Io;
+
+    fn name(&self) -> String {
+        format!("Reading '{}'", self.path.display())
+    }
+
+    fn perform(self, _: bevy_jobs::Context) -> bevy_jobs::AsyncReturn<Self::Outcome> {
+        Box::pin(async move { std::fs::read(&self.path).map(bytes::Bytes::from) })
+    }
+}
+
+/// A job started by a `LoadFileEvent`, kept alongside its outcome so a failed job can be
+/// retried by sending the event again.
+pub struct RetryableJob<J> {
+    pub job: J,
+    pub source: rgis_events::LoadFileEvent,
+}
+
+impl<J: bevy_jobs::Job> bevy_jobs::Job for RetryableJob<J> {
+    type Outcome = (J::Outcome, rgis_events::LoadFileEvent);
+    const JOB_TYPE: bevy_jobs::JobType = J::JOB_TYPE;
+
+    fn name(&self) -> String {
+        self.job.name()
+    }
+
+    fn perform(self, ctx: bevy_jobs::Context) -> bevy_jobs::AsyncReturn<Self::Outcome> {
+        Box::pin(async move { (self.job.perform(ctx).await, self.source) })
+    }
+}
+
 fn geo_file_laoder_feature_to_geo_features_feature(
     feature: geo_file_loader::Feature,
 ) -> geo_features::Feature<f64> {
-    let geometry = feature.geometry;
-    let properties = feature
-        .properties
-        .into_iter()
-        .map(|(k, v)| {
-            (
-                k,
-                geo_file_loader_owned_column_value_to_geo_features_value(v),
-            )
-        })
-        .collect();
     geo_features::FeatureBuilder::new()
-        .with_geometry(geometry)
-        .with_properties(properties)
+        .with_geometry(feature.geometry)
+        .with_properties(feature.properties)
+        .with_source_id(feature.source_id)
         .build()
 }
-
-fn geo_file_loader_owned_column_value_to_geo_features_value(
-    value: geo_file_loader::OwnedColumnValue,
-) -> geo_features::Value {
-    match value {
-        geo_file_loader::OwnedColumnValue::String(s) => geo_features::Value::String(s),
-        geo_file_loader::OwnedColumnValue::Int(n) => geo_features::Value::Number(n.into()),
-        geo_file_loader::OwnedColumnValue::UInt(n) => geo_features::Value::Number(n.into()),
-        geo_file_loader::OwnedColumnValue::Long(n) => geo_features::Value::Number(n as f64),
-        geo_file_loader::OwnedColumnValue::ULong(n) => geo_features::Value::Number(n as f64),
-        geo_file_loader::OwnedColumnValue::Float(n) => geo_features::Value::Number(n.into()),
-        geo_file_loader::OwnedColumnValue::Double(n) => geo_features::Value::Number(n.into()),
-        geo_file_loader::OwnedColumnValue::Bool(b) => geo_features::Value::Boolean(b),
-        // TODO: fill in the other implementations here
-        _ => unimplemented!(),
-    }
-}
@@ -1,51 +1,370 @@
+use crate::jobs::{LayerPageJob, RetryableJob};
 use bevy::prelude::*;
+use std::collections::HashMap;
+
+/// Pages that failed to load, by the layer they were loading into, until they're retried.
+#[derive(Default, Resource)]
+struct FailedPages(HashMap<rgis_layer_id::LayerId, FailedPageJob>);
+
+enum FailedPageJob {
+    OgcApi(RetryableJob<LayerPageJob<rgis_network::OgcApiItemsJob>>),
+    Wfs(RetryableJob<LayerPageJob<rgis_network::WfsGetFeatureJob>>),
+}
+
+impl From<RetryableJob<LayerPageJob<rgis_network::OgcApiItemsJob>>> for FailedPageJob {
+    fn from(job: RetryableJob<LayerPageJob<rgis_network::OgcApiItemsJob>>) -> Self {
+        FailedPageJob::OgcApi(job)
+    }
+}
+
+impl From<RetryableJob<LayerPageJob<rgis_network::WfsGetFeatureJob>>> for FailedPageJob {
+    fn from(job: RetryableJob<LayerPageJob<rgis_network::WfsGetFeatureJob>>) -> Self {
+        FailedPageJob::Wfs(job)
+    }
+}
+
+#[derive(bevy::ecs::system::SystemParam)]
+struct FailureEvents<'w> {
+    job_failed_event_writer: EventWriter<'w, rgis_events::JobFailedEvent>,
+    error_notification_event_writer: EventWriter<'w, rgis_events::ErrorNotificationEvent>,
+}
+
+impl FailureEvents<'_> {
+    fn write(
+        &mut self,
+        notification: rgis_events::ErrorNotificationEvent,
+        source: rgis_events::LoadFileEvent,
+    ) {
+        self.write_with_retry(notification, rgis_events::RetryJob::LoadFile(source));
+    }
+
+    fn write_with_retry(
+        &mut self,
+        notification: rgis_events::ErrorNotificationEvent,
+        retry: rgis_events::RetryJob,
+    ) {
+        let error = notification.details.join(": ");
+        bevy::log::error!("{}: {}", notification.summary, error);
+        self.job_failed_event_writer
+            .write(rgis_events::JobFailedEvent {
+                name: notification.summary.clone(),
+                error,
+                retry,
+            });
+        self.error_notification_event_writer.write(notification);
+    }
+}
 
 fn handle_network_fetch_finished_jobs(
-    mut load_event_reader: ResMut<Events<rgis_events::LoadFileEvent>>,
     mut finished_jobs: bevy_jobs::FinishedJobs,
+    mut job_spawner: bevy_jobs::JobSpawner,
+    mut failure_events: FailureEvents,
+) {
+    while let Some((outcome, source)) =
+        finished_jobs.take_next::<RetryableJob<rgis_network::NetworkFetchJob>>()
+    {
+        handle_fetch_outcome(outcome, source, &mut job_spawner, &mut failure_events);
+    }
+    while let Some((outcome, source)) =
+        finished_jobs.take_next::<RetryableJob<rgis_network::ArcGisFeatureLayerJob>>()
+    {
+        handle_fetch_outcome(outcome, source, &mut job_spawner, &mut failure_events);
+    }
+}
+
+fn handle_page_finished_jobs(
+    mut finished_jobs: bevy_jobs::FinishedJobs,
+    mut job_spawner: bevy_jobs::JobSpawner,
+    mut features_page_loaded_event_writer: EventWriter<rgis_events::FeaturesPageLoadedEvent>,
+    layers: Res<rgis_layers::Layers>,
+    mut failed_pages: ResMut<FailedPages>,
+    mut failure_events: FailureEvents,
 ) {
-    while let Some(outcome) = finished_jobs.take_next::<rgis_network::NetworkFetchJob>() {
+    while let Some((outcome, source)) =
+        finished_jobs.take_next::<RetryableJob<LayerPageJob<rgis_network::OgcApiItemsJob>>>()
+    {
+        handle_page_outcome(
+            outcome,
+            source,
+            &mut job_spawner,
+            &mut features_page_loaded_event_writer,
+            &layers,
+            &mut failed_pages,
+            &mut failure_events,
+        );
+    }
+    while let Some((outcome, source)) =
+        finished_jobs.take_next::<RetryableJob<LayerPageJob<rgis_network::WfsGetFeatureJob>>>()
+    {
+        handle_page_outcome(
+            outcome,
+            source,
+            &mut job_spawner,
+            &mut features_page_loaded_event_writer,
+            &layers,
+            &mut failed_pages,
+            &mut failure_events,
+        );
+    }
+}
+
+fn handle_page_outcome<J: rgis_network::PageJob + Clone>(
+    outcome: Result<crate::jobs::LayerPageJobOutcome<J>, crate::jobs::FailedPage<J>>,
+    source: rgis_events::LoadFileEvent,
+    job_spawner: &mut bevy_jobs::JobSpawner,
+    features_page_loaded_event_writer: &mut EventWriter<rgis_events::FeaturesPageLoadedEvent>,
+    layers: &rgis_layers::Layers,
+    failed_pages: &mut FailedPages,
+    failure_events: &mut FailureEvents,
+) where
+    RetryableJob<LayerPageJob<J>>: Into<FailedPageJob>,
+{
+    let outcome = match outcome {
+        Ok(outcome) => outcome,
+        Err(crate::jobs::FailedPage { error, job }) => {
+            let layer_id = job.layer_id;
+            let mut notification = rgis_events::ErrorNotificationEvent::new(
+                format!("Could not fetch '{}'", source.name()),
+                &error,
+            );
+            if layers.get(layer_id).is_some() {
+                notification = notification.with_layer_id(layer_id);
+            }
+            // Retry the page rather than the source, which would load the previous pages into
+            // a new layer
+            failed_pages
+                .0
+                .insert(layer_id, RetryableJob { job, source }.into());
+            failure_events
+                .write_with_retry(notification, rgis_events::RetryJob::LoadPage(layer_id));
+            return;
+        }
+    };
+    // Stop fetching once the layer was removed
+    if !outcome.is_first_page && layers.get(outcome.layer_id).is_none() {
+        return;
+    }
+    features_page_loaded_event_writer.write(rgis_events::FeaturesPageLoadedEvent {
+        layer_id: outcome.layer_id,
+        feature_collection: outcome.feature_collection,
+        name: source.name().to_owned(),
+        source_crs_epsg_code: source.crs_epsg_code(),
+        is_first_page: outcome.is_first_page,
+    });
+    if let Some(job) = outcome.next_job {
+        job_spawner.spawn(RetryableJob {
+            job: LayerPageJob {
+                job,
+                layer_id: outcome.layer_id,
+            },
+            source,
+        });
+    }
+}
+
+fn handle_fetch_outcome(
+    outcome: Result<rgis_network::FetchedFile, rgis_network::Error>,
+    source: rgis_events::LoadFileEvent,
+    job_spawner: &mut bevy_jobs::JobSpawner,
+    failure_events: &mut FailureEvents,
+) {
+    let notification = match outcome {
+        Ok(fetched) => match spawn_load_fetched_file_job(fetched, source.clone(), job_spawner) {
+            Ok(()) => return,
+            Err(notification) => notification,
+        },
+        Err(e) => rgis_events::ErrorNotificationEvent::new(
+            format!("Could not fetch '{}'", source.name()),
+            &e,
+        ),
+    };
+    failure_events.write(notification, source);
+}
+
+fn handle_read_file_finished_jobs(
+    mut finished_jobs: bevy_jobs::FinishedJobs,
+    mut job_spawner: bevy_jobs::JobSpawner,
+    mut failure_events: FailureEvents,
+) {
+    while let Some((outcome, source)) =
+        finished_jobs.take_next::<RetryableJob<crate::jobs::ReadFileJob>>()
+    {
+        let rgis_events::LoadFileEvent::FromPath {
+            file_name,
+            file_format,
+            crs_epsg_code,
+            ..
+        } = &source
+        else {
+            continue;
+        };
         match outcome {
-            Ok(fetched) => {
-                load_event_reader.send(rgis_events::LoadFileEvent::FromBytes {
-                    file_format: geo_file_loader::FileFormat::GeoJson,
-                    bytes: fetched.bytes,
-                    file_name: fetched.name,
-                    crs_epsg_code: fetched.crs_epsg_code,
+            Ok(bytes) => {
+                job_spawner.spawn(RetryableJob {
+                    job: crate::jobs::LoadFileJob {
+                        file_format: *file_format,
+                        bytes,
+                        name: file_name.clone(),
+                        source_crs_epsg_code: *crs_epsg_code,
+                    },
+                    source,
                 });
             }
             Err(e) => {
-                bevy::log::error!("Could not fetch file: {:?}", e);
+                let notification = rgis_events::ErrorNotificationEvent::new(
+                    format!("Could not read '{}'", source.name()),
+                    &e,
+                );
+                failure_events.write(notification, source);
             }
         }
     }
 }
 
+fn spawn_load_fetched_file_job(
+    fetched: rgis_network::FetchedFile,
+    source: rgis_events::LoadFileEvent,
+    job_spawner: &mut bevy_jobs::JobSpawner,
+) -> Result<(), rgis_events::ErrorNotificationEvent> {
+    let file_format = fetched
+        .file_format
+        .or_else(|| {
+            fetched
+                .content_type
+                .as_deref()
+                .and_then(geo_file_loader::FileFormat::from_content_type)
+        })
+        .or_else(|| geo_file_loader::FileFormat::from_extension(&fetched.url))
+        .ok_or_else(|| rgis_events::ErrorNotificationEvent {
+            summary: format!("Could not load '{}'", fetched.name),
+            layer_id: None,
+            details: vec![format!(
+                "Could not determine the file format of '{}', select it explicitly",
+                fetched.url
+            )],
+        })?;
+    job_spawner.spawn(RetryableJob {
+        job: crate::jobs::LoadFileJob {
+            file_format,
+            bytes: fetched.bytes,
+            name: fetched.name,
+            source_crs_epsg_code: fetched.crs_epsg_code,
+        },
+        source,
+    });
+    Ok(())
+}
+
+fn handle_retry_page_events(
+    mut retry_page_events: ResMut<Events<rgis_events::RetryPageEvent>>,
+    mut failed_pages: ResMut<FailedPages>,
+    mut job_spawner: bevy_jobs::JobSpawner,
+) {
+    for rgis_events::RetryPageEvent(layer_id) in retry_page_events.drain() {
+        match failed_pages.0.remove(&layer_id) {
+            Some(FailedPageJob::OgcApi(job)) => job_spawner.spawn(job),
+            Some(FailedPageJob::Wfs(job)) => job_spawner.spawn(job),
+            None => continue,
+        };
+    }
+}
+
 fn handle_load_file_events(
     mut load_event_reader: ResMut<Events<rgis_events::LoadFileEvent>>,
     mut job_spawner: bevy_jobs::JobSpawner,
+    mut recent_sources: ResMut<rgis_settings::RecentSources>,
+    http_cache: Res<rgis_network::HttpCache>,
 ) {
     for event in load_event_reader.drain() {
+        recent_sources.push(event.clone());
+        let source = event.clone();
         match event {
             rgis_events::LoadFileEvent::FromNetwork {
                 url,
                 crs_epsg_code,
                 name,
-            } => job_spawner.spawn(rgis_network::NetworkFetchJob {
-                url,
-                crs_epsg_code,
-                name,
+                file_format,
+                headers,
+            } => job_spawner.spawn(RetryableJob {
+                job: rgis_network::NetworkFetchJob {
+                    url,
+                    crs_epsg_code,
+                    name,
+                    file_format,
+                    headers,
+                    cache: Some(http_cache.clone()),
+                },
+                source,
             }),
             rgis_events::LoadFileEvent::FromBytes {
                 file_name,
                 bytes,
                 file_format,
                 crs_epsg_code,
-            } => job_spawner.spawn(crate::jobs::LoadFileJob {
-                source_crs_epsg_code: crs_epsg_code,
-                name: file_name,
-                bytes,
-                file_format,
+            } => job_spawner.spawn(RetryableJob {
+                job: crate::jobs::LoadFileJob {
+                    source_crs_epsg_code: crs_epsg_code,
+                    name: file_name,
+                    bytes,
+                    file_format,
+                },
+                source,
+            }),
+            rgis_events::LoadFileEvent::FromPath { path, .. } => job_spawner.spawn(RetryableJob {
+                job: crate::jobs::ReadFileJob { path },
+                source,
+            }),
+            rgis_events::LoadFileEvent::FromOgcApiFeatures {
+                name,
+                items_url,
+                bbox,
+            } => job_spawner.spawn(RetryableJob {
+                job: LayerPageJob {
+                    job: rgis_network::OgcApiItemsJob {
+                        name,
+                        items_url,
+                        bbox,
+                        next_page: None,
+                        cache: Some(http_cache.clone()),
+                    },
+                    layer_id: rgis_layer_id::LayerId::new(),
+                },
+                source,
+            }),
+            rgis_events::LoadFileEvent::FromArcGisFeatureLayer { name, layer_url } => job_spawner
+                .spawn(RetryableJob {
+                    job: rgis_network::ArcGisFeatureLayerJob {
+                        name,
+                        layer_url,
+                        cache: Some(http_cache.clone()),
+                    },
+                    source,
+                }),
+            rgis_events::LoadFileEvent::FromWfs {
+                name,
+                service_url,
+                type_name,
+                output_format,
+                crs_epsg_code,
+                bbox,
+                page_size,
+            } => job_spawner.spawn(RetryableJob {
+                job: LayerPageJob {
+                    job: rgis_network::WfsGetFeatureJob {
+                        name,
+                        service_url,
+                        type_name,
+                        output_format,
+                        crs_epsg_code,
+                        bbox,
+                        page_size,
+                        start_index: 0,
+                        number_matched: None,
+                        cache: Some(http_cache.clone()),
+                    },
+                    layer_id: rgis_layer_id::LayerId::new(),
+                },
+                source,
             }),
         };
     }
@@ -54,8 +373,11 @@ fn handle_load_file_events(
 fn handle_load_file_job_finished_events(
     mut finished_jobs: bevy_jobs::FinishedJobs,
     mut create_layer_event_writer: EventWriter<rgis_events::CreateLayerEvent>,
+    mut failure_events: FailureEvents,
 ) {
-    while let Some(outcome) = finished_jobs.take_next::<crate::jobs::LoadFileJob>() {
+    while let Some((outcome, source)) =
+        finished_jobs.take_next::<RetryableJob<crate::jobs::LoadFileJob>>()
+    {
         match outcome {
             Ok(outcome) => {
                 create_layer_event_writer.write(rgis_events::CreateLayerEvent {
@@ -65,17 +387,24 @@ fn handle_load_file_job_finished_events(
                 });
             }
             Err(e) => {
-                bevy::log::error!("Encountered error when loading file: {:?}", e);
+                let notification = rgis_events::ErrorNotificationEvent::new(
+                    format!("Could not load '{}'", source.name()),
+                    &e,
+                );
+                failure_events.write(notification, source);
             }
         }
     }
 }
 
 pub fn configure(app: &mut App) {
-    app.add_systems(
+    app.init_resource::<FailedPages>().add_systems(
         Update,
         (
             handle_network_fetch_finished_jobs,
+            handle_page_finished_jobs,
+            handle_retry_page_events,
+            handle_read_file_finished_jobs,
             handle_load_file_events,
             handle_load_file_job_finished_events,
         ),
@@ -0,0 +1,550 @@
+use crate::{Operation, OperationEntry, Outcome};
+use geo::{Bearing, BoundingRect, Destination, Distance, MapCoords, Orient};
+use geo_projected::UnprojectedScalar;
+use i_overlay::mesh::{
+    outline::offset::OutlineOffset,
+    stroke::offset::StrokeOffset,
+    style::{LineCap, LineJoin, OutlineStyle, StrokeStyle},
+};
+use std::{error, f64::consts, mem};
+
+/// Sharpest corner a miter join is kept for, in radians. Sharper corners are beveled so they
+/// don't stick out far beyond the buffer distance.
+const MIN_MITER_ANGLE: f64 = consts::PI / 10.;
+
+/// Shape of the buffer around the corners of lines and polygons.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Join {
+    #[default]
+    Round,
+    Miter,
+    Bevel,
+}
+
+impl Join {
+    const ALL: [Join; 3] = [Join::Round, Join::Miter, Join::Bevel];
+
+    fn name(self) -> &'static str {
+        match self {
+            Join::Round => "Round",
+            Join::Miter => "Miter",
+            Join::Bevel => "Bevel",
+        }
+    }
+}
+
+/// Shape of the buffer around the ends of lines.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Cap {
+    #[default]
+    Round,
+    Flat,
+    Square,
+}
+
+impl Cap {
+    const ALL: [Cap; 3] = [Cap::Round, Cap::Flat, Cap::Square];
+
+    fn name(self) -> &'static str {
+        match self {
+            Cap::Round => "Round",
+            Cap::Flat => "Flat",
+            Cap::Square => "Square",
+        }
+    }
+}
+
+/// Area within a distance of the features, with the distance in a unit chosen by the user.
+pub struct Buffer {
+    crs_epsg_code: u16,
+    /// In `unit`
+    distance: f64,
+    unit: rgis_units::LengthUnit,
+    segments_per_quarter_circle: u32,
+    join: Join,
+    cap: Cap,
+    execute_pressed: bool,
+    feature_collection: geo_features::FeatureCollection<UnprojectedScalar>,
+}
+
+impl Default for Buffer {
+    fn default() -> Self {
+        Buffer {
+            crs_epsg_code: crate::geodesic::WGS_84_EPSG_CODE,
+            distance: 100.,
+            unit: rgis_units::LengthUnit::Meters,
+            segments_per_quarter_circle: 8,
+            join: Join::default(),
+            cap: Cap::default(),
+            execute_pressed: false,
+            feature_collection: Default::default(),
+        }
+    }
+}
+
+impl OperationEntry for Buffer {
+    const ALLOWED_GEOM_TYPES: geo_geom_type::GeomType = geo_geom_type::GeomType::all();
+    const NAME: &'static str = "Buffer";
+
+    fn build() -> Box<dyn Operation + Send + Sync> {
+        Box::<Buffer>::default()
+    }
+}
+
+impl Operation for Buffer {
+    fn set_crs_epsg_code(&mut self, crs_epsg_code: u16) {
+        self.crs_epsg_code = crs_epsg_code;
+    }
+
+    fn next_action(&self) -> crate::Action {
+        if self.execute_pressed {
+            crate::Action::Perform
+        } else {
+            crate::Action::RenderUi
+        }
+    }
+
+    fn ui(
+        &mut self,
+        ui: &mut bevy_egui::egui::Ui,
+        _feature_collection: &geo_features::FeatureCollection<UnprojectedScalar>,
+    ) {
+        bevy_egui::egui::Grid::new("buffer-grid")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Distance:");
+                ui.horizontal(|ui| {
+                    ui.add(
+                        bevy_egui::egui::DragValue::new(&mut self.distance)
+                            .range(0. ..=f64::MAX)
+                            .speed(1.),
+                    );
+                    bevy_egui::egui::ComboBox::from_id_salt("buffer-unit")
+                        .selected_text(self.unit.symbol())
+                        .show_ui(ui, |ui| {
+                            for unit in rgis_units::LengthUnit::ALL {
+                                ui.selectable_value(&mut self.unit, unit, unit.symbol());
+                            }
+                        });
+                });
+                ui.end_row();
+
+                ui.label("Segments per quarter circle:");
+                ui.add(
+                    bevy_egui::egui::DragValue::new(&mut self.segments_per_quarter_circle)
+                        .range(2..=32),
+                );
+                ui.end_row();
+
+                ui.label("Join:");
+                bevy_egui::egui::ComboBox::from_id_salt("buffer-join")
+                    .selected_text(self.join.name())
+                    .show_ui(ui, |ui| {
+                        for join in Join::ALL {
+                            ui.selectable_value(&mut self.join, join, join.name());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Cap:");
+                bevy_egui::egui::ComboBox::from_id_salt("buffer-cap")
+                    .selected_text(self.cap.name())
+                    .show_ui(ui, |ui| {
+                        for cap in Cap::ALL {
+                            ui.selectable_value(&mut self.cap, cap, cap.name());
+                        }
+                    });
+                ui.end_row();
+            });
+        if ui
+            .add_enabled(self.distance > 0., bevy_egui::egui::Button::new("Execute"))
+            .clicked()
+        {
+            self.execute_pressed = true;
+        }
+    }
+
+    fn visit_feature_collection(
+        &mut self,
+        feature_collection: &geo_features::FeatureCollection<UnprojectedScalar>,
+    ) {
+        self.feature_collection = feature_collection.clone();
+    }
+
+    fn finalize(&mut self) -> Result<Outcome, Box<dyn error::Error>> {
+        let feature_collection = mem::take(&mut self.feature_collection);
+        let style = Style {
+            distance: self.unit.to_meters(self.distance),
+            segments_per_quarter_circle: self.segments_per_quarter_circle,
+            join: self.join,
+            cap: self.cap,
+        };
+        // Projected CRSs aren't necessarily in meters nor true to scale, like Web Mercator, so
+        // features are buffered in a projection local to each of them
+        let transformers = if self.crs_epsg_code == crate::geodesic::WGS_84_EPSG_CODE {
+            None
+        } else {
+            Some((
+                geo_geodesy::Transformer::setup(
+                    self.crs_epsg_code,
+                    crate::geodesic::WGS_84_EPSG_CODE,
+                )?,
+                geo_geodesy::Transformer::setup(
+                    crate::geodesic::WGS_84_EPSG_CODE,
+                    self.crs_epsg_code,
+                )?,
+            ))
+        };
+
+        let mut features = Vec::with_capacity(feature_collection.features().len());
+        for feature in feature_collection {
+            let Some(geometry) = feature.geometry else {
+                continue;
+            };
+            let mut geometry = geometry.map_coords(|coord| geo::Coord {
+                x: coord.x.0,
+                y: coord.y.0,
+            });
+            if let Some((ref to_wgs_84, _)) = transformers {
+                to_wgs_84.transform(&mut geometry)?;
+            }
+            let mut buffered = geo::Geometry::MultiPolygon(buffer_geographic(&geometry, &style));
+            if let Some((_, ref from_wgs_84)) = transformers {
+                from_wgs_84.transform(&mut buffered)?;
+            }
+            features.push(
+                geo_features::FeatureBuilder::new()
+                    .with_geometry(buffered.map_coords(|coord| geo::Coord {
+                        x: num_t::Num::new(coord.x),
+                        y: num_t::Num::new(coord.y),
+                    }))
+                    .with_properties(feature.properties)
+                    .build(),
+            );
+        }
+        Ok(Outcome::FeatureCollection(
+            geo_features::FeatureCollection::from_features(features),
+        ))
+    }
+}
+
+struct Style {
+    /// In meters
+    distance: f64,
+    segments_per_quarter_circle: u32,
+    join: Join,
+    cap: Cap,
+}
+
+impl Style {
+    /// Angle between the segments approximating an arc, in radians.
+    fn arc_angle(&self) -> f64 {
+        consts::FRAC_PI_2 / f64::from(self.segments_per_quarter_circle.max(1))
+    }
+
+    fn line_join(&self) -> LineJoin<f64> {
+        match self.join {
+            Join::Round => LineJoin::Round(self.arc_angle()),
+            Join::Miter => LineJoin::Miter(MIN_MITER_ANGLE),
+            Join::Bevel => LineJoin::Bevel,
+        }
+    }
+
+    fn line_cap(&self) -> LineCap<[f64; 2], f64> {
+        match self.cap {
+            Cap::Round => LineCap::Round(self.arc_angle()),
+            Cap::Flat => LineCap::Butt,
+            Cap::Square => LineCap::Square,
+        }
+    }
+}
+
+/// Azimuthal equidistant projection centered on a point, in which distances and bearings from
+/// the center are the geodesic ones on the WGS 84 ellipsoid, in meters.
+struct LocalProjection {
+    center: geo::Point<f64>,
+}
+
+impl LocalProjection {
+    fn project(&self, coord: geo::Coord<f64>) -> geo::Coord<f64> {
+        let point = geo::Point::from(coord);
+        let distance = geo::Geodesic.distance(self.center, point);
+        let bearing = geo::Geodesic.bearing(self.center, point).to_radians();
+        geo::Coord {
+            x: distance * bearing.sin(),
+            y: distance * bearing.cos(),
+        }
+    }
+
+    fn unproject(&self, coord: geo::Coord<f64>) -> geo::Coord<f64> {
+        let bearing = coord.x.atan2(coord.y).to_degrees();
+        geo::Geodesic
+            .destination(self.center, bearing, coord.x.hypot(coord.y))
+            .0
+    }
+}
+
+/// Buffers a geometry in geographic coordinates, in a projection centered on it.
+fn buffer_geographic(geometry: &geo::Geometry<f64>, style: &Style) -> geo::MultiPolygon<f64> {
+    let Some(rect) = geometry.bounding_rect() else {
+        return geo::MultiPolygon(vec![]);
+    };
+    let projection = LocalProjection {
+        center: rect.center().into(),
+    };
+    buffer(
+        &geometry.map_coords(|coord| projection.project(coord)),
+        style,
+    )
+    .map_coords(|coord| projection.unproject(coord))
+}
+
+/// Buffers a geometry whose coordinates are in meters.
+fn buffer(geometry: &geo::Geometry<f64>, style: &Style) -> geo::MultiPolygon<f64> {
+    match geometry {
+        geo::Geometry::Point(point) => geo::MultiPolygon(vec![circle(point.0, style)]),
+        geo::Geometry::MultiPoint(multi_point) => {
+            let circles = multi_point
+                .iter()
+                .map(|point| circle(point.0, style))
+                .collect::<Vec<_>>();
+            geo::unary_union(&circles)
+        }
+        geo::Geometry::Line(line) => stroke(vec![vec![line.start, line.end]], style),
+        geo::Geometry::LineString(line_string) => stroke(vec![line_string.0.clone()], style),
+        geo::Geometry::MultiLineString(multi_line_string) => stroke(
+            multi_line_string
+                .iter()
+                .map(|line_string| line_string.0.clone())
+                .collect(),
+            style,
+        ),
+        geo::Geometry::Polygon(polygon) => outline(vec![polygon.clone()], style),
+        geo::Geometry::MultiPolygon(multi_polygon) => outline(multi_polygon.0.clone(), style),
+        geo::Geometry::Rect(rect) => outline(vec![rect.to_polygon()], style),
+        geo::Geometry::Triangle(triangle) => outline(vec![triangle.to_polygon()], style),
+        geo::Geometry::GeometryCollection(geometry_collection) => {
+            let buffered = geometry_collection
+                .iter()
+                .map(|geometry| buffer(geometry, style))
+                .collect::<Vec<_>>();
+            geo::unary_union(&buffered)
+        }
+    }
+}
+
+fn circle(center: geo::Coord<f64>, style: &Style) -> geo::Polygon<f64> {
+    let count = 4 * style.segments_per_quarter_circle.max(1);
+    let angle = style.arc_angle();
+    geo::Polygon::new(
+        (0..count)
+            .map(|i| {
+                let (sin, cos) = (f64::from(i) * angle).sin_cos();
+                geo::Coord {
+                    x: center.x + style.distance * cos,
+                    y: center.y + style.distance * sin,
+                }
+            })
+            .collect(),
+        vec![],
+    )
+}
+
+fn stroke(paths: Vec<Vec<geo::Coord<f64>>>, style: &Style) -> geo::MultiPolygon<f64> {
+    // Paths without any length are buffered like points
+    let (paths, points): (Vec<_>, Vec<_>) = paths
+        .into_iter()
+        .filter_map(|path| path.first().copied().map(|first| (first, path)))
+        .partition(|(first, path)| path.iter().any(|coord| coord != first));
+    let paths = paths
+        .into_iter()
+        .map(|(_, path)| path.into_iter().map(|coord| [coord.x, coord.y]).collect())
+        .collect::<Vec<Vec<_>>>();
+    // i_overlay can't stroke an empty set of paths
+    let stroked = if paths.is_empty() {
+        geo::MultiPolygon(vec![])
+    } else {
+        from_shapes(
+            paths.stroke(
+                StrokeStyle::new(2. * style.distance)
+                    .line_join(style.line_join())
+                    .start_cap(style.line_cap())
+                    .end_cap(style.line_cap()),
+                false,
+            ),
+        )
+    };
+    if points.is_empty() {
+        return stroked;
+    }
+    let mut polygons = stroked.0;
+    polygons.extend(points.into_iter().map(|(point, _)| circle(point, style)));
+    geo::unary_union(&polygons)
+}
+
+fn outline(polygons: Vec<geo::Polygon<f64>>, style: &Style) -> geo::MultiPolygon<f64> {
+    if polygons.is_empty() {
+        return geo::MultiPolygon(vec![]);
+    }
+    let shapes = polygons
+        .into_iter()
+        .map(|polygon| {
+            // i_overlay wants exteriors clockwise and interiors counterclockwise
+            let polygon = polygon.orient(geo::orient::Direction::Reversed);
+            let (exterior, interiors) = polygon.into_inner();
+            std::iter::once(exterior)
+                .chain(interiors)
+                .map(|ring| {
+                    // Rings are closed implicitly
+                    let mut coords = ring.0;
+                    coords.pop();
+                    coords
+                        .into_iter()
+                        .map(|coord| [coord.x, coord.y])
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    from_shapes(shapes.outline(OutlineStyle::new(style.distance).line_join(style.line_join())))
+}
+
+fn from_shapes(shapes: Vec<Vec<Vec<[f64; 2]>>>) -> geo::MultiPolygon<f64> {
+    geo::MultiPolygon(
+        shapes
+            .into_iter()
+            .map(|shape| {
+                let mut rings = shape.into_iter().map(|path| {
+                    // Back to exteriors counterclockwise and interiors clockwise
+                    let mut line_string = geo::LineString::from(path);
+                    line_string.close();
+                    line_string.0.reverse();
+                    line_string
+                });
+                let exterior = rings.next().unwrap_or_else(|| geo::LineString::new(vec![]));
+                geo::Polygon::new(exterior, rings.collect())
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::{Area, Winding};
+
+    fn style(join: Join, cap: Cap) -> Style {
+        Style {
+            distance: 1.,
+            segments_per_quarter_circle: 8,
+            join,
+            cap,
+        }
+    }
+
+    #[test]
+    fn test_buffer_point() {
+        let buffered = buffer(
+            &geo::Point::new(5., 5.).into(),
+            &style(Join::Round, Cap::Round),
+        );
+        // A 32-gon is within 1% of the circle's area
+        assert!((buffered.unsigned_area() - consts::PI).abs() < 0.01 * consts::PI);
+    }
+
+    #[test]
+    fn test_buffer_line_caps() {
+        let line: geo::Geometry<f64> = geo::LineString::from(vec![(0., 0.), (10., 0.)]).into();
+        let flat = buffer(&line, &style(Join::Round, Cap::Flat));
+        assert!((flat.unsigned_area() - 20.).abs() < 1e-6);
+        let square = buffer(&line, &style(Join::Round, Cap::Square));
+        assert!((square.unsigned_area() - 24.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_buffer_polygon_joins() {
+        let square: geo::Geometry<f64> =
+            geo::Rect::new(geo::coord! { x: 0., y: 0. }, geo::coord! { x: 10., y: 10. }).into();
+        let miter = buffer(&square, &style(Join::Miter, Cap::Round));
+        assert!((miter.unsigned_area() - 144.).abs() < 1e-6);
+        let bevel = buffer(&square, &style(Join::Bevel, Cap::Round));
+        assert!((bevel.unsigned_area() - 142.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_ring_orientation() {
+        let polygon: geo::Geometry<f64> = geo::Polygon::new(
+            geo::LineString::from(vec![(0., 0.), (10., 0.), (10., 10.), (0., 10.)]),
+            vec![geo::LineString::from(vec![
+                (3., 3.),
+                (7., 3.),
+                (7., 7.),
+                (3., 7.),
+            ])],
+        )
+        .into();
+        let line: geo::Geometry<f64> = geo::LineString::from(vec![(0., 0.), (10., 0.)]).into();
+        for geometry in [&polygon, &line] {
+            for polygon in buffer(geometry, &style(Join::Round, Cap::Round)) {
+                assert!(polygon.exterior().is_ccw());
+                assert!(polygon.interiors().iter().all(|interior| interior.is_cw()));
+            }
+        }
+        // The hole shrinks but remains
+        let buffered = buffer(&polygon, &style(Join::Miter, Cap::Round));
+        assert!(matches!(
+            buffered.0.first(),
+            Some(polygon) if polygon.interiors().len() == 1
+        ));
+    }
+
+    #[test]
+    fn test_finalize_in_web_mercator() {
+        // Web Mercator stretches distances about twice at 60° of latitude
+        let y = 6_378_137. * (consts::FRAC_PI_4 + 60_f64.to_radians() / 2.).tan().ln();
+        let mut operation = Buffer {
+            crs_epsg_code: 3857,
+            distance: 1.,
+            unit: rgis_units::LengthUnit::Kilometers,
+            ..Default::default()
+        };
+        operation.visit_feature_collection(&geo_features::FeatureCollection::from_geometry(
+            geo::Point::new(num_t::Num::new(0.), num_t::Num::new(y)).into(),
+        ));
+        let area = match operation.finalize() {
+            Ok(Outcome::FeatureCollection(feature_collection)) => feature_collection
+                .features()
+                .first()
+                .and_then(|feature| feature.geometry.as_ref())
+                .map(|geometry| {
+                    geometry
+                        .map_coords(|coord| geo::Coord {
+                            x: coord.x.0,
+                            y: coord.y.0,
+                        })
+                        .unsigned_area()
+                }),
+            _ => None,
+        };
+        // About four times the area of the 32-gon around a 1 km circle on the ground
+        assert!(matches!(area, Some(area) if (area / (consts::PI * 1e6) - 3.96).abs() < 0.05));
+    }
+
+    #[test]
+    fn test_local_projection() {
+        let projection = LocalProjection {
+            center: geo::Point::new(10., 50.),
+        };
+        let coord = geo::coord! { x: 10.01, y: 50.02 };
+        let projected = projection.project(coord);
+        assert!(
+            (projected.x.hypot(projected.y)
+                - geo::Geodesic.distance(projection.center, coord.into()))
+            .abs()
+                < 1e-6
+        );
+        let unprojected = projection.unproject(projected);
+        assert!((unprojected.x - coord.x).abs() < 1e-9);
+        assert!((unprojected.y - coord.y).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,116 @@
+use geo::{GeodesicArea, Length, MapCoords};
+use geo_projected::UnprojectedScalar;
+
+/// WGS 84, the geographic CRS geodesic measurements are made in.
+pub(crate) const WGS_84_EPSG_CODE: u16 = 4326;
+
+/// Measures each feature's geometry on the WGS 84 ellipsoid, converting it to geographic
+/// coordinates first if `crs_epsg_code` is another CRS, and stores the result converted with
+/// `to_unit` in the property `property_name`.
+///
+/// Returns the sum of the unconverted measurements.
+pub(crate) fn measure_features(
+    feature_collection: &mut geo_features::FeatureCollection<UnprojectedScalar>,
+    crs_epsg_code: u16,
+    property_name: &str,
+    measure: impl Fn(&geo::Geometry<f64>) -> f64,
+    to_unit: impl Fn(f64) -> f64,
+) -> Result<f64, geo_geodesy::Error> {
+    let transformer = if crs_epsg_code == WGS_84_EPSG_CODE {
+        None
+    } else {
+        Some(geo_geodesy::Transformer::setup(
+            crs_epsg_code,
+            WGS_84_EPSG_CODE,
+        )?)
+    };
+
+    let mut total = 0.;
+    for feature in feature_collection.iter_mut() {
+        let Some(ref geometry) = feature.geometry else {
+            continue;
+        };
+        let mut geometry = geometry.map_coords(|coord| geo::Coord {
+            x: coord.x.0,
+            y: coord.y.0,
+        });
+        if let Some(ref transformer) = transformer {
+            transformer.transform(&mut geometry)?;
+        }
+        let measurement = measure(&geometry);
+        total += measurement;
+        feature.properties.insert(
+            property_name.to_owned(),
+            geo_features::Value::Number(to_unit(measurement)),
+        );
+    }
+    Ok(total)
+}
+
+/// Geodesic length in meters of the lines in `geometry`, or of the boundary of its polygons.
+pub(crate) fn length(geometry: &geo::Geometry<f64>) -> f64 {
+    match geometry {
+        geo::Geometry::Line(line) => geo::Geodesic.length(line),
+        geo::Geometry::LineString(line_string) => geo::Geodesic.length(line_string),
+        geo::Geometry::MultiLineString(multi_line_string) => {
+            geo::Geodesic.length(multi_line_string)
+        }
+        geo::Geometry::GeometryCollection(geometry_collection) => {
+            geometry_collection.iter().map(length).sum()
+        }
+        _ => geometry.geodesic_perimeter(),
+    }
+}
+
+/// Geodesic area in square meters of the polygons in `geometry`.
+pub(crate) fn area(geometry: &geo::Geometry<f64>) -> f64 {
+    geometry.geodesic_area_unsigned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feature_collection(
+        geometry: geo::Geometry<f64>,
+    ) -> geo_features::FeatureCollection<UnprojectedScalar> {
+        geo_features::FeatureCollection::from_geometry(geometry.map_coords(|coord| geo::Coord {
+            x: num_t::Num::new(coord.x),
+            y: num_t::Num::new(coord.y),
+        }))
+    }
+
+    #[test]
+    fn test_measure_features() {
+        // One degree square on the equator
+        let mut feature_collection = feature_collection(
+            geo::Rect::new(geo::coord! { x: 0., y: 0. }, geo::coord! { x: 1., y: 1. }).into(),
+        );
+        let total = measure_features(
+            &mut feature_collection,
+            WGS_84_EPSG_CODE,
+            "area_km2",
+            area,
+            |square_meters| square_meters / 1_000_000.,
+        );
+        assert!(matches!(total, Ok(total) if (total / 1e9 - 12.308).abs() < 0.001));
+        assert!(matches!(
+            feature_collection.features().first().and_then(|feature| feature.properties.get("area_km2")),
+            Some(geo_features::Value::Number(area)) if (area - 12_308.).abs() < 1.
+        ));
+    }
+
+    #[test]
+    fn test_length() {
+        let line_string: geo::Geometry<f64> =
+            geo::LineString::from(vec![(0., 0.), (1., 0.)]).into();
+        assert!((length(&line_string) - 111_319.49).abs() < 0.01);
+
+        let polygon: geo::Geometry<f64> =
+            geo::Rect::new(geo::coord! { x: 0., y: 0. }, geo::coord! { x: 1., y: 1. }).into();
+        assert!((length(&polygon) - 443_770.92).abs() < 0.01);
+
+        let point: geo::Geometry<f64> = geo::Point::new(0., 0.).into();
+        assert_eq!(length(&point), 0.);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{Operation, OperationEntry, Outcome};
+use geo_projected::UnprojectedScalar;
+use std::{error, mem};
+
+pub struct GeodesicArea {
+    crs_epsg_code: u16,
+    unit: rgis_units::AreaUnit,
+    execute_pressed: bool,
+    feature_collection: geo_features::FeatureCollection<UnprojectedScalar>,
+}
+
+impl Default for GeodesicArea {
+    fn default() -> Self {
+        GeodesicArea {
+            crs_epsg_code: crate::geodesic::WGS_84_EPSG_CODE,
+            unit: rgis_units::AreaUnit::Hectares,
+            execute_pressed: false,
+            feature_collection: Default::default(),
+        }
+    }
+}
+
+impl OperationEntry for GeodesicArea {
+    const ALLOWED_GEOM_TYPES: geo_geom_type::GeomType = geo_geom_type::GeomType::from_bits_truncate(
+        geo_geom_type::GeomType::POLYGON.bits()
+            | geo_geom_type::GeomType::MULTI_POLYGON.bits()
+            | geo_geom_type::GeomType::RECT.bits()
+            | geo_geom_type::GeomType::TRIANGLE.bits(),
+    );
+    const NAME: &'static str = "Area (geodesic)";
+
+    fn build() -> Box<dyn Operation + Send + Sync> {
+        Box::<GeodesicArea>::default()
+    }
+}
+
+impl Operation for GeodesicArea {
+    fn set_crs_epsg_code(&mut self, crs_epsg_code: u16) {
+        self.crs_epsg_code = crs_epsg_code;
+    }
+
+    fn next_action(&self) -> crate::Action {
+        if self.execute_pressed {
+            crate::Action::Perform
+        } else {
+            crate::Action::RenderUi
+        }
+    }
+
+    fn ui(
+        &mut self,
+        ui: &mut bevy_egui::egui::Ui,
+        _feature_collection: &geo_features::FeatureCollection<UnprojectedScalar>,
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("Unit:");
+            bevy_egui::egui::ComboBox::from_id_salt("geodesic-area-unit")
+                .selected_text(self.unit.symbol())
+                .show_ui(ui, |ui| {
+                    for unit in rgis_units::AreaUnit::ALL {
+                        ui.selectable_value(&mut self.unit, unit, unit.symbol());
+                    }
+                });
+        });
+        if ui.button("Execute").clicked() {
+            self.execute_pressed = true;
+        }
+    }
+
+    fn visit_feature_collection(
+        &mut self,
+        feature_collection: &geo_features::FeatureCollection<UnprojectedScalar>,
+    ) {
+        self.feature_collection = feature_collection.clone();
+    }
+
+    fn finalize(&mut self) -> Result<Outcome, Box<dyn error::Error>> {
+        let mut feature_collection = mem::take(&mut self.feature_collection);
+        let unit = self.unit;
+        let total_area = crate::geodesic::measure_features(
+            &mut feature_collection,
+            self.crs_epsg_code,
+            &format!("geodesic_area_{}", unit.identifier()),
+            crate::geodesic::area,
+            |square_meters| unit.from_square_meters(square_meters),
+        )?;
+        Ok(Outcome::FeatureProperties {
+            feature_collection,
+            summary: format!("Total area: {}", unit.format(total_area)),
+        })
+    }
+}
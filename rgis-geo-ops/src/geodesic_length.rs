@@ -0,0 +1,96 @@
+use crate::{Operation, OperationEntry, Outcome};
+use geo_projected::UnprojectedScalar;
+use std::{error, mem};
+
+/// Length of lines, and perimeter of polygons.
+pub struct GeodesicLength {
+    crs_epsg_code: u16,
+    unit: rgis_units::LengthUnit,
+    execute_pressed: bool,
+    feature_collection: geo_features::FeatureCollection<UnprojectedScalar>,
+}
+
+impl Default for GeodesicLength {
+    fn default() -> Self {
+        GeodesicLength {
+            crs_epsg_code: crate::geodesic::WGS_84_EPSG_CODE,
+            unit: rgis_units::LengthUnit::Kilometers,
+            execute_pressed: false,
+            feature_collection: Default::default(),
+        }
+    }
+}
+
+impl OperationEntry for GeodesicLength {
+    const ALLOWED_GEOM_TYPES: geo_geom_type::GeomType = geo_geom_type::GeomType::from_bits_truncate(
+        geo_geom_type::GeomType::LINE.bits()
+            | geo_geom_type::GeomType::LINE_STRING.bits()
+            | geo_geom_type::GeomType::MULTI_LINE_STRING.bits()
+            | geo_geom_type::GeomType::POLYGON.bits()
+            | geo_geom_type::GeomType::MULTI_POLYGON.bits()
+            | geo_geom_type::GeomType::RECT.bits()
+            | geo_geom_type::GeomType::TRIANGLE.bits(),
+    );
+    const NAME: &'static str = "Length / perimeter (geodesic)";
+
+    fn build() -> Box<dyn Operation + Send + Sync> {
+        Box::<GeodesicLength>::default()
+    }
+}
+
+impl Operation for GeodesicLength {
+    fn set_crs_epsg_code(&mut self, crs_epsg_code: u16) {
+        self.crs_epsg_code = crs_epsg_code;
+    }
+
+    fn next_action(&self) -> crate::Action {
+        if self.execute_pressed {
+            crate::Action::Perform
+        } else {
+            crate::Action::RenderUi
+        }
+    }
+
+    fn ui(
+        &mut self,
+        ui: &mut bevy_egui::egui::Ui,
+        _feature_collection: &geo_features::FeatureCollection<UnprojectedScalar>,
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("Unit:");
+            bevy_egui::egui::ComboBox::from_id_salt("geodesic-length-unit")
+                .selected_text(self.unit.symbol())
+                .show_ui(ui, |ui| {
+                    for unit in rgis_units::LengthUnit::ALL {
+                        ui.selectable_value(&mut self.unit, unit, unit.symbol());
+                    }
+                });
+        });
+        if ui.button("Execute").clicked() {
+            self.execute_pressed = true;
+        }
+    }
+
+    fn visit_feature_collection(
+        &mut self,
+        feature_collection: &geo_features::FeatureCollection<UnprojectedScalar>,
+    ) {
+        self.feature_collection = feature_collection.clone();
+    }
+
+    fn finalize(&mut self) -> Result<Outcome, Box<dyn error::Error>> {
+        let mut feature_collection = mem::take(&mut self.feature_collection);
+        let unit = self.unit;
+        let total_length = crate::geodesic::measure_features(
+            &mut feature_collection,
+            self.crs_epsg_code,
+            &format!("geodesic_length_{}", unit.symbol()),
+            crate::geodesic::length,
+            |meters| unit.from_meters(meters),
+        )?;
+        Ok(Outcome::FeatureProperties {
+            feature_collection,
+            summary: format!("Total length: {}", unit.format(total_length)),
+        })
+    }
+}
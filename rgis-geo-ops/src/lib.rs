@@ -9,12 +9,20 @@
 use geo_projected::UnprojectedScalar;
 use std::error;
 
-mod unsigned_area;
-pub use unsigned_area::UnsignedArea;
+mod buffer;
+pub use buffer::Buffer;
 
 mod convex_hull;
 pub use convex_hull::ConvexHull;
 
+mod geodesic;
+
+mod geodesic_area;
+pub use geodesic_area::GeodesicArea;
+
+mod geodesic_length;
+pub use geodesic_length::GeodesicLength;
+
 mod outliers;
 pub use outliers::Outliers;
 
@@ -33,6 +41,11 @@ pub use triangulate::Triangulate;
 pub enum Outcome {
     Text(String),
     FeatureCollection(geo_features::FeatureCollection<UnprojectedScalar>),
+    /// The features the operation was performed on, with new properties holding its results.
+    FeatureProperties {
+        feature_collection: geo_features::FeatureCollection<UnprojectedScalar>,
+        summary: String,
+    },
 }
 
 pub trait OperationEntry {
@@ -53,7 +66,7 @@ pub trait Operation {
         feature_collection: geo_features::FeatureCollection<UnprojectedScalar>,
     ) -> Result<Outcome, Box<dyn error::Error>> {
         self.visit_feature_collection(&feature_collection);
-        for feature in feature_collection.features.into_iter() {
+        for feature in feature_collection {
             self.visit_feature(&feature);
             if let Some(geometry) = feature.geometry {
                 self.visit_geometry(&geometry);
@@ -80,6 +93,9 @@ pub trait Operation {
 
     fn finalize(&mut self) -> Result<Outcome, Box<dyn error::Error>>;
 
+    /// Called before anything else with the EPSG code of the CRS the features are in.
+    fn set_crs_epsg_code(&mut self, _crs_epsg_code: u16) {}
+
     fn next_action(&self) -> Action {
         Action::Perform
     }
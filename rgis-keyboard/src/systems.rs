@@ -25,6 +25,36 @@ fn process_key_code_input_system(
     }
 }
 
+/// Ctrl+Z (or ⌘Z) undoes, and with Shift redoes.
+fn undo_redo_system(
+    keyboard_input: Res<bevy::input::ButtonInput<bevy::input::keyboard::KeyCode>>,
+    mut bevy_egui_ctx: bevy_egui::EguiContexts,
+    mut undo_event_writer: bevy::ecs::event::EventWriter<rgis_events::UndoEvent>,
+    mut redo_event_writer: bevy::ecs::event::EventWriter<rgis_events::RedoEvent>,
+) {
+    use bevy::input::keyboard::KeyCode;
+
+    if !keyboard_input.just_pressed(KeyCode::KeyZ)
+        || !keyboard_input.any_pressed([
+            KeyCode::ControlLeft,
+            KeyCode::ControlRight,
+            KeyCode::SuperLeft,
+            KeyCode::SuperRight,
+        ])
+    {
+        return;
+    }
+    // Text fields undo their own edits
+    if bevy_egui_ctx.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        redo_event_writer.write_default();
+    } else {
+        undo_event_writer.write_default();
+    }
+}
+
 pub fn configure(app: &mut App) {
-    app.add_systems(Update, process_key_code_input_system);
+    app.add_systems(Update, (process_key_code_input_system, undo_redo_system));
 }
@@ -8,13 +8,12 @@
 
 use std::{num, sync};
 
-// The starting value is `1` so we can utilize `NonZeroU16`.
-static NEXT_ID: sync::atomic::AtomicU16 = sync::atomic::AtomicU16::new(1);
+static NEXT_ID: sync::atomic::AtomicU64 = sync::atomic::AtomicU64::new(0);
 
 #[derive(
     Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd, Hash, bevy::ecs::component::Component,
 )]
-pub struct LayerId(num::NonZeroU16);
+pub struct LayerId(num::NonZeroU64);
 
 impl Default for LayerId {
     fn default() -> Self {
@@ -28,7 +27,7 @@ impl LayerId {
     }
 }
 
-fn new_id() -> num::NonZeroU16 {
-    // Unsafety: The starting ID is 1 and we always increment.
-    unsafe { num::NonZeroU16::new_unchecked(NEXT_ID.fetch_add(1, sync::atomic::Ordering::SeqCst)) }
+fn new_id() -> num::NonZeroU64 {
+    // A 64-bit counter won't realistically overflow, but saturate rather than wrap to zero
+    num::NonZeroU64::MIN.saturating_add(NEXT_ID.fetch_add(1, sync::atomic::Ordering::Relaxed))
 }
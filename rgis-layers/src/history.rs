@@ -0,0 +1,648 @@
+use bevy::prelude::*;
+use std::{collections, mem};
+
+/// Changes kept for undoing, oldest dropped first.
+const MAX_UNDO_STEPS: usize = 100;
+
+/// Seconds within which changes of the same color are undone together, like the ones sent every
+/// frame while dragging in a color picker.
+const MERGE_SECS: f64 = 1.;
+
+/// A change to the layers or the target CRS. Applying one gives the change that reverts it.
+#[derive(Debug)]
+pub(crate) enum Change {
+    DeleteLayer(rgis_layer_id::LayerId),
+    /// Put a deleted layer back at its index, counted from the bottom
+    RestoreLayer {
+        layer: Box<crate::Layer>,
+        index: usize,
+        /// Whether it was the selected layer
+        is_selected: bool,
+        /// Whether it was the layer features drawn on the map are added to
+        is_scratch: bool,
+    },
+    MoveLayer(rgis_layer_id::LayerId, rgis_events::MoveDirection),
+    /// `None` for layers without a fill, like the ones of lines
+    FillColor(rgis_layer_id::LayerId, Option<Color>),
+    StrokeColor(rgis_layer_id::LayerId, Color),
+    Rename(rgis_layer_id::LayerId, String),
+    TargetCrs(u16),
+    /// Replace a feature's geometry, given in the CRS of the layer
+    FeatureGeometry {
+        layer_id: rgis_layer_id::LayerId,
+        feature_id: geo_features::FeatureId,
+        geometry: geo::Geometry<geo_projected::UnprojectedScalar>,
+        /// `None` for a new edit
+        revisions: Option<Revisions>,
+    },
+    FeatureProperties {
+        layer_id: rgis_layer_id::LayerId,
+        feature_id: geo_features::FeatureId,
+        properties: geo_features::Properties,
+        /// `None` for a new edit
+        revisions: Option<Revisions>,
+    },
+}
+
+/// The revision a layer goes back to when undoing or redoing a feature edit, if it still has
+/// the revision it was left with. Otherwise, features were added in between and the layer gets a
+/// new revision.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Revisions {
+    from: u64,
+    to: u64,
+}
+
+impl Revisions {
+    /// Goes back to `to` after an edit of `layer`, which had revision `from` before it. Returns
+    /// the revisions reverting this.
+    fn apply(revisions: Option<Self>, from: u64, layer: &mut crate::Layer) -> Option<Self> {
+        if let Some(revisions) = revisions.filter(|revisions| revisions.from == from) {
+            layer.set_revision(revisions.to);
+        }
+        Some(Revisions {
+            from: layer.revision(),
+            to: from,
+        })
+    }
+}
+
+impl Change {
+    fn description(&self) -> &'static str {
+        match self {
+            Change::DeleteLayer(_) | Change::RestoreLayer { .. } => "remove layer",
+            Change::MoveLayer(..) => "move layer",
+            Change::FillColor(..) => "fill color",
+            Change::StrokeColor(..) => "stroke color",
+            Change::Rename(..) => "rename",
+            Change::TargetCrs(_) => "CRS change",
+            Change::FeatureGeometry { .. } => "geometry edit",
+            Change::FeatureProperties { .. } => "properties edit",
+        }
+    }
+
+    fn is_same_color(&self, other: &Change) -> bool {
+        match (self, other) {
+            (Change::FillColor(a, _), Change::FillColor(b, _))
+            | (Change::StrokeColor(a, _), Change::StrokeColor(b, _)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Changes that can be undone and redone, kept as the changes reverting them.
+#[derive(Default, Resource)]
+pub struct History {
+    undo_stack: collections::VecDeque<Change>,
+    redo_stack: Vec<Change>,
+    /// When the last change was recorded, in seconds since startup
+    last_recorded_at: f64,
+}
+
+impl History {
+    /// What undoing would revert, e.g. "remove layer".
+    pub fn undo_description(&self) -> Option<&'static str> {
+        self.undo_stack.back().map(Change::description)
+    }
+
+    /// What redoing would apply again.
+    pub fn redo_description(&self) -> Option<&'static str> {
+        self.redo_stack.last().map(Change::description)
+    }
+}
+
+/// Applies changes to the layers, recording them so they can be undone.
+#[derive(bevy::ecs::system::SystemParam)]
+pub(crate) struct Changes<'w> {
+    layers: ResMut<'w, crate::Layers>,
+    history: ResMut<'w, History>,
+    rgis_settings: ResMut<'w, rgis_settings::RgisSettings>,
+    time: Res<'w, Time>,
+    despawn_meshes_event_writer: EventWriter<'w, rgis_events::DespawnMeshesEvent>,
+    reproject_layer_event_writer: EventWriter<'w, rgis_events::ReprojectLayerEvent>,
+    layer_z_index_updated_event_writer: EventWriter<'w, rgis_events::LayerZIndexUpdatedEvent>,
+    layer_color_updated_event_writer: EventWriter<'w, rgis_events::LayerColorUpdatedEvent>,
+    layer_features_updated_event_writer: EventWriter<'w, rgis_events::LayerFeaturesUpdatedEvent>,
+    feature_geometry_updated_event_writer:
+        EventWriter<'w, rgis_events::FeatureGeometryUpdatedEvent>,
+    crs_changed_event_writer: EventWriter<'w, rgis_events::CrsChangedEvent>,
+}
+
+impl Changes<'_> {
+    pub(crate) fn layers(&self) -> &crate::Layers {
+        &self.layers
+    }
+
+    pub(crate) fn target_crs_epsg_code(&self) -> u16 {
+        self.rgis_settings.target_crs_epsg_code
+    }
+
+    /// Applies a change that can be undone, after which the undone changes can't be redone.
+    pub(crate) fn apply(&mut self, change: Change) {
+        if let Some(revert) = self.apply_change(change) {
+            self.record(revert);
+        }
+    }
+
+    /// Records a change that was applied elsewhere, given as the change reverting it.
+    pub(crate) fn record(&mut self, revert: Change) {
+        let now = self.time.elapsed_secs_f64();
+        let history = &mut *self.history;
+        history.redo_stack.clear();
+        let is_merged = now - history.last_recorded_at <= MERGE_SECS
+            && history
+                .undo_stack
+                .back()
+                .is_some_and(|last| last.is_same_color(&revert));
+        history.last_recorded_at = now;
+        if is_merged {
+            // Keep reverting to the color from before the first change
+            return;
+        }
+        history.undo_stack.push_back(revert);
+        if history.undo_stack.len() > MAX_UNDO_STEPS {
+            history.undo_stack.pop_front();
+        }
+    }
+
+    pub(crate) fn undo(&mut self) {
+        let Some(change) = self.history.undo_stack.pop_back() else {
+            return;
+        };
+        if let Some(revert) = self.apply_change(change) {
+            self.history.redo_stack.push(revert);
+        }
+    }
+
+    pub(crate) fn redo(&mut self) {
+        let Some(change) = self.history.redo_stack.pop() else {
+            return;
+        };
+        if let Some(revert) = self.apply_change(change) {
+            self.history.undo_stack.push_back(revert);
+        }
+    }
+
+    /// Applies a change, returning the change reverting it, or `None` if nothing changed.
+    fn apply_change(&mut self, change: Change) -> Option<Change> {
+        match change {
+            Change::DeleteLayer(layer_id) => {
+                let index = self.layers.get_index(layer_id)?;
+                let layer = self.layers.data.remove(index);
+                let is_selected = self.layers.selected_layer_id == Some(layer_id);
+                if is_selected {
+                    self.layers.selected_layer_id = None;
+                }
+                let is_scratch = self.layers.scratch_layer_id == Some(layer_id);
+                if is_scratch {
+                    self.layers.scratch_layer_id = None;
+                }
+                self.despawn_meshes_event_writer
+                    .write(rgis_events::DespawnMeshesEvent(layer_id));
+                self.layers_moved_from(index);
+                Some(Change::RestoreLayer {
+                    layer: Box::new(layer),
+                    index,
+                    is_selected,
+                    is_scratch,
+                })
+            }
+            Change::RestoreLayer {
+                mut layer,
+                index,
+                is_selected,
+                is_scratch,
+            } => {
+                let layer_id = layer.id;
+                // The target CRS may have changed since the layer was deleted
+                layer.clear_projected_feature_collection();
+                let index = index.min(self.layers.data.len());
+                self.layers.data.insert(index, *layer);
+                if is_selected {
+                    self.layers.selected_layer_id = Some(layer_id);
+                }
+                // Unless features were drawn into a new layer since
+                if is_scratch && self.layers.scratch_layer_id.is_none() {
+                    self.layers.scratch_layer_id = Some(layer_id);
+                }
+                self.reproject_layer_event_writer
+                    .write(rgis_events::ReprojectLayerEvent(layer_id));
+                self.layers_moved_from(index + 1);
+                Some(Change::DeleteLayer(layer_id))
+            }
+            Change::MoveLayer(layer_id, direction) => {
+                let index = self.layers.get_index(layer_id)?;
+                let other_index = match direction {
+                    rgis_events::MoveDirection::Up => index + 1,
+                    rgis_events::MoveDirection::Down => index.checked_sub(1)?,
+                };
+                let other_layer_id = self.layers.data.get(other_index)?.id;
+                self.layers.data.swap(index, other_index);
+                self.layer_z_index_updated_event_writer
+                    .write(rgis_events::LayerZIndexUpdatedEvent(layer_id));
+                self.layer_z_index_updated_event_writer
+                    .write(rgis_events::LayerZIndexUpdatedEvent(other_layer_id));
+                Some(Change::MoveLayer(
+                    layer_id,
+                    match direction {
+                        rgis_events::MoveDirection::Up => rgis_events::MoveDirection::Down,
+                        rgis_events::MoveDirection::Down => rgis_events::MoveDirection::Up,
+                    },
+                ))
+            }
+            Change::FillColor(layer_id, color) => {
+                let layer = self.layers.get_mut(layer_id)?;
+                let old_color = mem::replace(&mut layer.color.fill, color);
+                self.layer_color_updated_event_writer
+                    .write(rgis_events::LayerColorUpdatedEvent::Fill(layer_id));
+                Some(Change::FillColor(layer_id, old_color))
+            }
+            Change::StrokeColor(layer_id, color) => {
+                let layer = self.layers.get_mut(layer_id)?;
+                let old_color = mem::replace(&mut layer.color.stroke, color);
+                self.layer_color_updated_event_writer
+                    .write(rgis_events::LayerColorUpdatedEvent::Stroke(layer_id));
+                Some(Change::StrokeColor(layer_id, old_color))
+            }
+            Change::Rename(layer_id, name) => {
+                let layer = self.layers.get_mut(layer_id)?;
+                let old_name = mem::replace(&mut layer.name, name);
+                Some(Change::Rename(layer_id, old_name))
+            }
+            Change::TargetCrs(crs_epsg_code) => {
+                let old_crs_epsg_code =
+                    mem::replace(&mut self.rgis_settings.target_crs_epsg_code, crs_epsg_code);
+                self.crs_changed_event_writer
+                    .write(rgis_events::CrsChangedEvent {
+                        old_crs_epsg_code,
+                        new_crs_epsg_code: crs_epsg_code,
+                    });
+                Some(Change::TargetCrs(old_crs_epsg_code))
+            }
+            Change::FeatureGeometry {
+                layer_id,
+                feature_id,
+                geometry,
+                revisions,
+            } => {
+                let target_crs_epsg_code = self.rgis_settings.target_crs_epsg_code;
+                let layer = self.layers.get_mut(layer_id)?;
+                let projected_geometry = match crate::systems::project_geometry(
+                    &geometry,
+                    layer.crs_epsg_code,
+                    target_crs_epsg_code,
+                ) {
+                    Ok(projected_geometry) => projected_geometry,
+                    Err(e) => {
+                        bevy::log::error!("Could not project the feature's geometry: {e}");
+                        return None;
+                    }
+                };
+                let old_geometry = layer
+                    .unprojected_feature_collection
+                    .get(feature_id)?
+                    .geometry
+                    .clone()?;
+                let old_revision = layer.revision();
+                layer.set_feature_geometry(feature_id, geometry, projected_geometry);
+                let revisions = Revisions::apply(revisions, old_revision, layer);
+                self.request_projection(layer_id);
+                self.feature_geometry_updated_event_writer.write(
+                    rgis_events::FeatureGeometryUpdatedEvent(layer_id, feature_id),
+                );
+                Some(Change::FeatureGeometry {
+                    layer_id,
+                    feature_id,
+                    geometry: old_geometry,
+                    revisions,
+                })
+            }
+            Change::FeatureProperties {
+                layer_id,
+                feature_id,
+                properties,
+                revisions,
+            } => {
+                let layer = self.layers.get_mut(layer_id)?;
+                let old_properties = layer
+                    .unprojected_feature_collection
+                    .get(feature_id)?
+                    .properties
+                    .clone();
+                let old_revision = layer.revision();
+                layer.set_feature_properties(feature_id, properties);
+                let revisions = Revisions::apply(revisions, old_revision, layer);
+                self.request_projection(layer_id);
+                // Features may now match the layer's filter differently
+                self.layer_features_updated_event_writer
+                    .write(rgis_events::LayerFeaturesUpdatedEvent(layer_id));
+                Some(Change::FeatureProperties {
+                    layer_id,
+                    feature_id,
+                    properties: old_properties,
+                    revisions,
+                })
+            }
+        }
+    }
+
+    /// Projects a layer whose features changed while it wasn't projected, as projections started
+    /// before the change are discarded.
+    fn request_projection(&mut self, layer_id: rgis_layer_id::LayerId) {
+        if self
+            .layers
+            .get(layer_id)
+            .is_some_and(|layer| !layer.is_active())
+        {
+            self.reproject_layer_event_writer
+                .write(rgis_events::ReprojectLayerEvent(layer_id));
+        }
+    }
+
+    /// Updates the z-index of the layers from `index` up, after a layer below them was added or
+    /// removed.
+    fn layers_moved_from(&mut self, index: usize) {
+        for layer in self.layers.data.iter().skip(index) {
+            self.layer_z_index_updated_event_writer
+                .write(rgis_events::LayerZIndexUpdatedEvent(layer.id));
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use geo_projected::WrapTo;
+
+    fn world() -> World {
+        let mut world = World::new();
+        let mut layers = crate::Layers::new();
+        for name in ["Bottom", "Middle", "Top"] {
+            let features = vec![geo_features::FeatureBuilder::new()
+                .with_geometry(geo::point!(x: 1., y: 2.).into())
+                .with_properties(geo_features::Properties::from([(
+                    "name".to_owned(),
+                    geo_features::Value::String(name.into()),
+                )]))
+                .build()];
+            layers.add(
+                geo_features::FeatureCollection::from_features(features).wrap(),
+                name.into(),
+                4326,
+            );
+        }
+        world.insert_resource(layers);
+        world.init_resource::<History>();
+        world.insert_resource(rgis_settings::RgisSettings {
+            target_crs_epsg_code: 4326,
+            ..Default::default()
+        });
+        world.init_resource::<Time>();
+        world.init_resource::<Events<rgis_events::DespawnMeshesEvent>>();
+        world.init_resource::<Events<rgis_events::ReprojectLayerEvent>>();
+        world.init_resource::<Events<rgis_events::LayerZIndexUpdatedEvent>>();
+        world.init_resource::<Events<rgis_events::LayerColorUpdatedEvent>>();
+        world.init_resource::<Events<rgis_events::LayerFeaturesUpdatedEvent>>();
+        world.init_resource::<Events<rgis_events::FeatureGeometryUpdatedEvent>>();
+        world.init_resource::<Events<rgis_events::CrsChangedEvent>>();
+        world
+    }
+
+    fn with_changes(world: &mut World, f: impl FnOnce(&mut Changes)) {
+        let mut state = bevy::ecs::system::SystemState::<Changes<'static>>::new(world);
+        f(&mut state.get_mut(world));
+        state.apply(world);
+    }
+
+    fn layer_ids(world: &World) -> Vec<rgis_layer_id::LayerId> {
+        world
+            .resource::<crate::Layers>()
+            .data
+            .iter()
+            .map(|layer| layer.id)
+            .collect()
+    }
+
+    /// What changes can change: the order, names, colors, features and revisions of the layers,
+    /// the selected and scratch layers, and the target CRS.
+    fn state(world: &World) -> String {
+        let layers = world.resource::<crate::Layers>();
+        let layer_states = layers
+            .data
+            .iter()
+            .map(|layer| {
+                format!(
+                    "{:?} {} {:?} {:?} {}",
+                    layer.id,
+                    layer.name,
+                    layer.color,
+                    layer.unprojected_feature_collection.features(),
+                    layer.revision()
+                )
+            })
+            .collect::<Vec<_>>();
+        format!(
+            "{layer_states:?} {:?} {:?} {}",
+            layers.selected_layer_id,
+            layers.scratch_layer_id,
+            world
+                .resource::<rgis_settings::RgisSettings>()
+                .target_crs_epsg_code
+        )
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut world = world();
+        let [bottom, middle, top] = <[_; 3]>::try_from(layer_ids(&world)).unwrap();
+        let feature_id = world
+            .resource::<crate::Layers>()
+            .get(middle)
+            .unwrap()
+            .unprojected_feature_collection
+            .features()
+            .first()
+            .unwrap()
+            .id;
+        let mut layers = world.resource_mut::<crate::Layers>();
+        layers.get_mut(top).unwrap().color.fill = None;
+        // For deleting it to clear them
+        layers.selected_layer_id = Some(middle);
+        layers.scratch_layer_id = Some(middle);
+
+        let changes = [
+            Change::DeleteLayer(middle),
+            Change::MoveLayer(bottom, rgis_events::MoveDirection::Up),
+            Change::MoveLayer(top, rgis_events::MoveDirection::Down),
+            Change::FillColor(middle, Some(Color::BLACK)),
+            // The layer had no fill before
+            Change::FillColor(top, Some(Color::BLACK)),
+            Change::StrokeColor(bottom, Color::WHITE),
+            Change::Rename(top, "Renamed".into()),
+            Change::TargetCrs(3857),
+            Change::FeatureGeometry {
+                layer_id: middle,
+                feature_id,
+                geometry: geo::Geometry::from(geo::line_string![
+                    (x: 0., y: 0.),
+                    (x: 1., y: 1.),
+                ])
+                .wrap(),
+                revisions: None,
+            },
+            Change::FeatureProperties {
+                layer_id: middle,
+                feature_id,
+                properties: geo_features::Properties::new(),
+                revisions: None,
+            },
+        ];
+        for change in changes {
+            let description = change.description();
+            // Changes are far enough apart not to be merged
+            world
+                .resource_mut::<Time>()
+                .advance_by(std::time::Duration::from_secs(2));
+            let before = state(&world);
+            with_changes(&mut world, |changes| changes.apply(change));
+            let after = state(&world);
+            assert_ne!(after, before, "{description}");
+
+            with_changes(&mut world, |changes| changes.undo());
+            assert_eq!(state(&world), before, "undoing {description}");
+            with_changes(&mut world, |changes| changes.redo());
+            assert_eq!(state(&world), after, "redoing {description}");
+            with_changes(&mut world, |changes| changes.undo());
+        }
+        assert_eq!(world.resource::<History>().undo_description(), None);
+    }
+
+    #[test]
+    fn test_unsaved_changes() {
+        let mut world = world();
+        let layer_id = *layer_ids(&world).first().unwrap();
+        let layer = |world: &World| {
+            world
+                .resource::<crate::Layers>()
+                .get(layer_id)
+                .unwrap()
+                .clone()
+        };
+        let feature_id = layer(&world)
+            .unprojected_feature_collection
+            .features()
+            .first()
+            .unwrap()
+            .id;
+        let edit = |world: &mut World, i| {
+            world
+                .resource_mut::<Time>()
+                .advance_by(std::time::Duration::from_secs(2));
+            with_changes(world, |changes| {
+                changes.apply(Change::FeatureProperties {
+                    layer_id,
+                    feature_id,
+                    properties: geo_features::Properties::from([(
+                        "i".to_owned(),
+                        geo_features::Value::Integer(i),
+                    )]),
+                    revisions: None,
+                });
+            });
+        };
+
+        edit(&mut world, 1);
+        let first_revision = layer(&world).revision();
+        edit(&mut world, 2);
+        let second_revision = layer(&world).revision();
+        assert_ne!(first_revision, second_revision);
+
+        with_changes(&mut world, |changes| changes.undo());
+        assert_eq!(layer(&world).revision(), first_revision);
+        with_changes(&mut world, |changes| changes.undo());
+        assert!(!layer(&world).has_unsaved_changes());
+        with_changes(&mut world, |changes| changes.redo());
+        assert_eq!(layer(&world).revision(), first_revision);
+        with_changes(&mut world, |changes| changes.redo());
+        assert_eq!(layer(&world).revision(), second_revision);
+
+        // Undoing doesn't go back to the loaded revision when a feature was added since
+        world
+            .resource_mut::<crate::Layers>()
+            .get_mut(layer_id)
+            .unwrap()
+            .push_feature(
+                geo::Geometry::from(geo::point!(x: 0., y: 0.)).wrap(),
+                geo::Geometry::from(geo::point!(x: 0., y: 0.)).wrap(),
+                geo_features::Properties::new(),
+            );
+        with_changes(&mut world, |changes| changes.undo());
+        with_changes(&mut world, |changes| changes.undo());
+        assert!(layer(&world).has_unsaved_changes());
+    }
+
+    #[test]
+    fn test_color_changes_are_merged() {
+        let mut world = world();
+        let layer_id = *layer_ids(&world).first().unwrap();
+        let stroke = |world: &World| {
+            world
+                .resource::<crate::Layers>()
+                .get(layer_id)
+                .unwrap()
+                .color
+                .stroke
+        };
+        let original = stroke(&world);
+
+        for (i, secs) in [0., 0.5, 0.5, 0.5].into_iter().enumerate() {
+            world
+                .resource_mut::<Time>()
+                .advance_by(std::time::Duration::from_secs_f64(secs));
+            let color = Color::srgb(0., 0., i as f32 / 10.);
+            with_changes(&mut world, |changes| {
+                changes.apply(Change::StrokeColor(layer_id, color));
+            });
+        }
+        // Changes to another color aren't merged
+        with_changes(&mut world, |changes| {
+            changes.apply(Change::FillColor(layer_id, Some(Color::BLACK)));
+        });
+        assert_eq!(world.resource::<History>().undo_stack.len(), 2);
+
+        with_changes(&mut world, |changes| changes.undo());
+        with_changes(&mut world, |changes| changes.undo());
+        assert_eq!(stroke(&world), original);
+
+        // Not within `MERGE_SECS` of the last change
+        for secs in [0., MERGE_SECS * 2.] {
+            world
+                .resource_mut::<Time>()
+                .advance_by(std::time::Duration::from_secs_f64(secs));
+            with_changes(&mut world, |changes| {
+                changes.apply(Change::StrokeColor(layer_id, Color::WHITE));
+            });
+        }
+        assert_eq!(world.resource::<History>().undo_stack.len(), 2);
+    }
+
+    #[test]
+    fn test_oldest_changes_are_dropped() {
+        let mut world = world();
+        let layer_id = *layer_ids(&world).first().unwrap();
+        for i in 0..=MAX_UNDO_STEPS {
+            with_changes(&mut world, |changes| {
+                changes.apply(Change::Rename(layer_id, i.to_string()));
+            });
+        }
+        assert_eq!(world.resource::<History>().undo_stack.len(), MAX_UNDO_STEPS);
+
+        for _ in 0..=MAX_UNDO_STEPS {
+            with_changes(&mut world, |changes| changes.undo());
+        }
+        // The first rename can't be undone anymore
+        let layers = world.resource::<crate::Layers>();
+        assert_eq!(layers.get(layer_id).unwrap().name, "0");
+    }
+}
@@ -7,11 +7,16 @@
 )]
 
 use bevy::prelude::*;
-use geo::contains::Contains;
-use std::sync;
+use geo::{contains::Contains, BoundingRect, Intersects};
+use std::{collections, sync};
 
+mod history;
+mod spatial_index;
 mod systems;
 
+pub use history::History;
+pub use spatial_index::SpatialIndex;
+
 #[derive(Copy, Clone, Debug)]
 pub struct LayerIndex(pub usize);
 
@@ -21,6 +26,8 @@ pub struct Layers {
     data: Vec<Layer>,
     // ID of the currently selected Layer
     pub selected_layer_id: Option<rgis_layer_id::LayerId>,
+    /// Layer features drawn on the map are added to, created with the first one.
+    pub scratch_layer_id: Option<rgis_layer_id::LayerId>,
 }
 
 impl Default for Layers {
@@ -34,6 +41,7 @@ impl Layers {
         Layers {
             data: vec![],
             selected_layer_id: None,
+            scratch_layer_id: None,
         }
     }
 
@@ -57,43 +65,7 @@ impl Layers {
         coord: geo_projected::ProjectedCoord,
     ) -> impl Iterator<Item = &Layer> {
         self.iter_top_to_bottom()
-            .filter(move |layer| match layer.projected_feature_collection {
-                Some(ref projected) => projected.contains(&coord),
-                None => false,
-            })
-    }
-
-    fn feature_collections_iter(&self) -> impl Iterator<Item = FeatureCollectionsIterItem> {
-        self.iter_top_to_bottom().flat_map(|layer| {
-            layer
-                .projected_feature_collection
-                .as_ref()
-                .map(|projected| FeatureCollectionsIterItem {
-                    layer,
-                    unprojected: &layer.unprojected_feature_collection,
-                    projected,
-                })
-        })
-    }
-
-    fn features_iter(&self) -> impl Iterator<Item = FeaturesIterItem> {
-        self.feature_collections_iter().flat_map(
-            |FeatureCollectionsIterItem {
-                 layer,
-                 projected,
-                 unprojected,
-             }| {
-                unprojected
-                    .features
-                    .iter()
-                    .zip(projected.features.iter())
-                    .map(move |(unprojected, projected)| FeaturesIterItem {
-                        layer,
-                        projected,
-                        unprojected,
-                    })
-            },
-        )
+            .filter(move |layer| layer.projected_feature_index_containing(coord).is_some())
     }
 
     pub fn feature_from_click(
@@ -103,9 +75,61 @@ impl Layers {
         &Layer,
         &geo_features::Feature<geo_projected::UnprojectedScalar>,
     )> {
-        self.features_iter()
-            .find(|item| item.projected.contains(&coord))
-            .map(|item| (item.layer, item.unprojected))
+        self.iter_top_to_bottom().find_map(|layer| {
+            let index = layer.projected_feature_index_containing(coord)?;
+            Some((
+                layer,
+                layer.unprojected_feature_collection.features().get(index)?,
+            ))
+        })
+    }
+
+    /// Projected features of visible layers whose bounding rect intersects `rect`, from the top
+    /// layer to the bottom one.
+    pub fn features_in_rect(
+        &self,
+        rect: geo::Rect<geo_projected::ProjectedScalar>,
+    ) -> impl Iterator<
+        Item = (
+            &Layer,
+            &geo_features::Feature<geo_projected::ProjectedScalar>,
+        ),
+    > {
+        self.iter_top_to_bottom()
+            .filter(|layer| layer.visible)
+            .flat_map(move |layer| {
+                layer
+                    .projected_features_in_rect(rect)
+                    .map(move |feature| (layer, feature))
+            })
+    }
+
+    /// IDs of the visible features picked by an area drawn with the selection tools, by layer.
+    pub(crate) fn features_in_area(
+        &self,
+        area: &geo::Polygon<geo_projected::ProjectedScalar>,
+        predicate: rgis_events::AreaSelectionPredicate,
+    ) -> collections::BTreeMap<rgis_layer_id::LayerId, Vec<geo_features::FeatureId>> {
+        let mut feature_ids_by_layer = collections::BTreeMap::<_, Vec<_>>::new();
+        let Some(rect) = area.bounding_rect() else {
+            return feature_ids_by_layer;
+        };
+        for (layer, feature) in self.features_in_rect(rect) {
+            let Some(ref geometry) = feature.geometry else {
+                continue;
+            };
+            let is_picked = match predicate {
+                rgis_events::AreaSelectionPredicate::Intersects => geometry.intersects(area),
+                rgis_events::AreaSelectionPredicate::Within => area.contains(geometry),
+            };
+            if is_picked {
+                feature_ids_by_layer
+                    .entry(layer.id)
+                    .or_default()
+                    .push(feature.id);
+            }
+        }
+        feature_ids_by_layer
     }
 
     fn get_index(&self, layer_id: rgis_layer_id::LayerId) -> Option<usize> {
@@ -154,10 +178,25 @@ impl Layers {
         source_crs_epsg_code: u16,
     ) -> rgis_layer_id::LayerId {
         let layer_id = self.next_layer_id();
+        self.add_with_id(layer_id, unprojected, name, source_crs_epsg_code);
+        layer_id
+    }
+
+    /// Same as `add`, for layers whose ID was chosen before they were created, like the ones
+    /// loaded page by page.
+    fn add_with_id(
+        &mut self,
+        layer_id: rgis_layer_id::LayerId,
+        unprojected: geo_features::FeatureCollection<geo_projected::UnprojectedScalar>,
+        name: String,
+        source_crs_epsg_code: u16,
+    ) {
         let geom_type = geo_geom_type::determine(unprojected.geometry_iter());
+        let filtered_feature_count = unprojected.features().len();
         let layer = Layer {
             unprojected_feature_collection: unprojected,
             projected_feature_collection: None,
+            spatial_index: None,
             color: if geom_type.has_fill() {
                 LayerColor {
                     fill: Some(colorous_color_to_bevy_color(next_colorous_color())),
@@ -169,19 +208,24 @@ impl Layers {
                     stroke: colorous_color_to_bevy_color(next_colorous_color()),
                 }
             },
+            filtered_feature_count,
             name,
             visible: true,
+            filter: None,
+            selected_feature_ids: collections::BTreeSet::new(),
             id: layer_id,
             crs_epsg_code: source_crs_epsg_code,
             geom_type,
+            revision: 0,
+            loaded_revision: 0,
+            next_revision: 1,
         };
         self.data.push(layer);
-        layer_id
     }
 
     pub fn clear_projected(&mut self) {
         for layer in self.data.iter_mut() {
-            layer.projected_feature_collection = None;
+            layer.clear_projected_feature_collection();
         }
     }
 
@@ -202,12 +246,28 @@ pub struct Layer {
         geo_features::FeatureCollection<geo_projected::UnprojectedScalar>,
     pub projected_feature_collection:
         Option<geo_features::FeatureCollection<geo_projected::ProjectedScalar>>,
+    /// Index of `projected_feature_collection`, set along with it.
+    spatial_index: Option<spatial_index::SpatialIndex>,
     pub color: LayerColor,
     pub id: rgis_layer_id::LayerId,
     pub name: String,
     pub visible: bool,
+    /// Features not matching the filter are neither rendered nor found by queries, but are
+    /// kept in the layer.
+    filter: Option<geo_features::filter::Filter>,
+    /// Number of features matching `filter`, kept up to date so it isn't counted every frame.
+    filtered_feature_count: usize,
+    /// Changed through `UpdateSelectionEvent`s, so the selected features get highlighted.
+    pub selected_feature_ids: collections::BTreeSet<geo_features::FeatureId>,
     pub crs_epsg_code: u16,
     pub geom_type: geo_geom_type::GeomType,
+    /// Identifies the state of the features. Every edit gets a new revision, and undoing one goes
+    /// back to the revision before it.
+    revision: u64,
+    /// Revision of the features as loaded, which changes while pages of them are still arriving.
+    loaded_revision: u64,
+    /// Never handed out before, so different states never share a revision.
+    next_revision: u64,
 }
 
 impl Layer {
@@ -215,6 +275,281 @@ impl Layer {
         self.projected_feature_collection.is_some()
     }
 
+    pub fn filter(&self) -> Option<&geo_features::filter::Filter> {
+        self.filter.as_ref()
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Whether the features differ from the ones the layer was loaded with.
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.revision != self.loaded_revision
+    }
+
+    /// Goes back to a revision the layer had, when its edits were undone or redone.
+    pub(crate) fn set_revision(&mut self, revision: u64) {
+        self.revision = revision;
+    }
+
+    fn new_revision(&mut self) {
+        self.revision = self.next_revision;
+        self.next_revision += 1;
+    }
+
+    pub fn set_filter(&mut self, filter: Option<geo_features::filter::Filter>) {
+        self.filter = filter;
+        self.filtered_feature_count = self.filtered_unprojected_features().count();
+    }
+
+    /// Same as counting `filtered_unprojected_features`, without going through every feature.
+    pub fn filtered_feature_count(&self) -> usize {
+        self.filtered_feature_count
+    }
+
+    #[inline]
+    pub fn matches_filter<Scalar: geo::CoordNum>(
+        &self,
+        feature: &geo_features::Feature<Scalar>,
+    ) -> bool {
+        self.filter
+            .as_ref()
+            .is_none_or(|filter| filter.matches(&feature.properties))
+    }
+
+    pub fn filtered_unprojected_features(
+        &self,
+    ) -> impl Iterator<Item = &geo_features::Feature<geo_projected::UnprojectedScalar>> {
+        self.unprojected_feature_collection
+            .iter()
+            .filter(|feature| self.matches_filter(feature))
+    }
+
+    pub fn filtered_projected_features(
+        &self,
+    ) -> impl Iterator<Item = &geo_features::Feature<geo_projected::ProjectedScalar>> {
+        self.projected_feature_collection
+            .iter()
+            .flat_map(|projected| projected.iter())
+            .filter(|feature| self.matches_filter(feature))
+    }
+
+    pub fn selected_unprojected_features(
+        &self,
+    ) -> impl Iterator<Item = &geo_features::Feature<geo_projected::UnprojectedScalar>> {
+        self.selected_feature_ids
+            .iter()
+            .filter_map(|feature_id| self.unprojected_feature_collection.get(*feature_id))
+    }
+
+    pub fn selected_projected_features(
+        &self,
+    ) -> impl Iterator<Item = &geo_features::Feature<geo_projected::ProjectedScalar>> {
+        self.selected_feature_ids
+            .iter()
+            .filter_map(|feature_id| self.projected_feature_collection.as_ref()?.get(*feature_id))
+    }
+
+    /// What operations run on: the selected features, or every feature when none are selected.
+    pub fn operation_feature_collection(
+        &self,
+    ) -> geo_features::FeatureCollection<geo_projected::UnprojectedScalar> {
+        if self.selected_feature_ids.is_empty() {
+            self.unprojected_feature_collection.clone()
+        } else {
+            geo_features::FeatureCollection::from_features(
+                self.selected_unprojected_features().cloned().collect(),
+            )
+        }
+    }
+
+    /// `spatial_index` must have been built from `projected`.
+    pub fn set_projected_feature_collection(
+        &mut self,
+        projected: geo_features::FeatureCollection<geo_projected::ProjectedScalar>,
+        spatial_index: SpatialIndex,
+    ) {
+        self.projected_feature_collection = Some(projected);
+        self.spatial_index = Some(spatial_index);
+    }
+
+    /// Discards the projected features and their spatial index, e.g. before reprojecting.
+    pub fn clear_projected_feature_collection(&mut self) {
+        self.projected_feature_collection = None;
+        self.spatial_index = None;
+    }
+
+    /// Position of the first projected feature matching the filter and containing `coord`.
+    fn projected_feature_index_containing(
+        &self,
+        coord: geo_projected::ProjectedCoord,
+    ) -> Option<usize> {
+        let projected = self.projected_feature_collection.as_ref()?;
+        self.spatial_index
+            .as_ref()?
+            .containing(coord)
+            .filter(|index| {
+                projected
+                    .features()
+                    .get(*index)
+                    .is_some_and(|feature| self.matches_filter(feature) && feature.contains(&coord))
+            })
+            .min()
+    }
+
+    /// Projected features matching the filter whose bounding rect intersects `rect`.
+    fn projected_features_in_rect(
+        &self,
+        rect: geo::Rect<geo_projected::ProjectedScalar>,
+    ) -> Box<dyn Iterator<Item = &geo_features::Feature<geo_projected::ProjectedScalar>> + '_> {
+        let (Some(projected), Some(spatial_index)) =
+            (&self.projected_feature_collection, &self.spatial_index)
+        else {
+            return Box::new(std::iter::empty());
+        };
+        Box::new(
+            spatial_index
+                .intersecting(rect)
+                .filter_map(|index| projected.features().get(index))
+                .filter(|feature| self.matches_filter(feature)),
+        )
+    }
+
+    /// Appends a feature, given with its geometry in both the layer's CRS and the target CRS.
+    pub fn push_feature(
+        &mut self,
+        geometry: geo::Geometry<geo_projected::UnprojectedScalar>,
+        projected_geometry: geo::Geometry<geo_projected::ProjectedScalar>,
+        properties: geo_features::Properties,
+    ) -> geo_features::FeatureId {
+        let feature = geo_features::FeatureBuilder::new()
+            .with_geometry(geometry)
+            .with_properties(properties.clone())
+            .build();
+        if self.matches_filter(&feature) {
+            self.filtered_feature_count += 1;
+        }
+        let feature_id = self.unprojected_feature_collection.push(feature);
+        if let (Some(projected), Some(spatial_index)) = (
+            &mut self.projected_feature_collection,
+            &mut self.spatial_index,
+        ) {
+            projected.push(
+                geo_features::FeatureBuilder::new()
+                    .with_geometry(projected_geometry)
+                    .with_properties(properties)
+                    .build(),
+            );
+            if let Some(feature) = projected.features().last() {
+                spatial_index.insert(projected.features().len() - 1, feature);
+            }
+        }
+        self.new_revision();
+        self.update_geom_type();
+        feature_id
+    }
+
+    /// Appends the features of a page loaded after the layer was created, given in the layer's
+    /// CRS. The layer is no longer active until it's projected again.
+    pub fn append_features(
+        &mut self,
+        feature_collection: geo_features::FeatureCollection<geo_projected::UnprojectedScalar>,
+    ) {
+        let had_unsaved_changes = self.has_unsaved_changes();
+        for feature in feature_collection {
+            if self.matches_filter(&feature) {
+                self.filtered_feature_count += 1;
+            }
+            self.unprojected_feature_collection.push(feature);
+        }
+        self.clear_projected_feature_collection();
+        self.new_revision();
+        // Still part of loading the layer
+        if !had_unsaved_changes {
+            self.loaded_revision = self.revision;
+        }
+        self.update_geom_type();
+    }
+
+    /// Gives the layer a fill once it has features that can be filled.
+    fn update_geom_type(&mut self) {
+        self.geom_type =
+            geo_geom_type::determine(self.unprojected_feature_collection.geometry_iter());
+        if self.geom_type.has_fill() && self.color.fill.is_none() {
+            self.color.fill = Some(colorous_color_to_bevy_color(next_colorous_color()));
+        }
+    }
+
+    /// Replaces a feature's geometry, given in both the layer's CRS and the target CRS. Returns
+    /// whether the feature exists.
+    pub fn set_feature_geometry(
+        &mut self,
+        feature_id: geo_features::FeatureId,
+        geometry: geo::Geometry<geo_projected::UnprojectedScalar>,
+        projected_geometry: geo::Geometry<geo_projected::ProjectedScalar>,
+    ) -> bool {
+        let Some(feature) = self.unprojected_feature_collection.get_mut(feature_id) else {
+            return false;
+        };
+        feature.geometry = Some(geometry);
+        feature.recalculate_bounding_rect();
+        self.unprojected_feature_collection
+            .recalculate_bounding_rect();
+        self.new_revision();
+
+        let (Some(projected), Some(spatial_index)) = (
+            &mut self.projected_feature_collection,
+            &mut self.spatial_index,
+        ) else {
+            return true;
+        };
+        let Some(index) = projected.position(feature_id) else {
+            return true;
+        };
+        if let Some(feature) = projected.get_mut(feature_id) {
+            // Only this feature's entry changes, not worth rebuilding the whole index for
+            spatial_index.remove(index, feature);
+            feature.geometry = Some(projected_geometry);
+            feature.recalculate_bounding_rect();
+            spatial_index.insert(index, feature);
+        }
+        projected.recalculate_bounding_rect();
+        true
+    }
+
+    /// Replaces a feature's properties in both feature collections. Returns whether the feature
+    /// exists.
+    pub fn set_feature_properties(
+        &mut self,
+        feature_id: geo_features::FeatureId,
+        properties: geo_features::Properties,
+    ) -> bool {
+        let Some(feature) = self.unprojected_feature_collection.get_mut(feature_id) else {
+            return false;
+        };
+        let matches = |properties: &geo_features::Properties| {
+            self.filter
+                .as_ref()
+                .is_none_or(|filter| filter.matches(properties))
+        };
+        match (matches(&feature.properties), matches(&properties)) {
+            (true, false) => self.filtered_feature_count -= 1,
+            (false, true) => self.filtered_feature_count += 1,
+            _ => (),
+        }
+        feature.properties.clone_from(&properties);
+        if let Some(feature) = self
+            .projected_feature_collection
+            .as_mut()
+            .and_then(|projected| projected.get_mut(feature_id))
+        {
+            feature.properties = properties;
+        }
+        self.new_revision();
+        true
+    }
+
     #[inline]
     pub fn get_projected_feature_collection_or_log(
         &self,
@@ -236,11 +571,8 @@ impl Layer {
         &self,
         feature_id: geo_features::FeatureId,
     ) -> Option<&geo_features::Feature<geo_projected::ProjectedScalar>> {
-        let feature_collection = self.get_projected_feature_collection_or_log()?;
-        feature_collection
-            .features
-            .iter()
-            .find(|f| f.id == feature_id)
+        self.get_projected_feature_collection_or_log()?
+            .get(feature_id)
     }
 }
 
@@ -264,19 +596,230 @@ pub struct Plugin;
 
 impl bevy::app::Plugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(Layers::new());
+        app.insert_resource(Layers::new())
+            .init_resource::<History>();
         systems::configure(app);
     }
 }
 
-struct FeatureCollectionsIterItem<'a> {
-    layer: &'a Layer,
-    projected: &'a geo_features::FeatureCollection<geo_projected::ProjectedScalar>,
-    unprojected: &'a geo_features::FeatureCollection<geo_projected::UnprojectedScalar>,
-}
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use geo_projected::{CastTo, WrapTo};
+
+    fn properties(i: i64) -> geo_features::Properties {
+        geo_features::Properties::from([("i".to_owned(), geo_features::Value::Integer(i))])
+    }
+
+    fn point() -> geo::Geometry<f64> {
+        geo::point!(x: 1., y: 2.).into()
+    }
+
+    fn layer() -> super::Layer {
+        let features = (0..5)
+            .map(|i| {
+                geo_features::FeatureBuilder::new()
+                    .with_geometry(point())
+                    .with_properties(properties(i))
+                    .build()
+            })
+            .collect();
+        let mut layers = super::Layers::new();
+        let layer_id = layers.add(
+            geo_features::FeatureCollection::from_features(features).wrap(),
+            "Layer".into(),
+            4326,
+        );
+        layers.get(layer_id).unwrap().clone()
+    }
+
+    fn project(layer: &mut super::Layer) {
+        let projected = layer
+            .unprojected_feature_collection
+            .clone()
+            .cast::<geo_projected::Projected>();
+        let spatial_index = super::SpatialIndex::new(&projected);
+        layer.set_projected_feature_collection(projected, spatial_index);
+    }
+
+    #[test]
+    fn test_features_in_area() {
+        let geometries: [geo::Geometry<f64>; 4] = [
+            geo::point!(x: 1., y: 1.).into(),
+            geo::Rect::new((2., 2.), (3., 3.)).into(),
+            // Crosses the edge of the area
+            geo::line_string![(x: 3., y: 3.), (x: 6., y: 3.)].into(),
+            geo::point!(x: 8., y: 8.).into(),
+        ];
+        let mut layers = super::Layers::new();
+        let mut add_layer = |visible| {
+            let features = geometries
+                .iter()
+                .map(|geometry| {
+                    geo_features::FeatureBuilder::new()
+                        .with_geometry(geometry.clone())
+                        .build()
+                })
+                .collect();
+            let layer_id = layers.add(
+                geo_features::FeatureCollection::from_features(features).wrap(),
+                "Layer".into(),
+                3857,
+            );
+            let layer = layers.get_mut(layer_id).unwrap();
+            layer.visible = visible;
+            project(layer);
+            layer_id
+        };
+        let layer_id = add_layer(true);
+        add_layer(false);
+        let feature_ids: Vec<_> = layers
+            .get(layer_id)
+            .unwrap()
+            .unprojected_feature_collection
+            .iter()
+            .map(|feature| feature.id)
+            .collect();
+        let area = geo::polygon![
+            (x: 0., y: 0.),
+            (x: 4., y: 0.),
+            (x: 4., y: 4.),
+            (x: 0., y: 4.),
+        ]
+        .wrap();
+        let picked = |predicate| {
+            let mut picked = layers.features_in_area(&area, predicate);
+            // Features of the hidden layer are never picked
+            assert_eq!(picked.len(), 1);
+            let mut feature_ids = picked.remove(&layer_id).unwrap();
+            feature_ids.sort();
+            feature_ids
+        };
 
-struct FeaturesIterItem<'a> {
-    layer: &'a Layer,
-    projected: &'a geo_features::Feature<geo_projected::ProjectedScalar>,
-    unprojected: &'a geo_features::Feature<geo_projected::UnprojectedScalar>,
+        assert_eq!(
+            picked(rgis_events::AreaSelectionPredicate::Intersects),
+            feature_ids.get(..3).unwrap()
+        );
+        assert_eq!(
+            picked(rgis_events::AreaSelectionPredicate::Within),
+            feature_ids.get(..2).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_filtered_feature_count() {
+        let mut layer = layer();
+        assert_eq!(layer.filtered_feature_count(), 5);
+
+        layer.set_filter(Some(geo_features::filter::Filter::parse("i < 2").unwrap()));
+        assert_eq!(layer.filtered_feature_count(), 2);
+
+        layer.push_feature(point().wrap(), point().wrap(), properties(0));
+        layer.push_feature(point().wrap(), point().wrap(), properties(9));
+        assert_eq!(layer.filtered_feature_count(), 3);
+
+        let feature_id = |layer: &super::Layer, i| {
+            layer
+                .unprojected_feature_collection
+                .iter()
+                .find(|feature| feature.properties == properties(i))
+                .unwrap()
+                .id
+        };
+        layer.set_feature_properties(feature_id(&layer, 4), properties(1));
+        layer.set_feature_properties(feature_id(&layer, 0), properties(3));
+        layer.set_feature_properties(feature_id(&layer, 2), properties(3));
+        assert_eq!(layer.filtered_feature_count(), 3);
+        assert_eq!(
+            layer.filtered_feature_count(),
+            layer.filtered_unprojected_features().count()
+        );
+    }
+
+    #[test]
+    fn test_append_features() {
+        let page = || {
+            let features = [2, 7]
+                .into_iter()
+                .map(|i| {
+                    geo_features::FeatureBuilder::new()
+                        .with_geometry(point())
+                        .with_properties(properties(i))
+                        .build()
+                })
+                .collect();
+            geo_features::FeatureCollection::from_features(features).wrap()
+        };
+        let mut layer = layer();
+        layer.set_filter(Some(geo_features::filter::Filter::parse("i < 3").unwrap()));
+        project(&mut layer);
+
+        layer.append_features(page());
+        assert!(!layer.is_active());
+        assert!(!layer.has_unsaved_changes());
+        assert_eq!(layer.unprojected_feature_collection.features().len(), 7);
+        assert_eq!(layer.filtered_feature_count(), 4);
+        let feature_ids: std::collections::BTreeSet<_> = layer
+            .unprojected_feature_collection
+            .iter()
+            .map(|feature| feature.id)
+            .collect();
+        assert_eq!(feature_ids.len(), 7);
+
+        // Edits made while pages are loading remain unsaved
+        let feature_id = *feature_ids.first().unwrap();
+        layer.set_feature_properties(feature_id, properties(9));
+        layer.append_features(page());
+        assert!(layer.has_unsaved_changes());
+    }
+
+    #[test]
+    fn test_set_feature_properties() {
+        let mut layer = layer();
+        project(&mut layer);
+        assert!(!layer.has_unsaved_changes());
+        let feature_id = layer
+            .unprojected_feature_collection
+            .features()
+            .first()
+            .unwrap()
+            .id;
+
+        assert!(layer.set_feature_properties(feature_id, properties(9)));
+        let revision = layer.revision();
+        assert!(layer.has_unsaved_changes());
+        assert_eq!(
+            layer
+                .unprojected_feature_collection
+                .get(feature_id)
+                .unwrap()
+                .properties,
+            properties(9)
+        );
+        assert_eq!(
+            layer
+                .projected_feature_collection
+                .as_ref()
+                .unwrap()
+                .get(feature_id)
+                .unwrap()
+                .properties,
+            properties(9)
+        );
+
+        // Every edit gets a new revision, even one going back to the loaded properties
+        assert!(layer.set_feature_properties(feature_id, properties(0)));
+        assert_ne!(layer.revision(), revision);
+        assert!(layer.has_unsaved_changes());
+
+        // An ID the layer hasn't handed out yet
+        let revision = layer.revision();
+        let other_feature_id = layer.unprojected_feature_collection.clone().push(
+            geo_features::FeatureBuilder::new()
+                .with_geometry(point().wrap())
+                .build(),
+        );
+        assert!(!layer.set_feature_properties(other_feature_id, properties(1)));
+        assert_eq!(layer.revision(), revision);
+    }
 }
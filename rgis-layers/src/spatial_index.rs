@@ -0,0 +1,262 @@
+use rstar::primitives::{GeomWithData, Rectangle};
+
+/// The bounding rect of a projected feature, along with the feature's position in its
+/// feature collection.
+type Entry = GeomWithData<Rectangle<[f64; 2]>, usize>;
+
+/// R-tree of the bounding rects of a layer's projected features. Built by the job projecting the
+/// layer, since bulk loading a large layer takes too long for a frame.
+#[derive(Clone, Debug)]
+pub struct SpatialIndex(rstar::RTree<Entry>);
+
+impl SpatialIndex {
+    pub fn new(
+        feature_collection: &geo_features::FeatureCollection<geo_projected::ProjectedScalar>,
+    ) -> Self {
+        let entries = feature_collection
+            .iter()
+            .enumerate()
+            .filter_map(|(index, feature)| entry(index, feature))
+            .collect();
+        SpatialIndex(rstar::RTree::bulk_load(entries))
+    }
+
+    /// Adds the feature at position `index` of the indexed feature collection.
+    pub(crate) fn insert(
+        &mut self,
+        index: usize,
+        feature: &geo_features::Feature<geo_projected::ProjectedScalar>,
+    ) {
+        if let Some(entry) = entry(index, feature) {
+            self.0.insert(entry);
+        }
+    }
+
+    /// Removes the feature at position `index`, given with the bounding rect it was added with.
+    pub(crate) fn remove(
+        &mut self,
+        index: usize,
+        feature: &geo_features::Feature<geo_projected::ProjectedScalar>,
+    ) {
+        if let Some(entry) = entry(index, feature) {
+            self.0.remove(&entry);
+        }
+    }
+
+    /// Positions of the features whose bounding rect contains `coord`, in no particular order.
+    pub(crate) fn containing(
+        &self,
+        coord: geo_projected::ProjectedCoord,
+    ) -> impl Iterator<Item = usize> + '_ {
+        self.0
+            .locate_all_at_point(&point(coord))
+            .map(|entry| entry.data)
+    }
+
+    /// Positions of the features whose bounding rect intersects `rect`, in no particular order.
+    pub(crate) fn intersecting(
+        &self,
+        rect: geo::Rect<geo_projected::ProjectedScalar>,
+    ) -> impl Iterator<Item = usize> + '_ {
+        let envelope = rstar::AABB::from_corners(point(rect.min()), point(rect.max()));
+        self.0
+            .locate_in_envelope_intersecting(&envelope)
+            .map(|entry| entry.data)
+    }
+}
+
+/// `None` for features without a geometry, which are left out of the index.
+fn entry(
+    index: usize,
+    feature: &geo_features::Feature<geo_projected::ProjectedScalar>,
+) -> Option<Entry> {
+    let rect = feature.bounding_rect?;
+    Some(Entry::new(
+        Rectangle::from_corners(point(rect.min()), point(rect.max())),
+        index,
+    ))
+}
+
+fn point(coord: geo_projected::ProjectedCoord) -> [f64; 2] {
+    [coord.x.0, coord.y.0]
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use geo::{Contains, Intersects};
+    use geo_projected::{CastTo, WrapTo};
+    use std::collections;
+
+    /// Two overlapping layers of squares, lines and points, each with a filter hiding some of
+    /// its features.
+    fn layers() -> crate::Layers {
+        let mut layers = crate::Layers::new();
+        for offset in [0., 2.5] {
+            let features = (0..10_i32)
+                .flat_map(|i| {
+                    let x = f64::from(i) * 3. + offset;
+                    let properties = geo_features::Properties::from([(
+                        "i".to_owned(),
+                        geo_features::Value::Integer(i.into()),
+                    )]);
+                    [
+                        geo::Geometry::from(geo::Rect::new((x, x), (x + 4., x + 4.))),
+                        geo::line_string![(x: x, y: 0.), (x: x + 2., y: 5.)].into(),
+                        geo::point!(x: x + 1., y: x + 1.).into(),
+                    ]
+                    .map(|geometry| {
+                        geo_features::FeatureBuilder::new()
+                            .with_geometry(geometry)
+                            .with_properties(properties.clone())
+                            .build()
+                    })
+                })
+                .collect();
+            let unprojected = geo_features::FeatureCollection::from_features(features).wrap();
+            let layer_id = layers.add(unprojected, "Layer".into(), 3857);
+            let layer = layers.get_mut(layer_id).unwrap();
+            layer.set_filter(Some(geo_features::filter::Filter::parse("i != 4").unwrap()));
+            let projected = layer
+                .unprojected_feature_collection
+                .clone()
+                .cast::<geo_projected::Projected>();
+            let spatial_index = super::SpatialIndex::new(&projected);
+            layer.set_projected_feature_collection(projected, spatial_index);
+        }
+        layers
+    }
+
+    /// `layers()` after moving some features and adding others, which updates the spatial
+    /// indexes instead of rebuilding them.
+    fn edited_layers() -> crate::Layers {
+        let mut layers = layers();
+        let layer_ids: Vec<_> = layers.iter_top_to_bottom().map(|layer| layer.id).collect();
+        for layer_id in layer_ids {
+            let layer = layers.get_mut(layer_id).unwrap();
+            let feature_ids: Vec<_> = layer
+                .unprojected_feature_collection
+                .iter()
+                .map(|feature| feature.id)
+                .step_by(4)
+                .collect();
+            for (i, feature_id) in (0_i32..).zip(feature_ids) {
+                let x = f64::from(i) * 2.;
+                let geometry = geo::Geometry::from(geo::Rect::new((x, 30.), (x + 3., 33.)));
+                assert!(layer.set_feature_geometry(
+                    feature_id,
+                    geometry.clone().wrap(),
+                    geometry.wrap()
+                ));
+            }
+            for i in 0..5_i32 {
+                let x = f64::from(i) * 5.;
+                let geometry = geo::Geometry::from(geo::line_string![
+                    (x: x, y: 20.),
+                    (x: x + 6., y: 26.),
+                ]);
+                layer.push_feature(
+                    geometry.clone().wrap(),
+                    geometry.wrap(),
+                    geo_features::Properties::from([(
+                        "i".to_owned(),
+                        geo_features::Value::Integer(i.into()),
+                    )]),
+                );
+            }
+        }
+        layers
+    }
+
+    fn coord(x: f64, y: f64) -> geo_projected::ProjectedCoord {
+        geo::Coord { x, y }.wrap()
+    }
+
+    /// Feature IDs of the layer's projected features, found by scanning every feature.
+    fn scan(
+        layer: &crate::Layer,
+        predicate: impl Fn(&geo_features::Feature<geo_projected::ProjectedScalar>) -> bool,
+    ) -> Vec<geo_features::FeatureId> {
+        layer
+            .projected_feature_collection
+            .iter()
+            .flat_map(|projected| projected.iter())
+            .filter(|feature| layer.matches_filter(feature) && predicate(feature))
+            .map(|feature| feature.id)
+            .collect()
+    }
+
+    fn coords() -> impl Iterator<Item = geo_projected::ProjectedCoord> {
+        (0..80_i32)
+            .flat_map(|x| (0..80_i32).map(move |y| coord(f64::from(x) * 0.5, f64::from(y) * 0.5)))
+    }
+
+    #[test]
+    fn test_feature_from_click() {
+        for (layers, coord) in [layers(), edited_layers()]
+            .iter()
+            .flat_map(|layers| coords().map(move |coord| (layers, coord)))
+        {
+            let expected = layers.iter_top_to_bottom().find_map(|layer| {
+                let feature_id = *scan(layer, |feature| feature.contains(&coord)).first()?;
+                Some((layer.id, feature_id))
+            });
+            let actual = layers
+                .feature_from_click(coord)
+                .map(|(layer, feature)| (layer.id, feature.id));
+            assert_eq!(actual, expected, "at {coord:?}");
+        }
+    }
+
+    #[test]
+    fn test_containing_coord() {
+        for (layers, coord) in [layers(), edited_layers()]
+            .iter()
+            .flat_map(|layers| coords().map(move |coord| (layers, coord)))
+        {
+            let expected: Vec<_> = layers
+                .iter_top_to_bottom()
+                .filter(|layer| !scan(layer, |feature| feature.contains(&coord)).is_empty())
+                .map(|layer| layer.id)
+                .collect();
+            let actual: Vec<_> = layers
+                .containing_coord(coord)
+                .map(|layer| layer.id)
+                .collect();
+            assert_eq!(actual, expected, "at {coord:?}");
+        }
+    }
+
+    #[test]
+    fn test_features_in_rect() {
+        for (layers, (min, max)) in [layers(), edited_layers()].iter().flat_map(|layers| {
+            [
+                ((0., 0.), (1., 1.)),
+                ((5., 2.), (12., 9.)),
+                ((2., 19.), (21., 31.)),
+                ((-5., -5.), (50., 50.)),
+            ]
+            .map(|rect| (layers, rect))
+        }) {
+            let rect = geo::Rect::new(coord(min.0, min.1), coord(max.0, max.1));
+            let expected: collections::BTreeSet<_> = layers
+                .iter_top_to_bottom()
+                .flat_map(|layer| {
+                    scan(layer, |feature| {
+                        feature
+                            .bounding_rect
+                            .is_some_and(|bounding_rect| bounding_rect.intersects(&rect))
+                    })
+                    .into_iter()
+                    .map(move |feature_id| (layer.id, feature_id))
+                })
+                .collect();
+            let actual: collections::BTreeSet<_> = layers
+                .features_in_rect(rect)
+                .map(|(layer, feature)| (layer.id, feature.id))
+                .collect();
+            assert!(!expected.is_empty());
+            assert_eq!(actual, expected, "in {rect:?}");
+        }
+    }
+}
@@ -1,4 +1,6 @@
 use bevy::prelude::*;
+use geo_projected::CastTo;
+use std::collections;
 
 fn handle_toggle_layer_visibility_events(
     mut toggle_layer_visibility_event_reader: EventReader<rgis_events::ToggleLayerVisibilityEvent>,
@@ -22,106 +24,223 @@ fn handle_toggle_layer_visibility_events(
 
 fn handle_update_color_events(
     mut update_events: EventReader<rgis_events::UpdateLayerColorEvent>,
-    mut updated_events: EventWriter<rgis_events::LayerColorUpdatedEvent>,
-    mut layers: ResMut<crate::Layers>,
+    mut changes: crate::history::Changes,
 ) {
     for event in update_events.read() {
-        let event = match event {
+        changes.apply(match *event {
             rgis_events::UpdateLayerColorEvent::Stroke(layer_id, color) => {
-                let Some(layer) = layers.get_mut(*layer_id) else {
-                    bevy::log::warn!("Could not find layer");
-                    continue;
-                };
-                layer.color.stroke = *color;
-                rgis_events::LayerColorUpdatedEvent::Stroke(*layer_id)
+                crate::history::Change::StrokeColor(layer_id, color)
             }
             rgis_events::UpdateLayerColorEvent::Fill(layer_id, color) => {
-                let Some(layer) = layers.get_mut(*layer_id) else {
-                    bevy::log::warn!("Could not find layer");
-                    continue;
-                };
-                layer.color.fill = Some(*color);
-                rgis_events::LayerColorUpdatedEvent::Fill(*layer_id)
+                crate::history::Change::FillColor(layer_id, Some(color))
             }
+        });
+    }
+}
+
+fn handle_rename_layer_events(
+    mut rename_layer_events: ResMut<bevy::ecs::event::Events<rgis_events::RenameLayerEvent>>,
+    mut changes: crate::history::Changes,
+) {
+    for rgis_events::RenameLayerEvent(layer_id, name) in rename_layer_events.drain() {
+        changes.apply(crate::history::Change::Rename(layer_id, name));
+    }
+}
+
+fn handle_update_filter_events(
+    mut update_events: ResMut<bevy::ecs::event::Events<rgis_events::UpdateLayerFilterEvent>>,
+    mut updated_events: EventWriter<rgis_events::LayerFilterUpdatedEvent>,
+    mut selection_updated_event_writer: EventWriter<rgis_events::SelectionUpdatedEvent>,
+    mut layers: ResMut<crate::Layers>,
+) {
+    for rgis_events::UpdateLayerFilterEvent(layer_id, filter) in update_events.drain() {
+        let Some(layer) = layers.get_mut(layer_id) else {
+            bevy::log::warn!("Could not find layer");
+            continue;
         };
-        updated_events.write(event);
+        layer.set_filter(filter);
+        updated_events.write(rgis_events::LayerFilterUpdatedEvent(layer_id));
+
+        // Features hidden by the filter can't stay selected
+        let selected_count = layer.selected_feature_ids.len();
+        let selected_feature_ids = layer
+            .selected_unprojected_features()
+            .filter(|feature| layer.matches_filter(feature))
+            .map(|feature| feature.id)
+            .collect();
+        layer.selected_feature_ids = selected_feature_ids;
+        if layer.selected_feature_ids.len() != selected_count {
+            selection_updated_event_writer.write(rgis_events::SelectionUpdatedEvent(layer_id));
+        }
     }
 }
 
 fn handle_delete_layer_events(
     mut delete_layer_event_reader: EventReader<rgis_events::DeleteLayerEvent>,
-    mut despawn_meshes_event_writer: EventWriter<rgis_events::DespawnMeshesEvent>,
-    mut layers: ResMut<crate::Layers>,
+    mut changes: crate::history::Changes,
 ) {
     for event in delete_layer_event_reader.read() {
-        layers.remove(event.0);
-        despawn_meshes_event_writer.write(rgis_events::DespawnMeshesEvent(event.0));
+        changes.apply(crate::history::Change::DeleteLayer(event.0));
     }
 }
 
 fn handle_move_layer_events(
     mut move_layer_event_reader: EventReader<rgis_events::MoveLayerEvent>,
-    mut layer_z_index_updated_event_writer: EventWriter<rgis_events::LayerZIndexUpdatedEvent>,
-    mut layers: ResMut<crate::Layers>,
+    mut changes: crate::history::Changes,
 ) {
     for event in move_layer_event_reader.read() {
-        let old_z_index = match layers.get_with_index(event.0) {
-            Some(result) => result.1 .0,
-            None => {
-                bevy::log::warn!("Could not find layer");
-                continue;
-            }
-        };
-
-        let new_z_index = match event.1 {
-            rgis_events::MoveDirection::Up => {
-                if old_z_index < layers.count() - 1 {
-                    old_z_index + 1
-                } else {
-                    old_z_index
-                }
-            }
-            rgis_events::MoveDirection::Down => {
-                if old_z_index > 0 {
-                    old_z_index - 1
-                } else {
-                    old_z_index
-                }
-            }
-        };
-        if new_z_index != old_z_index {
-            let Some(other_layer_id) = layers.data.get(new_z_index).map(|l| l.id) else {
-                bevy::log::warn!("Could not find layer");
-                continue;
-            };
-
-            layers.data.swap(old_z_index, new_z_index);
+        changes.apply(crate::history::Change::MoveLayer(event.0, event.1));
+    }
+}
 
-            layer_z_index_updated_event_writer.write(rgis_events::LayerZIndexUpdatedEvent(event.0));
-            layer_z_index_updated_event_writer
-                .write(rgis_events::LayerZIndexUpdatedEvent(other_layer_id));
+/// Records CRS changes, which are applied by `rgis_settings`, so they can be undone.
+fn handle_change_crs_events(
+    mut change_crs_event_reader: EventReader<rgis_events::ChangeCrsEvent>,
+    mut changes: crate::history::Changes,
+) {
+    for event in change_crs_event_reader.read() {
+        if event.old_crs_epsg_code != event.new_crs_epsg_code {
+            changes.record(crate::history::Change::TargetCrs(event.old_crs_epsg_code));
         }
     }
 }
 
+fn handle_undo_redo_events(
+    mut undo_event_reader: EventReader<rgis_events::UndoEvent>,
+    mut redo_event_reader: EventReader<rgis_events::RedoEvent>,
+    mut changes: crate::history::Changes,
+) {
+    for _ in undo_event_reader.read() {
+        changes.undo();
+    }
+    for _ in redo_event_reader.read() {
+        changes.redo();
+    }
+}
+
 fn handle_map_clicked_events(
     mut map_clicked_event_reader: EventReader<rgis_events::MapClickedEvent>,
     mut render_message_event_writer: EventWriter<rgis_events::RenderFeaturePropertiesEvent>,
-    mut feature_clicked_event_writer: EventWriter<rgis_events::FeatureSelectedEvent>,
+    mut update_selection_event_writer: EventWriter<rgis_events::UpdateSelectionEvent>,
+    layers: Res<crate::Layers>,
+) {
+    for rgis_events::MapClickedEvent(coord, mode) in map_clicked_event_reader.read() {
+        match layers.feature_from_click(*coord) {
+            Some((layer, feature)) => {
+                render_message_event_writer.write(rgis_events::RenderFeaturePropertiesEvent {
+                    layer_id: layer.id,
+                    feature_id: feature.id,
+                    properties: feature.properties.clone(),
+                });
+                update_selection_event_writer.write(rgis_events::UpdateSelectionEvent::Features {
+                    layer_id: layer.id,
+                    feature_ids: vec![feature.id],
+                    mode: *mode,
+                });
+            }
+            // Clicking on an empty spot of the map deselects everything
+            None if *mode == rgis_events::SelectionMode::Replace => {
+                update_selection_event_writer.write(rgis_events::UpdateSelectionEvent::ClearAll);
+            }
+            None => (),
+        }
+    }
+}
+
+fn handle_map_area_selected_events(
+    mut map_area_selected_event_reader: EventReader<rgis_events::MapAreaSelectedEvent>,
+    mut update_selection_event_writer: EventWriter<rgis_events::UpdateSelectionEvent>,
     layers: Res<crate::Layers>,
 ) {
-    for event in map_clicked_event_reader.read() {
-        if let Some((layer, feature)) = layers.feature_from_click(event.0) {
-            render_message_event_writer.write(rgis_events::RenderFeaturePropertiesEvent {
-                layer_id: layer.id,
-                properties: feature.properties.clone(),
+    for event in map_area_selected_event_reader.read() {
+        let feature_ids_by_layer = layers.features_in_area(&event.area, event.predicate);
+
+        // Replacing per layer would deselect what was just picked in the other layers
+        let mode = if event.mode == rgis_events::SelectionMode::Replace {
+            update_selection_event_writer.write(rgis_events::UpdateSelectionEvent::ClearAll);
+            rgis_events::SelectionMode::Add
+        } else {
+            event.mode
+        };
+        for (layer_id, feature_ids) in feature_ids_by_layer {
+            update_selection_event_writer.write(rgis_events::UpdateSelectionEvent::Features {
+                layer_id,
+                feature_ids,
+                mode,
             });
-            feature_clicked_event_writer
-                .write(rgis_events::FeatureSelectedEvent(layer.id, feature.id));
         }
     }
 }
 
+fn handle_update_selection_events(
+    mut update_selection_event_reader: EventReader<rgis_events::UpdateSelectionEvent>,
+    mut selection_updated_event_writer: EventWriter<rgis_events::SelectionUpdatedEvent>,
+    mut layers: ResMut<crate::Layers>,
+) {
+    let mut updated_layer_ids = collections::BTreeSet::new();
+    for event in update_selection_event_reader.read() {
+        if matches!(
+            event,
+            rgis_events::UpdateSelectionEvent::Features {
+                mode: rgis_events::SelectionMode::Replace,
+                ..
+            } | rgis_events::UpdateSelectionEvent::ClearAll
+        ) {
+            for layer in layers.data.iter_mut() {
+                if !layer.selected_feature_ids.is_empty() {
+                    layer.selected_feature_ids.clear();
+                    updated_layer_ids.insert(layer.id);
+                }
+            }
+        }
+
+        let layer_id = match event {
+            rgis_events::UpdateSelectionEvent::Features { layer_id, .. }
+            | rgis_events::UpdateSelectionEvent::All(layer_id)
+            | rgis_events::UpdateSelectionEvent::Invert(layer_id)
+            | rgis_events::UpdateSelectionEvent::Clear(layer_id) => *layer_id,
+            rgis_events::UpdateSelectionEvent::ClearAll => continue,
+        };
+        let Some(layer) = layers.get_mut(layer_id) else {
+            bevy::log::warn!("Could not find layer");
+            continue;
+        };
+
+        match event {
+            rgis_events::UpdateSelectionEvent::Features {
+                feature_ids, mode, ..
+            } => {
+                for feature_id in feature_ids {
+                    let is_newly_selected = layer.selected_feature_ids.insert(*feature_id);
+                    if !is_newly_selected && *mode == rgis_events::SelectionMode::Toggle {
+                        layer.selected_feature_ids.remove(feature_id);
+                    }
+                }
+            }
+            rgis_events::UpdateSelectionEvent::All(_) => {
+                layer.selected_feature_ids = layer
+                    .filtered_unprojected_features()
+                    .map(|feature| feature.id)
+                    .collect();
+            }
+            rgis_events::UpdateSelectionEvent::Invert(_) => {
+                layer.selected_feature_ids = layer
+                    .filtered_unprojected_features()
+                    .map(|feature| feature.id)
+                    .filter(|feature_id| !layer.selected_feature_ids.contains(feature_id))
+                    .collect();
+            }
+            rgis_events::UpdateSelectionEvent::Clear(_)
+            | rgis_events::UpdateSelectionEvent::ClearAll => layer.selected_feature_ids.clear(),
+        }
+        updated_layer_ids.insert(layer_id);
+    }
+
+    for layer_id in updated_layer_ids {
+        selection_updated_event_writer.write(rgis_events::SelectionUpdatedEvent(layer_id));
+    }
+}
+
 fn handle_create_layer_events(
     mut create_layer_events: ResMut<bevy::ecs::event::Events<rgis_events::CreateLayerEvent>>,
     mut layer_created_event_writer: EventWriter<rgis_events::LayerCreatedEvent>,
@@ -137,16 +256,182 @@ fn handle_create_layer_events(
     }
 }
 
+fn handle_features_page_loaded_events(
+    mut page_loaded_events: ResMut<bevy::ecs::event::Events<rgis_events::FeaturesPageLoadedEvent>>,
+    mut layer_created_event_writer: EventWriter<rgis_events::LayerCreatedEvent>,
+    mut reproject_layer_event_writer: EventWriter<rgis_events::ReprojectLayerEvent>,
+    mut layers: ResMut<crate::Layers>,
+) {
+    for event in page_loaded_events.drain() {
+        if event.is_first_page {
+            layers.add_with_id(
+                event.layer_id,
+                event.feature_collection,
+                event.name,
+                event.source_crs_epsg_code,
+            );
+            layer_created_event_writer.write(rgis_events::LayerCreatedEvent(event.layer_id));
+            continue;
+        }
+        // The layer may have been removed while the page was fetched
+        let Some(layer) = layers.get_mut(event.layer_id) else {
+            continue;
+        };
+        layer.append_features(event.feature_collection);
+        reproject_layer_event_writer.write(rgis_events::ReprojectLayerEvent(layer.id));
+    }
+}
+
+fn handle_add_feature_events(
+    mut add_feature_events: ResMut<bevy::ecs::event::Events<rgis_events::AddFeatureEvent>>,
+    mut layer_created_event_writer: EventWriter<rgis_events::LayerCreatedEvent>,
+    mut layer_features_updated_event_writer: EventWriter<rgis_events::LayerFeaturesUpdatedEvent>,
+    mut reproject_layer_event_writer: EventWriter<rgis_events::ReprojectLayerEvent>,
+    mut layers: ResMut<crate::Layers>,
+    rgis_settings: Res<rgis_settings::RgisSettings>,
+) {
+    let target_crs_epsg_code = rgis_settings.target_crs_epsg_code;
+    for rgis_events::AddFeatureEvent {
+        geometry,
+        properties,
+    } in add_feature_events.drain()
+    {
+        let scratch_layer_id = layers.scratch_layer_id;
+        let Some(layer) = scratch_layer_id.and_then(|layer_id| layers.get_mut(layer_id)) else {
+            // Create the scratch layer in the target CRS, so the geometry is as drawn
+            let feature = geo_features::FeatureBuilder::new()
+                .with_geometry(geometry.cast())
+                .with_properties(properties)
+                .build();
+            let layer_id = layers.add(
+                geo_features::FeatureCollection::from_feature(feature),
+                "Scratch".into(),
+                target_crs_epsg_code,
+            );
+            layers.scratch_layer_id = Some(layer_id);
+            layer_created_event_writer.write(rgis_events::LayerCreatedEvent(layer_id));
+            continue;
+        };
+
+        let unprojected_geometry =
+            match unproject_geometry(&geometry, target_crs_epsg_code, layer.crs_epsg_code) {
+                Ok(unprojected_geometry) => unprojected_geometry,
+                Err(e) => {
+                    bevy::log::error!("Could not convert the feature to the layer's CRS: {e}");
+                    continue;
+                }
+            };
+        layer.push_feature(unprojected_geometry, geometry, properties);
+        if layer.is_active() {
+            layer_features_updated_event_writer
+                .write(rgis_events::LayerFeaturesUpdatedEvent(layer.id));
+        } else {
+            // The layer is still being projected without the new feature
+            reproject_layer_event_writer.write(rgis_events::ReprojectLayerEvent(layer.id));
+        }
+    }
+}
+
+fn handle_update_feature_geometry_events(
+    mut update_events: ResMut<bevy::ecs::event::Events<rgis_events::UpdateFeatureGeometryEvent>>,
+    mut changes: crate::history::Changes,
+) {
+    for rgis_events::UpdateFeatureGeometryEvent {
+        layer_id,
+        feature_id,
+        geometry,
+    } in update_events.drain()
+    {
+        let Some(layer) = changes.layers().get(layer_id) else {
+            continue;
+        };
+        let geometry = match unproject_geometry(
+            &geometry,
+            changes.target_crs_epsg_code(),
+            layer.crs_epsg_code,
+        ) {
+            Ok(geometry) => geometry,
+            Err(e) => {
+                bevy::log::error!("Could not convert the geometry to the layer's CRS: {e}");
+                continue;
+            }
+        };
+        changes.apply(crate::history::Change::FeatureGeometry {
+            layer_id,
+            feature_id,
+            geometry,
+            revisions: None,
+        });
+    }
+}
+
+fn handle_update_feature_properties_events(
+    mut update_events: ResMut<bevy::ecs::event::Events<rgis_events::UpdateFeaturePropertiesEvent>>,
+    mut changes: crate::history::Changes,
+) {
+    for rgis_events::UpdateFeaturePropertiesEvent {
+        layer_id,
+        feature_id,
+        properties,
+    } in update_events.drain()
+    {
+        changes.apply(crate::history::Change::FeatureProperties {
+            layer_id,
+            feature_id,
+            properties,
+            revisions: None,
+        });
+    }
+}
+
+/// Converts a geometry from the target CRS back to the CRS of a layer.
+pub(crate) fn unproject_geometry(
+    geometry: &geo::Geometry<geo_projected::ProjectedScalar>,
+    target_crs_epsg_code: u16,
+    layer_crs_epsg_code: u16,
+) -> Result<geo::Geometry<geo_projected::UnprojectedScalar>, geo_geodesy::Error> {
+    let mut geometry = geometry.clone().cast();
+    if target_crs_epsg_code != layer_crs_epsg_code {
+        geo_geodesy::Transformer::setup(target_crs_epsg_code, layer_crs_epsg_code)?
+            .transform(&mut geometry)?;
+    }
+    Ok(geometry)
+}
+
+/// Converts a geometry from the CRS of a layer to the target CRS.
+pub(crate) fn project_geometry(
+    geometry: &geo::Geometry<geo_projected::UnprojectedScalar>,
+    layer_crs_epsg_code: u16,
+    target_crs_epsg_code: u16,
+) -> Result<geo::Geometry<geo_projected::ProjectedScalar>, geo_geodesy::Error> {
+    let mut geometry = geometry.clone();
+    if layer_crs_epsg_code != target_crs_epsg_code {
+        geo_geodesy::Transformer::setup(layer_crs_epsg_code, target_crs_epsg_code)?
+            .transform(&mut geometry)?;
+    }
+    Ok(geometry.cast())
+}
+
 pub fn configure(app: &mut App) {
     app.add_systems(
         Update,
         (
             handle_toggle_layer_visibility_events,
             handle_update_color_events,
+            handle_update_filter_events,
             handle_move_layer_events,
             handle_delete_layer_events,
             handle_map_clicked_events,
+            handle_map_area_selected_events,
+            handle_update_selection_events,
             handle_create_layer_events,
+            handle_features_page_loaded_events,
+            handle_add_feature_events,
+            handle_update_feature_geometry_events,
+            handle_update_feature_properties_events,
+            handle_rename_layer_events,
+            handle_change_crs_events,
+            handle_undo_redo_events,
         ),
     );
 }
@@ -7,21 +7,299 @@
 )]
 
 use bevy::prelude::*;
-use geo_projected::ProjectedScalar;
+use geo::{Distance, GeodesicArea};
+use geo_projected::{ProjectedCoord, ProjectedScalar};
+use std::collections;
 
+mod snapping;
 mod systems;
+mod vertices;
 
 #[derive(Clone, Resource)]
 pub struct MousePos(pub geo::Coord<ProjectedScalar>);
 
+/// Shape being drawn with the rectangle or lasso selection tool, in window coordinates. For the
+/// rectangle tool, the first and last points are opposite corners.
+#[derive(Default, Resource)]
+struct SelectionArea(Vec<Vec2>);
+
+/// Where the cursor snaps to while drawing or editing vertices.
+#[derive(Default, Resource)]
+struct Snap(Option<snapping::SnapTarget>);
+
+impl Snap {
+    fn coord(&self) -> Option<ProjectedCoord> {
+        self.0.map(|target| geo::Coord {
+            x: num_t::Num::new(target.coord.x),
+            y: num_t::Num::new(target.coord.y),
+        })
+    }
+
+    /// The snap target, or `coord` when there's none.
+    fn snapped(&self, coord: ProjectedCoord) -> ProjectedCoord {
+        self.coord().unwrap_or(coord)
+    }
+}
+
+/// Snapping indexes of the features with many coordinates that the cursor came near, by layer and
+/// feature, dropped when the feature's geometry changes.
+#[derive(Default, Resource)]
+struct SnapIndexes(
+    collections::HashMap<
+        (rgis_layer_id::LayerId, geo_features::FeatureId),
+        snapping::GeometryIndex,
+    >,
+);
+
+/// Vertices clicked so far with a drawing tool.
+#[derive(Default, Resource)]
+struct Sketch {
+    /// The tool and CRS the vertices were clicked with
+    tool: Option<rgis_settings::Tool>,
+    epsg_code: u16,
+    vertices: Vec<ProjectedCoord>,
+}
+
+/// Shape being measured with the measure tool, and its geodesic measurements.
+#[derive(Default, Resource)]
+pub struct Measurement {
+    /// Clicked vertices, in the CRS `epsg_code`
+    vertices: Vec<ProjectedCoord>,
+    /// Position of the cursor while the shape is still being drawn, measured as its next vertex
+    cursor: Option<ProjectedCoord>,
+    epsg_code: u16,
+    /// Whether the shape was closed by clicking its first vertex
+    is_closed: bool,
+    /// Whether the shape was finished, after which the next click starts a new one
+    is_finished: bool,
+    /// Geodesic length of each segment, in meters
+    segment_lengths: Vec<f64>,
+    /// Geodesic area enclosed by the shape once it's closed, in square meters
+    area: Option<f64>,
+    /// Transformer from the CRS `epsg_code` to WGS 84, kept across cursor moves along with the
+    /// EPSG code it was set up for
+    transformer: Option<(u16, geo_geodesy::Transformer)>,
+}
+
+impl Measurement {
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.is_closed
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.is_finished
+    }
+
+    /// Geodesic length of each segment in meters, including the one to the cursor while drawing
+    /// and the closing one of a closed shape.
+    pub fn segment_lengths(&self) -> &[f64] {
+        &self.segment_lengths
+    }
+
+    /// Total geodesic length in meters, which is the perimeter of a closed shape.
+    pub fn total_length(&self) -> f64 {
+        self.segment_lengths.iter().sum()
+    }
+
+    /// Geodesic area in square meters enclosed by the shape, if it's closed.
+    pub fn area(&self) -> Option<f64> {
+        self.area
+    }
+
+    pub fn clear(&mut self) {
+        *self = Measurement {
+            transformer: self.transformer.take(),
+            ..Measurement::default()
+        };
+    }
+
+    /// The vertices followed by the cursor, or the first vertex again if the shape is closed.
+    fn measured_coords(&self) -> Vec<ProjectedCoord> {
+        let mut coords = self.vertices.clone();
+        if self.is_closed {
+            coords.extend(self.vertices.first().copied());
+        } else if !self.is_finished {
+            coords.extend(self.cursor);
+        }
+        coords
+    }
+
+    /// Recompute the measurements on the ellipsoid, independent of the CRS of the vertices.
+    fn remeasure(&mut self) -> Result<(), geo_geodesy::Error> {
+        let mut geometry: geo::Geometry<ProjectedScalar> =
+            geo::LineString::new(self.measured_coords()).into();
+        let transformer = match self.transformer.take() {
+            Some((epsg_code, transformer)) if epsg_code == self.epsg_code => transformer,
+            _ => geo_geodesy::Transformer::setup(self.epsg_code, 4326)?,
+        };
+        let result = transformer.transform(&mut geometry);
+        self.transformer = Some((self.epsg_code, transformer));
+        result?;
+        let geo::Geometry::LineString(line_string) = geometry else {
+            return Ok(());
+        };
+        let coords = line_string
+            .coords()
+            .map(|coord| geo::Point::new(coord.x.0, coord.y.0))
+            .collect::<Vec<_>>();
+
+        self.segment_lengths = coords
+            .windows(2)
+            .filter_map(|segment| match segment {
+                [a, b] => Some(geo::Geodesic.distance(*a, *b)),
+                _ => None,
+            })
+            .collect();
+        self.area = self.is_closed.then(|| {
+            geo::Polygon::new(geo::LineString::from(coords), vec![]).geodesic_area_unsigned()
+        });
+        Ok(())
+    }
+}
+
+/// Feature whose vertices are edited with the vertex editing tool.
+#[derive(Default, Resource)]
+pub struct VertexEditor {
+    layer_id: Option<rgis_layer_id::LayerId>,
+    /// `None` until a single feature of the layer is selected
+    feature: Option<EditedFeature>,
+}
+
+struct EditedFeature {
+    feature_id: geo_features::FeatureId,
+    /// The geometry before it was edited, in the target CRS, which the parts are put back into
+    geometry: geo::Geometry<ProjectedScalar>,
+    parts: Vec<vertices::Part<ProjectedScalar>>,
+    drag: Option<VertexDrag>,
+}
+
+impl EditedFeature {
+    fn edited_geometry(&self) -> geo::Geometry<ProjectedScalar> {
+        vertices::recompose(&self.geometry, self.parts.clone())
+    }
+}
+
+/// Vertex being dragged, as the index of its part and its index in the part.
+#[derive(Clone, Copy)]
+struct VertexDrag {
+    part: usize,
+    index: usize,
+    has_moved: bool,
+}
+
+impl VertexEditor {
+    pub fn layer_id(&self) -> Option<rgis_layer_id::LayerId> {
+        self.layer_id
+    }
+
+    pub fn feature_id(&self) -> Option<geo_features::FeatureId> {
+        self.feature.as_ref().map(|feature| feature.feature_id)
+    }
+}
+
 pub struct Plugin;
 
 impl bevy::app::Plugin for Plugin {
     fn build(&self, app: &mut bevy::app::App) {
         systems::configure(app);
+        app.init_resource::<SelectionArea>();
+        app.init_resource::<Measurement>();
+        app.init_resource::<Sketch>();
+        app.init_resource::<VertexEditor>();
+        app.init_resource::<Snap>();
+        app.init_resource::<SnapIndexes>();
         app.insert_resource(MousePos(geo::Coord {
             x: num_t::Num::new(0.),
             y: num_t::Num::new(0.),
         }));
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use geo_projected::WrapTo;
+
+    /// Length of a degree of longitude along the equator, in meters
+    const DEGREE: f64 = 111_319.490_793;
+
+    fn measurement(epsg_code: u16, vertices: &[(f64, f64)]) -> Measurement {
+        Measurement {
+            vertices: vertices
+                .iter()
+                .map(|&(x, y)| geo::Coord { x, y }.wrap())
+                .collect(),
+            epsg_code,
+            ..Measurement::default()
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() <= expected * 1e-6,
+            "{actual} != {expected}"
+        );
+    }
+
+    #[test]
+    fn test_open_shape() {
+        let mut measurement = measurement(4326, &[(0., 0.), (1., 0.), (1., 1.)]);
+        measurement.is_finished = true;
+        measurement.remeasure().unwrap();
+
+        assert_eq!(measurement.segment_lengths().len(), 2);
+        assert_close(*measurement.segment_lengths().first().unwrap(), DEGREE);
+        assert!(measurement.total_length() > 2. * DEGREE * 0.99);
+        assert_eq!(measurement.area(), None);
+    }
+
+    #[test]
+    fn test_closed_shape() {
+        let mut measurement = measurement(4326, &[(0., 0.), (1., 0.), (1., 1.), (0., 1.)]);
+        measurement.is_closed = true;
+        measurement.is_finished = true;
+        measurement.remeasure().unwrap();
+
+        // Including the closing segment
+        assert_eq!(measurement.segment_lengths().len(), 4);
+        // About a degree squared along the equator
+        let area = measurement.area().unwrap();
+        assert!((1.2e10..1.25e10).contains(&area), "{area}");
+    }
+
+    #[test]
+    fn test_cursor_is_measured_while_drawing() {
+        let mut measurement = measurement(3857, &[(0., 0.)]);
+        measurement.cursor = Some(geo::Coord { x: DEGREE, y: 0. }.wrap());
+        measurement.remeasure().unwrap();
+
+        assert_eq!(measurement.segment_lengths().len(), 1);
+        assert_close(measurement.total_length(), DEGREE);
+        assert_eq!(measurement.area(), None);
+    }
+
+    #[test]
+    fn test_transformer_is_kept_for_the_crs() {
+        let mut measurement = measurement(3857, &[(0., 0.), (DEGREE, 0.)]);
+        measurement.remeasure().unwrap();
+        assert!(matches!(measurement.transformer, Some((3857, _))));
+
+        measurement.clear();
+        assert!(measurement.is_empty());
+        assert!(matches!(measurement.transformer, Some((3857, _))));
+
+        measurement.epsg_code = 4326;
+        measurement.vertices = vec![
+            geo::Coord { x: 0., y: 0. }.wrap(),
+            geo::Coord { x: 1., y: 0. }.wrap(),
+        ];
+        measurement.remeasure().unwrap();
+        assert!(matches!(measurement.transformer, Some((4326, _))));
+        assert_close(measurement.total_length(), DEGREE);
+    }
+}
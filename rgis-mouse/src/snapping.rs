@@ -0,0 +1,369 @@
+//! Finding the vertex, segment intersection or segment nearby geometries the cursor snaps to.
+
+use geo::{CoordsIter, LinesIter};
+
+/// Segments considered for intersections, closest to the cursor first. Zoomed out, many segments
+/// can be within the tolerance, and each pair of them is intersected.
+const MAX_INTERSECTED_SEGMENTS: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SnapKind {
+    Vertex,
+    /// Where two segments cross
+    Intersection,
+    /// Closest point on a segment
+    Edge,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct SnapTarget {
+    pub coord: geo::Coord<f64>,
+    pub kind: SnapKind,
+}
+
+/// Features with at least this many coordinates get their vertices and segments indexed, instead
+/// of going through all of them on every cursor move.
+pub(crate) const MIN_INDEXED_COORDS: usize = 256;
+
+/// R-trees of the vertices and segments of a geometry.
+pub(crate) struct GeometryIndex {
+    vertices: rstar::RTree<[f64; 2]>,
+    segments: rstar::RTree<rstar::primitives::Line<[f64; 2]>>,
+}
+
+impl GeometryIndex {
+    /// `to_f64` converts the geometry's coordinates.
+    pub(crate) fn new<T: geo::CoordNum>(
+        geometry: &geo::Geometry<T>,
+        to_f64: impl Fn(geo::Coord<T>) -> geo::Coord<f64>,
+    ) -> Self {
+        GeometryIndex {
+            vertices: rstar::RTree::bulk_load(
+                geometry
+                    .coords_iter()
+                    .map(|coord| point(to_f64(coord)))
+                    .collect(),
+            ),
+            segments: rstar::RTree::bulk_load(
+                lines(geometry)
+                    .map(|line| {
+                        rstar::primitives::Line::new(
+                            point(to_f64(line.start)),
+                            point(to_f64(line.end)),
+                        )
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Vertices and segments within `tolerance` of `coord`, which it may snap to.
+pub(crate) struct Candidates {
+    coord: geo::Coord<f64>,
+    tolerance: f64,
+    vertices: Vec<geo::Coord<f64>>,
+    /// Segments along with their distance to `coord`
+    segments: Vec<(geo::Line<f64>, f64)>,
+}
+
+impl Candidates {
+    pub(crate) fn new(coord: geo::Coord<f64>, tolerance: f64) -> Self {
+        Candidates {
+            coord,
+            tolerance,
+            vertices: vec![],
+            segments: vec![],
+        }
+    }
+
+    fn add_vertex(&mut self, vertex: geo::Coord<f64>) {
+        if distance(self.coord, vertex) <= self.tolerance {
+            self.vertices.push(vertex);
+        }
+    }
+
+    fn add_segment(&mut self, line: geo::Line<f64>) {
+        let distance = distance(self.coord, closest_point(line, self.coord));
+        if distance <= self.tolerance {
+            self.segments.push((line, distance));
+        }
+    }
+
+    /// Adds the vertices and segments of `geometry`, going through all of them. `to_f64` converts
+    /// the geometry's coordinates.
+    pub(crate) fn add_geometry<T: geo::CoordNum>(
+        &mut self,
+        geometry: &geo::Geometry<T>,
+        to_f64: impl Fn(geo::Coord<T>) -> geo::Coord<f64>,
+    ) {
+        for vertex in geometry.coords_iter() {
+            self.add_vertex(to_f64(vertex));
+        }
+        for line in lines(geometry) {
+            self.add_segment(geo::Line::new(to_f64(line.start), to_f64(line.end)));
+        }
+    }
+
+    /// Adds the vertices and segments of an indexed geometry, looking up only the nearby ones.
+    pub(crate) fn add_indexed(&mut self, index: &GeometryIndex) {
+        let query_point = point(self.coord);
+        let max_squared_radius = self.tolerance * self.tolerance;
+        for vertex in index
+            .vertices
+            .locate_within_distance(query_point, max_squared_radius)
+        {
+            self.add_vertex(coord(*vertex));
+        }
+        for line in index
+            .segments
+            .locate_within_distance(query_point, max_squared_radius)
+        {
+            self.add_segment(geo::Line::new(coord(line.from), coord(line.to)));
+        }
+    }
+
+    /// Adds the vertices and segments of the parts of a geometry, except for the vertex at
+    /// `excluded`, given as the index of its part and its index in the part, and the segments
+    /// ending at it.
+    pub(crate) fn add_parts<T: geo::CoordNum>(
+        &mut self,
+        parts: &[crate::vertices::Part<T>],
+        excluded: Option<(usize, usize)>,
+        to_f64: impl Fn(geo::Coord<T>) -> geo::Coord<f64>,
+    ) {
+        for (part_index, part) in parts.iter().enumerate() {
+            let excluded = excluded
+                .filter(|(excluded_part_index, _)| *excluded_part_index == part_index)
+                .map(|(_, index)| index);
+            for (index, vertex) in part.coords.iter().enumerate() {
+                if Some(index) != excluded {
+                    self.add_vertex(to_f64(*vertex));
+                }
+            }
+            for (start, end) in part.segments() {
+                if excluded.is_some_and(|index| index == start || index == end) {
+                    continue;
+                }
+                if let (Some(start), Some(end)) = (part.coords.get(start), part.coords.get(end)) {
+                    self.add_segment(geo::Line::new(to_f64(*start), to_f64(*end)));
+                }
+            }
+        }
+    }
+
+    /// The candidate closest to the cursor, preferring vertices over intersections, and
+    /// intersections over edges.
+    pub(crate) fn snap(mut self) -> Option<SnapTarget> {
+        let coord = self.coord;
+        let tolerance = self.tolerance;
+        if let Some(coord) = self.vertices.iter().fold(None, |vertex, candidate| {
+            closer(vertex, coord, *candidate, tolerance)
+        }) {
+            return Some(SnapTarget {
+                coord,
+                kind: SnapKind::Vertex,
+            });
+        }
+
+        let segments = &mut self.segments;
+        segments.sort_unstable_by(|a, b| a.1.total_cmp(&b.1));
+        segments.truncate(MAX_INTERSECTED_SEGMENTS);
+        let mut intersection = None;
+        for (i, (a, _)) in segments.iter().enumerate() {
+            for (b, _) in segments.iter().skip(i + 1) {
+                if let Some(geo::LineIntersection::SinglePoint {
+                    intersection: candidate,
+                    ..
+                }) = geo::line_intersection::line_intersection(*a, *b)
+                {
+                    intersection = closer(intersection, coord, candidate, tolerance);
+                }
+            }
+        }
+        if let Some(coord) = intersection {
+            return Some(SnapTarget {
+                coord,
+                kind: SnapKind::Intersection,
+            });
+        }
+
+        segments.first().map(|(line, _)| SnapTarget {
+            coord: closest_point(*line, coord),
+            kind: SnapKind::Edge,
+        })
+    }
+}
+
+/// Segments of `geometry`, including those of the geometries in a collection.
+fn lines<T: geo::CoordNum>(
+    geometry: &geo::Geometry<T>,
+) -> Box<dyn Iterator<Item = geo::Line<T>> + '_> {
+    match geometry {
+        geo::Geometry::Point(_) | geo::Geometry::MultiPoint(_) => Box::new(std::iter::empty()),
+        geo::Geometry::Line(line) => Box::new(line.lines_iter()),
+        geo::Geometry::LineString(line_string) => Box::new(line_string.lines_iter()),
+        geo::Geometry::MultiLineString(multi_line_string) => {
+            Box::new(multi_line_string.lines_iter())
+        }
+        geo::Geometry::Polygon(polygon) => Box::new(polygon.lines_iter()),
+        geo::Geometry::MultiPolygon(multi_polygon) => Box::new(multi_polygon.lines_iter()),
+        geo::Geometry::Rect(rect) => Box::new(rect.lines_iter()),
+        geo::Geometry::Triangle(triangle) => Box::new(triangle.lines_iter()),
+        geo::Geometry::GeometryCollection(geometry_collection) => {
+            Box::new(geometry_collection.iter().flat_map(lines))
+        }
+    }
+}
+
+/// `candidate` if it's within `tolerance` of `coord` and closer than `current`.
+fn closer(
+    current: Option<geo::Coord<f64>>,
+    coord: geo::Coord<f64>,
+    candidate: geo::Coord<f64>,
+    tolerance: f64,
+) -> Option<geo::Coord<f64>> {
+    let candidate_distance = distance(coord, candidate);
+    if candidate_distance > tolerance
+        || current.is_some_and(|current| distance(coord, current) <= candidate_distance)
+    {
+        current
+    } else {
+        Some(candidate)
+    }
+}
+
+fn point(coord: geo::Coord<f64>) -> [f64; 2] {
+    [coord.x, coord.y]
+}
+
+fn coord([x, y]: [f64; 2]) -> geo::Coord<f64> {
+    geo::Coord { x, y }
+}
+
+fn distance(a: geo::Coord<f64>, b: geo::Coord<f64>) -> f64 {
+    (a.x - b.x).hypot(a.y - b.y)
+}
+
+fn closest_point(line: geo::Line<f64>, coord: geo::Coord<f64>) -> geo::Coord<f64> {
+    let delta = line.delta();
+    let length_squared = delta.x * delta.x + delta.y * delta.y;
+    if length_squared == 0. {
+        return line.start;
+    }
+    let t =
+        ((coord.x - line.start.x) * delta.x + (coord.y - line.start.y) * delta.y) / length_squared;
+    line.start + delta * t.clamp(0., 1.)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn cross() -> Vec<geo::Geometry<f64>> {
+        vec![
+            geo::LineString::from(vec![(0., 0.), (10., 10.)]).into(),
+            geo::LineString::from(vec![(0., 10.), (10., 0.)]).into(),
+        ]
+    }
+
+    fn snap_cross(x: f64, y: f64) -> Option<SnapTarget> {
+        let mut candidates = Candidates::new(geo::coord! { x: x, y: y }, 1.);
+        for geometry in cross() {
+            candidates.add_geometry(&geometry, |coord| coord);
+        }
+        candidates.snap()
+    }
+
+    #[test]
+    fn test_snap_vertex() {
+        assert_eq!(
+            snap_cross(0.5, 0.),
+            Some(SnapTarget {
+                coord: geo::coord! { x: 0., y: 0. },
+                kind: SnapKind::Vertex,
+            })
+        );
+    }
+
+    #[test]
+    fn test_snap_intersection() {
+        assert_eq!(
+            snap_cross(5.5, 5.),
+            Some(SnapTarget {
+                coord: geo::coord! { x: 5., y: 5. },
+                kind: SnapKind::Intersection,
+            })
+        );
+    }
+
+    #[test]
+    fn test_snap_edge() {
+        assert_eq!(
+            snap_cross(2., 3.),
+            Some(SnapTarget {
+                coord: geo::coord! { x: 2.5, y: 2.5 },
+                kind: SnapKind::Edge,
+            })
+        );
+        assert_eq!(snap_cross(5., 8.), None);
+    }
+
+    #[test]
+    fn test_indexed_geometry() {
+        let circle: geo::Geometry<f64> = geo::Polygon::new(
+            (0..400_i32)
+                .map(|i| {
+                    let angle = f64::from(i) * std::f64::consts::TAU / 400.;
+                    geo::coord! { x: 10. * angle.cos(), y: 10. * angle.sin() }
+                })
+                .collect(),
+            vec![],
+        )
+        .into();
+        let geometries = [circle, cross().remove(0)];
+        let indexes: Vec<_> = geometries
+            .iter()
+            .map(|geometry| GeometryIndex::new(geometry, |coord| coord))
+            .collect();
+
+        for i in -24..24_i32 {
+            for j in -24..24_i32 {
+                let coord = geo::coord! { x: f64::from(i) * 0.5, y: f64::from(j) * 0.5 + 0.1 };
+                let mut walked = Candidates::new(coord, 0.3);
+                let mut indexed = Candidates::new(coord, 0.3);
+                for (geometry, index) in geometries.iter().zip(&indexes) {
+                    walked.add_geometry(geometry, |coord| coord);
+                    indexed.add_indexed(index);
+                }
+                assert_eq!(indexed.snap(), walked.snap(), "at {coord:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_excluded_vertex() {
+        let parts = crate::vertices::decompose(&cross().remove(0)).unwrap();
+        let snap_parts = |x, y, excluded| {
+            let mut candidates = Candidates::new(geo::coord! { x: x, y: y }, 1.);
+            candidates.add_parts(&parts, excluded, |coord| coord);
+            candidates.snap()
+        };
+
+        assert_eq!(
+            snap_parts(0.5, 0., None).map(|target| target.kind),
+            Some(SnapKind::Vertex)
+        );
+        // Neither the dragged vertex nor the segment following it
+        assert_eq!(snap_parts(0.5, 0., Some((0, 0))), None);
+        assert_eq!(
+            snap_parts(9.5, 10., Some((0, 0))).map(|target| target.kind),
+            Some(SnapKind::Vertex)
+        );
+        assert_eq!(
+            snap_parts(9.5, 10., Some((1, 1))).map(|target| target.kind),
+            Some(SnapKind::Vertex)
+        );
+    }
+}
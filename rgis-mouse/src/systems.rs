@@ -1,4 +1,6 @@
 use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_egui::egui;
+use geo::CoordsIter;
 
 fn run_if_has_cursor_moved_events(
     cursor_moved_event_reader: bevy::ecs::event::EventReader<bevy::window::CursorMoved>,
@@ -103,7 +105,14 @@ fn mouse_motion_system(
     mouse_motion_event_reader.clear();
     let cursor_icon = match rgis_settings.current_tool {
         rgis_settings::Tool::Pan => bevy::window::SystemCursorIcon::Grab,
-        rgis_settings::Tool::Query => bevy::window::SystemCursorIcon::Crosshair,
+        rgis_settings::Tool::Query
+        | rgis_settings::Tool::SelectRect
+        | rgis_settings::Tool::SelectLasso
+        | rgis_settings::Tool::Measure
+        | rgis_settings::Tool::DrawPoint
+        | rgis_settings::Tool::DrawLine
+        | rgis_settings::Tool::DrawPolygon
+        | rgis_settings::Tool::EditVertices => bevy::window::SystemCursorIcon::Crosshair,
     };
     set_cursor_icon(&mut window, &mut last_cursor_icon, cursor_icon);
 }
@@ -135,8 +144,972 @@ fn current_tool_is_query(rgis_settings: Res<rgis_settings::RgisSettings>) -> boo
 fn mouse_click_system(
     mut map_clicked_event_writer: bevy::ecs::event::EventWriter<rgis_events::MapClickedEvent>,
     mouse_position: Res<crate::MousePos>,
+    keyboard: Res<ButtonInput<KeyCode>>,
 ) {
-    map_clicked_event_writer.write(rgis_events::MapClickedEvent(mouse_position.0));
+    map_clicked_event_writer.write(rgis_events::MapClickedEvent(
+        mouse_position.0,
+        rgis_events::SelectionMode::from_keyboard(&keyboard),
+    ));
+}
+
+fn current_tool_is_area_selection(rgis_settings: Res<rgis_settings::RgisSettings>) -> bool {
+    matches!(
+        rgis_settings.current_tool,
+        rgis_settings::Tool::SelectRect | rgis_settings::Tool::SelectLasso
+    )
+}
+
+/// Pixels the cursor has to move to extend a lasso, and the smallest size of a selection area,
+/// so clicks don't turn into selections.
+const MIN_DRAG_DISTANCE: f32 = 3.;
+
+fn area_selection_system(
+    mouse_button: Res<bevy::input::ButtonInput<bevy::input::mouse::MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    query: Query<&Transform, With<bevy::render::camera::Camera>>,
+    mut bevy_egui_ctx: bevy_egui::EguiContexts,
+    rgis_settings: Res<rgis_settings::RgisSettings>,
+    mut selection_area: ResMut<crate::SelectionArea>,
+    mut map_area_selected_event_writer: bevy::ecs::event::EventWriter<
+        rgis_events::MapAreaSelectedEvent,
+    >,
+) {
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let left = bevy::input::mouse::MouseButton::Left;
+
+    if mouse_button.just_pressed(left) {
+        selection_area.0.clear();
+        if let Some(cursor_position) = window.cursor_position() {
+            if !bevy_egui_ctx.ctx_mut().is_pointer_over_area() {
+                selection_area.0.push(cursor_position);
+            }
+        }
+        return;
+    }
+    if selection_area.0.is_empty() {
+        return;
+    }
+    if mouse_button.pressed(left) {
+        // Keep the shape as it was while the cursor is outside the window
+        let Some(cursor_position) = window.cursor_position() else {
+            return;
+        };
+        match rgis_settings.current_tool {
+            rgis_settings::Tool::SelectLasso => {
+                if selection_area
+                    .0
+                    .last()
+                    .is_none_or(|last| last.distance(cursor_position) >= MIN_DRAG_DISTANCE)
+                {
+                    selection_area.0.push(cursor_position);
+                }
+            }
+            _ => {
+                selection_area.0.truncate(1);
+                selection_area.0.push(cursor_position);
+            }
+        }
+        return;
+    }
+
+    // The button was released
+    let points = std::mem::take(&mut selection_area.0);
+    let Ok(transform) = query.single() else {
+        return;
+    };
+    let points = match rgis_settings.current_tool {
+        rgis_settings::Tool::SelectLasso => points,
+        _ => match (points.first(), points.last()) {
+            (Some(a), Some(b)) => vec![*a, Vec2::new(b.x, a.y), *b, Vec2::new(a.x, b.y)],
+            _ => return,
+        },
+    };
+    // Too small to be anything but a click
+    let (min, max) = points.iter().fold(
+        (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+        |(min, max), point| (min.min(*point), max.max(*point)),
+    );
+    if points.len() < 3 || (max - min).min_element() < MIN_DRAG_DISTANCE {
+        return;
+    }
+
+    let exterior = points
+        .into_iter()
+        .map(|point| {
+            rgis_units::ScreenCoord {
+                x: f64::from(point.x),
+                y: f64::from(point.y),
+            }
+            .to_projected_geo_coord(transform, window)
+        })
+        .collect::<Vec<_>>();
+    let alt = keyboard.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+    map_area_selected_event_writer.write(rgis_events::MapAreaSelectedEvent {
+        area: geo::Polygon::new(exterior.into(), vec![]),
+        predicate: if alt {
+            rgis_events::AreaSelectionPredicate::Within
+        } else {
+            rgis_events::AreaSelectionPredicate::Intersects
+        },
+        mode: rgis_events::SelectionMode::from_keyboard(&keyboard),
+    });
+}
+
+fn render_selection_area(
+    selection_area: Res<crate::SelectionArea>,
+    rgis_settings: Res<rgis_settings::RgisSettings>,
+    mut bevy_egui_ctx: bevy_egui::EguiContexts,
+) {
+    if selection_area.0.len() < 2 {
+        return;
+    }
+    let ctx = bevy_egui_ctx.ctx_mut();
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Background,
+        egui::Id::new("selection-area"),
+    ));
+    let stroke = egui::Stroke::new(1.5, ctx.style().visuals.selection.stroke.color);
+    let points = selection_area
+        .0
+        .iter()
+        .map(|point| egui::pos2(point.x, point.y))
+        .collect::<Vec<_>>();
+    match (rgis_settings.current_tool, points.first(), points.last()) {
+        (rgis_settings::Tool::SelectRect, Some(a), Some(b)) => {
+            let rect = egui::Rect::from_two_pos(*a, *b);
+            painter.rect_filled(
+                rect,
+                0.,
+                ctx.style().visuals.selection.bg_fill.gamma_multiply(0.3),
+            );
+            painter.rect_stroke(rect, 0., stroke, egui::StrokeKind::Inside);
+        }
+        _ => {
+            painter.add(egui::Shape::closed_line(points, stroke));
+        }
+    }
+}
+
+fn current_tool_is_measure(rgis_settings: Res<rgis_settings::RgisSettings>) -> bool {
+    rgis_settings.current_tool == rgis_settings::Tool::Measure
+}
+
+/// Seconds within which a second click on the same spot finishes a shape.
+const DOUBLE_CLICK_SECS: f64 = 0.4;
+
+/// Pixels from the first vertex within which a click closes a shape.
+const CLOSE_SHAPE_DISTANCE: f32 = 8.;
+
+/// Remembers a click at `position`, returning whether it's the second click of a double-click.
+fn register_click(last_click: &mut Option<(f64, Vec2)>, time: &Time, position: Vec2) -> bool {
+    let now = time.elapsed_secs_f64();
+    let is_double_click = last_click.is_some_and(|(secs, last_position)| {
+        now - secs <= DOUBLE_CLICK_SECS && last_position.distance(position) < MIN_DRAG_DISTANCE
+    });
+    *last_click = Some((now, position));
+    is_double_click
+}
+
+/// Position in the window of a coordinate on the map.
+fn screen_position(
+    coord: geo_projected::ProjectedCoord,
+    transform: &Transform,
+    window: &Window,
+) -> Vec2 {
+    let screen_coord = rgis_units::ScreenCoord::from_projected_geo_coord(coord, transform, window);
+    Vec2::new(screen_coord.x as f32, screen_coord.y as f32)
+}
+
+/// Pixels between a coordinate on the map and a position in the window.
+fn screen_distance(
+    coord: geo_projected::ProjectedCoord,
+    position: Vec2,
+    transform: &Transform,
+    window: &Window,
+) -> f32 {
+    screen_position(coord, transform, window).distance(position)
+}
+
+fn measure_system(
+    mouse_button: Res<bevy::input::ButtonInput<bevy::input::mouse::MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    query: Query<&Transform, With<bevy::render::camera::Camera>>,
+    mut bevy_egui_ctx: bevy_egui::EguiContexts,
+    rgis_settings: Res<rgis_settings::RgisSettings>,
+    time: Res<Time>,
+    mut measurement: ResMut<crate::Measurement>,
+    mut last_click: Local<Option<(f64, Vec2)>>,
+) {
+    let (Ok(window), Ok(transform)) = (windows.single(), query.single()) else {
+        return;
+    };
+
+    if keyboard.just_pressed(KeyCode::Escape)
+        || measurement.epsg_code != rgis_settings.target_crs_epsg_code
+    {
+        measurement.clear();
+        measurement.epsg_code = rgis_settings.target_crs_epsg_code;
+        return;
+    }
+    if keyboard.just_pressed(KeyCode::Enter) && !measurement.is_empty() {
+        measurement.is_finished = true;
+        measurement.cursor = None;
+        remeasure(&mut measurement);
+        return;
+    }
+
+    let cursor_position = window
+        .cursor_position()
+        .filter(|_| !bevy_egui_ctx.ctx_mut().is_pointer_over_area());
+    let Some(cursor_position) = cursor_position else {
+        return;
+    };
+    let cursor = rgis_units::ScreenCoord {
+        x: f64::from(cursor_position.x),
+        y: f64::from(cursor_position.y),
+    }
+    .to_projected_geo_coord(transform, window);
+
+    if !mouse_button.just_pressed(bevy::input::mouse::MouseButton::Left) {
+        if !measurement.is_finished && !measurement.is_empty() && measurement.cursor != Some(cursor)
+        {
+            measurement.cursor = Some(cursor);
+            remeasure(&mut measurement);
+        }
+        return;
+    }
+
+    let is_double_click = register_click(&mut last_click, &time, cursor_position);
+
+    if measurement.is_finished {
+        if is_double_click {
+            // The second click of the double-click that finished the shape
+            return;
+        }
+        measurement.clear();
+        measurement.epsg_code = rgis_settings.target_crs_epsg_code;
+    }
+
+    let closes_shape = measurement.vertices.len() >= 3
+        && measurement.vertices.first().is_some_and(|first| {
+            screen_distance(*first, cursor_position, transform, window) < CLOSE_SHAPE_DISTANCE
+        });
+    if closes_shape {
+        measurement.is_closed = true;
+        measurement.is_finished = true;
+    } else if is_double_click && !measurement.is_empty() {
+        // The first click of the double-click already added the vertex
+        measurement.is_finished = true;
+    } else {
+        measurement.vertices.push(cursor);
+    }
+    measurement.cursor = (!measurement.is_finished).then_some(cursor);
+    remeasure(&mut measurement);
+}
+
+fn remeasure(measurement: &mut crate::Measurement) {
+    if let Err(e) = measurement.remeasure() {
+        bevy::log::error!("Failed to measure the shape: {e}");
+    }
+}
+
+fn render_measurement(
+    measurement: Res<crate::Measurement>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    query: Query<&Transform, With<bevy::render::camera::Camera>>,
+    mut bevy_egui_ctx: bevy_egui::EguiContexts,
+) {
+    let (Ok(window), Ok(transform)) = (windows.single(), query.single()) else {
+        return;
+    };
+    let points = measurement
+        .measured_coords()
+        .into_iter()
+        .map(|coord| {
+            let screen_coord =
+                rgis_units::ScreenCoord::from_projected_geo_coord(coord, transform, window);
+            egui::pos2(screen_coord.x as f32, screen_coord.y as f32)
+        })
+        .collect::<Vec<_>>();
+    if points.is_empty() {
+        return;
+    }
+    let ctx = bevy_egui_ctx.ctx_mut();
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Background,
+        egui::Id::new("measurement"),
+    ));
+    let color = ctx.style().visuals.selection.stroke.color;
+    painter.add(egui::Shape::line(points, egui::Stroke::new(2., color)));
+    for vertex in &measurement.vertices {
+        let screen_coord =
+            rgis_units::ScreenCoord::from_projected_geo_coord(*vertex, transform, window);
+        painter.circle_filled(
+            egui::pos2(screen_coord.x as f32, screen_coord.y as f32),
+            3.,
+            color,
+        );
+    }
+}
+
+fn current_tool_is_drawing(rgis_settings: Res<rgis_settings::RgisSettings>) -> bool {
+    rgis_settings.current_tool.is_drawing()
+}
+
+fn draw_system(
+    mouse_button: Res<bevy::input::ButtonInput<bevy::input::mouse::MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    query: Query<&Transform, With<bevy::render::camera::Camera>>,
+    mut bevy_egui_ctx: bevy_egui::EguiContexts,
+    rgis_settings: Res<rgis_settings::RgisSettings>,
+    time: Res<Time>,
+    mut sketch: ResMut<crate::Sketch>,
+    snap: Res<crate::Snap>,
+    mut last_click: Local<Option<(f64, Vec2)>>,
+    mut feature_drawn_event_writer: bevy::ecs::event::EventWriter<rgis_events::FeatureDrawnEvent>,
+) {
+    let tool = rgis_settings.current_tool;
+    if keyboard.just_pressed(KeyCode::Escape)
+        || sketch.tool != Some(tool)
+        || sketch.epsg_code != rgis_settings.target_crs_epsg_code
+    {
+        *sketch = crate::Sketch {
+            tool: Some(tool),
+            epsg_code: rgis_settings.target_crs_epsg_code,
+            vertices: vec![],
+        };
+        return;
+    }
+    if keyboard.just_pressed(KeyCode::Enter) {
+        finish_sketch(&mut sketch, &mut feature_drawn_event_writer);
+        return;
+    }
+
+    if !mouse_button.just_pressed(bevy::input::mouse::MouseButton::Left) {
+        return;
+    }
+    let (Ok(window), Ok(transform)) = (windows.single(), query.single()) else {
+        return;
+    };
+    if bevy_egui_ctx.ctx_mut().is_pointer_over_area() {
+        return;
+    }
+    let Some(cursor_position) = window.cursor_position() else {
+        return;
+    };
+    let coord = snap.snapped(
+        rgis_units::ScreenCoord {
+            x: f64::from(cursor_position.x),
+            y: f64::from(cursor_position.y),
+        }
+        .to_projected_geo_coord(transform, window),
+    );
+    let is_double_click = register_click(&mut last_click, &time, cursor_position);
+
+    let closes_polygon = tool == rgis_settings::Tool::DrawPolygon
+        && sketch.vertices.len() >= 3
+        && sketch.vertices.first().is_some_and(|first| {
+            screen_distance(*first, cursor_position, transform, window) < CLOSE_SHAPE_DISTANCE
+        });
+    if tool == rgis_settings::Tool::DrawPoint {
+        if !is_double_click {
+            feature_drawn_event_writer
+                .write(rgis_events::FeatureDrawnEvent(geo::Point(coord).into()));
+        }
+    } else if closes_polygon || is_double_click {
+        // The first click of a double-click already added the last vertex
+        finish_sketch(&mut sketch, &mut feature_drawn_event_writer);
+    } else {
+        sketch.vertices.push(coord);
+    }
+}
+
+/// Sends the sketched line or polygon, unless it doesn't have enough vertices yet.
+fn finish_sketch(
+    sketch: &mut crate::Sketch,
+    feature_drawn_event_writer: &mut bevy::ecs::event::EventWriter<rgis_events::FeatureDrawnEvent>,
+) {
+    let geometry: geo::Geometry<geo_projected::ProjectedScalar> = match sketch.tool {
+        Some(rgis_settings::Tool::DrawLine) if sketch.vertices.len() >= 2 => {
+            geo::LineString::new(std::mem::take(&mut sketch.vertices)).into()
+        }
+        Some(rgis_settings::Tool::DrawPolygon) if sketch.vertices.len() >= 3 => geo::Polygon::new(
+            geo::LineString::new(std::mem::take(&mut sketch.vertices)),
+            vec![],
+        )
+        .into(),
+        _ => return,
+    };
+    feature_drawn_event_writer.write(rgis_events::FeatureDrawnEvent(geometry));
+}
+
+fn render_sketch(
+    sketch: Res<crate::Sketch>,
+    snap: Res<crate::Snap>,
+    rgis_settings: Res<rgis_settings::RgisSettings>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    query: Query<&Transform, With<bevy::render::camera::Camera>>,
+    mut bevy_egui_ctx: bevy_egui::EguiContexts,
+) {
+    if sketch.vertices.is_empty() {
+        return;
+    }
+    let (Ok(window), Ok(transform)) = (windows.single(), query.single()) else {
+        return;
+    };
+    let ctx = bevy_egui_ctx.ctx_mut();
+    let mut points = sketch
+        .vertices
+        .iter()
+        .map(|vertex| {
+            let screen_coord =
+                rgis_units::ScreenCoord::from_projected_geo_coord(*vertex, transform, window);
+            egui::pos2(screen_coord.x as f32, screen_coord.y as f32)
+        })
+        .collect::<Vec<_>>();
+    let vertex_count = points.len();
+    if !ctx.is_pointer_over_area() {
+        points.extend(window.cursor_position().map(|position| {
+            let position = snapped_position(&snap, position, transform, window);
+            egui::pos2(position.x, position.y)
+        }));
+    }
+
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Background,
+        egui::Id::new("sketch"),
+    ));
+    let color = ctx.style().visuals.selection.stroke.color;
+    let stroke = egui::Stroke::new(2., color);
+    for point in points.iter().take(vertex_count) {
+        painter.circle_filled(*point, 3., color);
+    }
+    if rgis_settings.current_tool == rgis_settings::Tool::DrawPolygon && points.len() >= 3 {
+        painter.add(egui::Shape::closed_line(points, stroke));
+    } else {
+        painter.add(egui::Shape::line(points, stroke));
+    }
+}
+
+/// Pixels from a vertex or segment midpoint within which it can be grabbed.
+const HANDLE_RADIUS: f32 = 6.;
+
+/// Something that can be grabbed while editing vertices.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Handle {
+    Vertex {
+        part: usize,
+        index: usize,
+    },
+    /// Midpoint of a segment, where a vertex gets inserted at `index`
+    Midpoint {
+        part: usize,
+        index: usize,
+    },
+}
+
+/// The handles of the parts with their position in the window, vertices first.
+fn handles<'a>(
+    parts: &'a [crate::vertices::Part<geo_projected::ProjectedScalar>],
+    transform: &'a Transform,
+    window: &'a Window,
+) -> impl Iterator<Item = (Handle, Vec2)> + 'a {
+    let vertices = parts
+        .iter()
+        .enumerate()
+        .flat_map(move |(part_index, part)| {
+            part.coords.iter().enumerate().map(move |(index, coord)| {
+                (
+                    Handle::Vertex {
+                        part: part_index,
+                        index,
+                    },
+                    screen_position(*coord, transform, window),
+                )
+            })
+        });
+    let midpoints = parts
+        .iter()
+        .enumerate()
+        .flat_map(move |(part_index, part)| {
+            part.segments().filter_map(move |(start_index, end_index)| {
+                let start = screen_position(*part.coords.get(start_index)?, transform, window);
+                let end = screen_position(*part.coords.get(end_index)?, transform, window);
+                Some((
+                    Handle::Midpoint {
+                        part: part_index,
+                        index: start_index + 1,
+                    },
+                    (start + end) / 2.,
+                ))
+            })
+        });
+    vertices.chain(midpoints)
+}
+
+/// Handle under `position`, preferring vertices over midpoints.
+fn handle_at(
+    parts: &[crate::vertices::Part<geo_projected::ProjectedScalar>],
+    position: Vec2,
+    transform: &Transform,
+    window: &Window,
+) -> Option<Handle> {
+    let nearest = |is_vertex: bool| {
+        handles(parts, transform, window)
+            .filter(|(handle, _)| matches!(handle, Handle::Vertex { .. }) == is_vertex)
+            .map(|(handle, handle_position)| (handle, handle_position.distance(position)))
+            .filter(|(_, distance)| *distance < HANDLE_RADIUS)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(handle, _)| handle)
+    };
+    nearest(true).or_else(|| nearest(false))
+}
+
+fn current_tool_is_edit_vertices(rgis_settings: Res<rgis_settings::RgisSettings>) -> bool {
+    rgis_settings.current_tool == rgis_settings::Tool::EditVertices
+}
+
+fn handle_edit_layer_vertices_events(
+    mut edit_layer_vertices_event_reader: bevy::ecs::event::EventReader<
+        rgis_events::EditLayerVerticesEvent,
+    >,
+    mut vertex_editor: ResMut<crate::VertexEditor>,
+    mut rgis_settings: ResMut<rgis_settings::RgisSettings>,
+) {
+    if let Some(event) = edit_layer_vertices_event_reader.read().last() {
+        *vertex_editor = crate::VertexEditor {
+            layer_id: Some(event.0),
+            feature: None,
+        };
+        rgis_settings.current_tool = rgis_settings::Tool::EditVertices;
+    }
+}
+
+/// Starts editing the layer's selected feature, and picks up changes made to it elsewhere.
+fn sync_vertex_editor(
+    layers: Res<rgis_layers::Layers>,
+    mut vertex_editor: ResMut<crate::VertexEditor>,
+    mut layer_features_updated_event_reader: bevy::ecs::event::EventReader<
+        rgis_events::LayerFeaturesUpdatedEvent,
+    >,
+    mut layer_reprojected_event_reader: bevy::ecs::event::EventReader<
+        rgis_events::LayerReprojectedEvent,
+    >,
+    mut feature_geometry_updated_event_reader: bevy::ecs::event::EventReader<
+        rgis_events::FeatureGeometryUpdatedEvent,
+    >,
+) {
+    if vertex_editor
+        .layer_id
+        .is_some_and(|layer_id| layers.get(layer_id).is_none())
+    {
+        // The layer was deleted
+        *vertex_editor = crate::VertexEditor::default();
+    }
+    let layer_id = vertex_editor.layer_id;
+    let is_layer_updated = layer_features_updated_event_reader
+        .read()
+        .map(|event| event.0)
+        .chain(layer_reprojected_event_reader.read().map(|event| event.0))
+        .chain(
+            feature_geometry_updated_event_reader
+                .read()
+                .map(|event| event.0),
+        )
+        .filter(|updated_layer_id| Some(*updated_layer_id) == layer_id)
+        .count()
+        > 0;
+    let selected_feature_id = layer_id
+        .and_then(|layer_id| layers.get(layer_id))
+        .filter(|layer| layer.selected_feature_ids.len() == 1)
+        .and_then(|layer| layer.selected_feature_ids.first().copied());
+    let is_dragging = vertex_editor
+        .feature
+        .as_ref()
+        .is_some_and(|feature| feature.drag.is_some());
+    if vertex_editor.feature_id() == selected_feature_id && (!is_layer_updated || is_dragging) {
+        return;
+    }
+
+    vertex_editor.feature = layer_id
+        .zip(selected_feature_id)
+        .and_then(|(layer_id, feature_id)| {
+            let geometry = layers
+                .get(layer_id)?
+                .projected_feature_collection
+                .as_ref()?
+                .get(feature_id)?
+                .geometry
+                .clone()?;
+            Some(crate::EditedFeature {
+                feature_id,
+                parts: crate::vertices::decompose(&geometry)?,
+                geometry,
+                drag: None,
+            })
+        });
+}
+
+fn edit_vertices_system(
+    mouse_button: Res<bevy::input::ButtonInput<bevy::input::mouse::MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    query: Query<&Transform, With<bevy::render::camera::Camera>>,
+    mut bevy_egui_ctx: bevy_egui::EguiContexts,
+    mut rgis_settings: ResMut<rgis_settings::RgisSettings>,
+    mut vertex_editor: ResMut<crate::VertexEditor>,
+    snap: Res<crate::Snap>,
+    mut update_feature_geometry_event_writer: bevy::ecs::event::EventWriter<
+        rgis_events::UpdateFeatureGeometryEvent,
+    >,
+) {
+    let vertex_editor = &mut *vertex_editor;
+    let Some(layer_id) = vertex_editor.layer_id else {
+        return;
+    };
+    let edited_feature = &mut vertex_editor.feature;
+
+    if keyboard.just_pressed(KeyCode::Escape) {
+        match edited_feature {
+            Some(feature) if feature.drag.is_some() => {
+                // Put the dragged vertex back
+                feature.drag = None;
+                if let Some(parts) = crate::vertices::decompose(&feature.geometry) {
+                    feature.parts = parts;
+                }
+            }
+            _ => rgis_settings.current_tool = rgis_settings::Tool::Pan,
+        }
+        return;
+    }
+
+    let Some(feature) = edited_feature else {
+        return;
+    };
+    let (Ok(window), Ok(transform)) = (windows.single(), query.single()) else {
+        return;
+    };
+    let Some(cursor_position) = window.cursor_position() else {
+        return;
+    };
+    let coord = snap.snapped(
+        rgis_units::ScreenCoord {
+            x: f64::from(cursor_position.x),
+            y: f64::from(cursor_position.y),
+        }
+        .to_projected_geo_coord(transform, window),
+    );
+    let mut send_update = |feature: &crate::EditedFeature| {
+        update_feature_geometry_event_writer.write(rgis_events::UpdateFeatureGeometryEvent {
+            layer_id,
+            feature_id: feature.feature_id,
+            geometry: feature.edited_geometry(),
+        });
+    };
+
+    if let Some(drag) = feature.drag.as_mut() {
+        if mouse_button.pressed(bevy::input::mouse::MouseButton::Left) {
+            if let Some(vertex) = feature
+                .parts
+                .get_mut(drag.part)
+                .and_then(|part| part.coords.get_mut(drag.index))
+                .filter(|vertex| **vertex != coord)
+            {
+                *vertex = coord;
+                drag.has_moved = true;
+            }
+        } else {
+            let has_moved = drag.has_moved;
+            feature.drag = None;
+            if has_moved {
+                send_update(feature);
+            }
+        }
+        return;
+    }
+
+    if bevy_egui_ctx.ctx_mut().is_pointer_over_area() {
+        return;
+    }
+    let handle = handle_at(&feature.parts, cursor_position, transform, window);
+    if mouse_button.just_pressed(bevy::input::mouse::MouseButton::Left) {
+        match handle {
+            Some(Handle::Vertex { part, index }) => {
+                feature.drag = Some(crate::VertexDrag {
+                    part,
+                    index,
+                    has_moved: false,
+                });
+            }
+            Some(Handle::Midpoint { part, index }) => {
+                if let Some(part_coords) = feature
+                    .parts
+                    .get_mut(part)
+                    .map(|part| &mut part.coords)
+                    .filter(|coords| index <= coords.len())
+                {
+                    part_coords.insert(index, coord);
+                    feature.drag = Some(crate::VertexDrag {
+                        part,
+                        index,
+                        has_moved: true,
+                    });
+                }
+            }
+            None => (),
+        }
+    } else if mouse_button.just_pressed(bevy::input::mouse::MouseButton::Right) {
+        let Some(Handle::Vertex { part, index }) = handle else {
+            return;
+        };
+        if let Some(part) = feature
+            .parts
+            .get_mut(part)
+            .filter(|part| part.can_remove_vertex() && index < part.coords.len())
+        {
+            part.coords.remove(index);
+            send_update(feature);
+        }
+    }
+}
+
+fn render_vertex_editor(
+    vertex_editor: Res<crate::VertexEditor>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    query: Query<&Transform, With<bevy::render::camera::Camera>>,
+    mut bevy_egui_ctx: bevy_egui::EguiContexts,
+) {
+    let Some(ref feature) = vertex_editor.feature else {
+        return;
+    };
+    let (Ok(window), Ok(transform)) = (windows.single(), query.single()) else {
+        return;
+    };
+    let ctx = bevy_egui_ctx.ctx_mut();
+    let hovered = match feature.drag {
+        Some(drag) => Some(Handle::Vertex {
+            part: drag.part,
+            index: drag.index,
+        }),
+        None if ctx.is_pointer_over_area() => None,
+        None => window
+            .cursor_position()
+            .and_then(|position| handle_at(&feature.parts, position, transform, window)),
+    };
+
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Background,
+        egui::Id::new("vertex_editor"),
+    ));
+    let color = ctx.style().visuals.selection.stroke.color;
+    let stroke = egui::Stroke::new(2., color);
+    for part in &feature.parts {
+        let points = part
+            .coords
+            .iter()
+            .map(|coord| {
+                let position = screen_position(*coord, transform, window);
+                egui::pos2(position.x, position.y)
+            })
+            .collect::<Vec<_>>();
+        match part.kind {
+            crate::vertices::PartKind::Point => (),
+            crate::vertices::PartKind::Line => {
+                painter.add(egui::Shape::line(points, stroke));
+            }
+            crate::vertices::PartKind::Ring => {
+                painter.add(egui::Shape::closed_line(points, stroke));
+            }
+        }
+    }
+    for (handle, position) in handles(&feature.parts, transform, window) {
+        let position = egui::pos2(position.x, position.y);
+        let scale = if hovered == Some(handle) { 1.5 } else { 1. };
+        match handle {
+            Handle::Vertex { .. } => {
+                painter.rect_filled(
+                    egui::Rect::from_center_size(position, egui::Vec2::splat(7. * scale)),
+                    0.,
+                    color,
+                );
+            }
+            Handle::Midpoint { .. } => {
+                painter.circle_stroke(position, 3. * scale, stroke);
+            }
+        }
+    }
+}
+
+fn current_tool_snaps(rgis_settings: Res<rgis_settings::RgisSettings>) -> bool {
+    rgis_settings.current_tool.snaps()
+}
+
+/// Finds what the cursor snaps to among the features of visible layers near it.
+fn snap_system(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    query: Query<&Transform, With<bevy::render::camera::Camera>>,
+    mut bevy_egui_ctx: bevy_egui::EguiContexts,
+    rgis_settings: Res<rgis_settings::RgisSettings>,
+    layers: Res<rgis_layers::Layers>,
+    vertex_editor: Res<crate::VertexEditor>,
+    mut snap_indexes: ResMut<crate::SnapIndexes>,
+    mut snap: ResMut<crate::Snap>,
+) {
+    snap.0 = None;
+    if !rgis_settings.is_snapping_enabled {
+        return;
+    }
+    let (Ok(window), Ok(transform)) = (windows.single(), query.single()) else {
+        return;
+    };
+    if bevy_egui_ctx.ctx_mut().is_pointer_over_area() {
+        return;
+    }
+    let Some(cursor_position) = window.cursor_position() else {
+        return;
+    };
+    let to_projected = |offset: f32| {
+        rgis_units::ScreenCoord {
+            x: f64::from(cursor_position.x + offset),
+            y: f64::from(cursor_position.y + offset),
+        }
+        .to_projected_geo_coord(transform, window)
+    };
+    let to_f64 = |coord: geo_projected::ProjectedCoord| geo::Coord {
+        x: coord.x.0,
+        y: coord.y.0,
+    };
+    let tolerance = rgis_settings.snapping_tolerance;
+    let coord = to_projected(0.);
+    // The spatial index narrows the features down to those near the cursor
+    let rect = geo::Rect::new(to_projected(-tolerance), to_projected(tolerance));
+    let tolerance = (to_projected(tolerance).x.0 - coord.x.0).abs();
+
+    let edited_feature = vertex_editor
+        .feature
+        .as_ref()
+        .filter(|_| rgis_settings.current_tool == rgis_settings::Tool::EditVertices);
+    let edited_feature_id = edited_feature
+        .zip(vertex_editor.layer_id)
+        .map(|(feature, layer_id)| (layer_id, feature.feature_id));
+    let mut candidates = crate::snapping::Candidates::new(to_f64(coord), tolerance);
+    for (layer, feature) in layers.features_in_rect(rect) {
+        // Its stored geometry is outdated while its vertices are being moved
+        if edited_feature_id == Some((layer.id, feature.id)) {
+            continue;
+        }
+        let Some(ref geometry) = feature.geometry else {
+            continue;
+        };
+        if geometry.coords_count() < crate::snapping::MIN_INDEXED_COORDS {
+            candidates.add_geometry(geometry, to_f64);
+            continue;
+        }
+        let index = snap_indexes
+            .0
+            .entry((layer.id, feature.id))
+            .or_insert_with(|| crate::snapping::GeometryIndex::new(geometry, to_f64));
+        candidates.add_indexed(index);
+    }
+    if let Some(feature) = edited_feature {
+        // The dragged vertex would snap to itself
+        candidates.add_parts(
+            &feature.parts,
+            feature.drag.map(|drag| (drag.part, drag.index)),
+            to_f64,
+        );
+    }
+    snap.0 = candidates.snap();
+}
+
+/// Drops the snapping indexes of features whose geometry changed.
+fn invalidate_snap_indexes(
+    mut snap_indexes: ResMut<crate::SnapIndexes>,
+    mut feature_geometry_updated_event_reader: bevy::ecs::event::EventReader<
+        rgis_events::FeatureGeometryUpdatedEvent,
+    >,
+    mut layer_reprojected_event_reader: bevy::ecs::event::EventReader<
+        rgis_events::LayerReprojectedEvent,
+    >,
+    mut despawn_meshes_event_reader: bevy::ecs::event::EventReader<rgis_events::DespawnMeshesEvent>,
+) {
+    for event in feature_geometry_updated_event_reader.read() {
+        snap_indexes.0.remove(&(event.0, event.1));
+    }
+    let layer_ids = layer_reprojected_event_reader
+        .read()
+        .map(|event| event.0)
+        .chain(despawn_meshes_event_reader.read().map(|event| event.0))
+        .collect::<Vec<_>>();
+    if !layer_ids.is_empty() {
+        snap_indexes
+            .0
+            .retain(|(layer_id, _), _| !layer_ids.contains(layer_id));
+    }
+}
+
+/// Position in the window the cursor at `position` snaps to.
+fn snapped_position(
+    snap: &crate::Snap,
+    position: Vec2,
+    transform: &Transform,
+    window: &Window,
+) -> Vec2 {
+    match snap.coord() {
+        Some(coord) => screen_position(coord, transform, window),
+        None => position,
+    }
+}
+
+fn render_snap(
+    snap: Res<crate::Snap>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    query: Query<&Transform, With<bevy::render::camera::Camera>>,
+    mut bevy_egui_ctx: bevy_egui::EguiContexts,
+) {
+    let (Some(target), Some(coord)) = (snap.0, snap.coord()) else {
+        return;
+    };
+    let (Ok(window), Ok(transform)) = (windows.single(), query.single()) else {
+        return;
+    };
+    let ctx = bevy_egui_ctx.ctx_mut();
+    let position = screen_position(coord, transform, window);
+    let position = egui::pos2(position.x, position.y);
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Background,
+        egui::Id::new("snap"),
+    ));
+    let stroke = egui::Stroke::new(2., ctx.style().visuals.warn_fg_color);
+    match target.kind {
+        crate::snapping::SnapKind::Vertex => {
+            painter.rect_stroke(
+                egui::Rect::from_center_size(position, egui::Vec2::splat(12.)),
+                0.,
+                stroke,
+                egui::StrokeKind::Middle,
+            );
+        }
+        crate::snapping::SnapKind::Intersection => {
+            let offset = egui::Vec2::splat(6.);
+            painter.line_segment([position - offset, position + offset], stroke);
+            painter.line_segment(
+                [
+                    position + egui::vec2(-offset.x, offset.y),
+                    position + egui::vec2(offset.x, -offset.y),
+                ],
+                stroke,
+            );
+        }
+        crate::snapping::SnapKind::Edge => {
+            painter.circle_stroke(position, 6., stroke);
+        }
+    }
 }
 
 fn run_if_has_mouse_scroll_events(
@@ -180,6 +1153,14 @@ fn mouse_scroll_system(
 }
 
 pub fn configure(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            handle_edit_layer_vertices_events,
+            sync_vertex_editor.run_if(current_tool_is_edit_vertices),
+        )
+            .chain(),
+    );
     // https://github.com/vladbat00/bevy_egui/issues/47#issuecomment-2368811068
     app.add_systems(
         PreUpdate,
@@ -190,8 +1171,27 @@ pub fn configure(app: &mut App) {
                 .run_if(current_tool_is_query)
                 .run_if(run_if_mouse_left_button_just_pressed),
             mouse_motion_system.run_if(run_if_has_mouse_motion_events),
+            area_selection_system.run_if(current_tool_is_area_selection),
+            measure_system.run_if(current_tool_is_measure),
+            invalidate_snap_indexes.before(snap_system),
+            snap_system
+                .run_if(current_tool_snaps)
+                .before(draw_system)
+                .before(edit_vertices_system),
+            draw_system.run_if(current_tool_is_drawing),
+            edit_vertices_system.run_if(current_tool_is_edit_vertices),
         )
             .after(bevy_egui::EguiPreUpdateSet::ProcessInput)
             .before(bevy_egui::EguiPreUpdateSet::BeginPass),
     );
+    app.add_systems(
+        bevy_egui::EguiContextPass,
+        (
+            render_selection_area.run_if(current_tool_is_area_selection),
+            render_measurement.run_if(current_tool_is_measure),
+            render_sketch.run_if(current_tool_is_drawing),
+            render_vertex_editor.run_if(current_tool_is_edit_vertices),
+            render_snap.run_if(current_tool_snaps),
+        ),
+    );
 }
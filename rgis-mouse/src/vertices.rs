@@ -0,0 +1,185 @@
+//! Splitting a geometry into sequences of vertices that can be moved, inserted and deleted, and
+//! putting it back together.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PartKind {
+    Point,
+    Line,
+    /// Closed ring of a polygon, whose closing vertex isn't repeated
+    Ring,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Part<T: geo::CoordNum> {
+    pub kind: PartKind,
+    pub coords: Vec<geo::Coord<T>>,
+}
+
+impl<T: geo::CoordNum> Part<T> {
+    fn new(kind: PartKind, mut coords: Vec<geo::Coord<T>>) -> Self {
+        if kind == PartKind::Ring && coords.len() > 1 && coords.first() == coords.last() {
+            coords.pop();
+        }
+        Part { kind, coords }
+    }
+
+    /// Indices of the vertices at both ends of each segment.
+    pub fn segments(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let len = self.coords.len();
+        let count = match self.kind {
+            PartKind::Point => 0,
+            PartKind::Line => len.saturating_sub(1),
+            PartKind::Ring if len >= 3 => len,
+            PartKind::Ring => len.saturating_sub(1),
+        };
+        (0..count).map(move |i| (i, (i + 1) % len))
+    }
+
+    /// Whether a vertex can be removed while keeping the part valid.
+    pub fn can_remove_vertex(&self) -> bool {
+        match self.kind {
+            PartKind::Point => false,
+            PartKind::Line => self.coords.len() > 2,
+            PartKind::Ring => self.coords.len() > 3,
+        }
+    }
+
+    fn into_line_string(self) -> geo::LineString<T> {
+        // Polygons close their rings themselves
+        geo::LineString::new(self.coords)
+    }
+}
+
+/// The parts of `geometry`, or `None` for rects, triangles, lines and geometry collections, which
+/// can't be edited vertex by vertex.
+pub(crate) fn decompose<T: geo::CoordNum>(geometry: &geo::Geometry<T>) -> Option<Vec<Part<T>>> {
+    let polygon_parts = |polygon: &geo::Polygon<T>| {
+        std::iter::once(polygon.exterior())
+            .chain(polygon.interiors())
+            .map(|ring| Part::new(PartKind::Ring, ring.0.clone()))
+            .collect::<Vec<_>>()
+    };
+    Some(match geometry {
+        geo::Geometry::Point(point) => vec![Part::new(PartKind::Point, vec![point.0])],
+        geo::Geometry::MultiPoint(multi_point) => multi_point
+            .iter()
+            .map(|point| Part::new(PartKind::Point, vec![point.0]))
+            .collect(),
+        geo::Geometry::LineString(line_string) => {
+            vec![Part::new(PartKind::Line, line_string.0.clone())]
+        }
+        geo::Geometry::MultiLineString(multi_line_string) => multi_line_string
+            .iter()
+            .map(|line_string| Part::new(PartKind::Line, line_string.0.clone()))
+            .collect(),
+        geo::Geometry::Polygon(polygon) => polygon_parts(polygon),
+        geo::Geometry::MultiPolygon(multi_polygon) => {
+            multi_polygon.iter().flat_map(polygon_parts).collect()
+        }
+        geo::Geometry::Line(_)
+        | geo::Geometry::Rect(_)
+        | geo::Geometry::Triangle(_)
+        | geo::Geometry::GeometryCollection(_) => return None,
+    })
+}
+
+/// Puts back together the parts `decompose` split `like` into.
+pub(crate) fn recompose<T: geo::CoordNum>(
+    like: &geo::Geometry<T>,
+    parts: Vec<Part<T>>,
+) -> geo::Geometry<T> {
+    let mut parts = parts.into_iter();
+    let mut polygon_like = |polygon: &geo::Polygon<T>| {
+        let exterior = parts
+            .next()
+            .map_or_else(|| geo::LineString::new(vec![]), Part::into_line_string);
+        let interiors = parts
+            .by_ref()
+            .take(polygon.interiors().len())
+            .map(Part::into_line_string)
+            .collect();
+        geo::Polygon::new(exterior, interiors)
+    };
+    match like {
+        geo::Geometry::Polygon(polygon) => polygon_like(polygon).into(),
+        geo::Geometry::MultiPolygon(multi_polygon) => {
+            geo::MultiPolygon(multi_polygon.iter().map(polygon_like).collect()).into()
+        }
+        geo::Geometry::Point(_) | geo::Geometry::MultiPoint(_) => {
+            let mut points = parts
+                .filter_map(|part| part.coords.first().copied())
+                .map(geo::Point);
+            match like {
+                geo::Geometry::Point(point) => points.next().unwrap_or(*point).into(),
+                _ => geo::MultiPoint(points.collect()).into(),
+            }
+        }
+        geo::Geometry::LineString(_) => parts
+            .next()
+            .map_or_else(|| geo::LineString::new(vec![]), Part::into_line_string)
+            .into(),
+        geo::Geometry::MultiLineString(_) => {
+            geo::MultiLineString(parts.map(Part::into_line_string).collect()).into()
+        }
+        geo::Geometry::Line(_)
+        | geo::Geometry::Rect(_)
+        | geo::Geometry::Triangle(_)
+        | geo::Geometry::GeometryCollection(_) => like.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square() -> geo::Polygon<f64> {
+        geo::Polygon::new(
+            geo::LineString::from(vec![(0., 0.), (1., 0.), (1., 1.), (0., 1.), (0., 0.)]),
+            vec![geo::LineString::from(vec![
+                (0.2, 0.2),
+                (0.4, 0.2),
+                (0.4, 0.4),
+                (0.2, 0.2),
+            ])],
+        )
+    }
+
+    #[test]
+    fn test_decompose_polygon() {
+        let parts = decompose(&geo::Geometry::Polygon(square())).unwrap_or_default();
+        assert_eq!(parts.len(), 2);
+        assert!(parts.iter().all(|part| part.kind == PartKind::Ring));
+        assert_eq!(parts.first().map(|part| part.coords.len()), Some(4));
+        assert_eq!(parts.first().map(|part| part.segments().count()), Some(4));
+        assert_eq!(parts.get(1).map(Part::can_remove_vertex), Some(false));
+    }
+
+    #[test]
+    fn test_recompose_round_trip() {
+        let geometries: Vec<geo::Geometry<f64>> = vec![
+            geo::Point::new(1., 2.).into(),
+            geo::LineString::from(vec![(0., 0.), (1., 1.), (2., 0.)]).into(),
+            square().into(),
+            geo::MultiPolygon(vec![square(), square()]).into(),
+        ];
+        for geometry in geometries {
+            let parts = decompose(&geometry).unwrap_or_default();
+            assert_eq!(recompose(&geometry, parts), geometry);
+        }
+    }
+
+    #[test]
+    fn test_recompose_edited_ring() {
+        let geometry = geo::Geometry::Polygon(square());
+        let mut parts = decompose(&geometry).unwrap_or_default();
+        if let Some(exterior) = parts.first_mut() {
+            exterior.coords.insert(1, geo::coord! { x: 0.5, y: -0.5 });
+        }
+        let geo::Geometry::Polygon(polygon) = recompose(&geometry, parts) else {
+            panic!("expected a polygon");
+        };
+        assert_eq!(polygon.exterior().0.len(), 6);
+        assert!(polygon.exterior().is_closed());
+        assert_eq!(polygon.interiors().len(), 1);
+    }
+}
@@ -0,0 +1,322 @@
+use serde_json::Value;
+
+// Used when the layer doesn't report its `maxRecordCount`
+const DEFAULT_MAX_RECORD_COUNT: u64 = 1000;
+
+/// Every feature of an ArcGIS REST FeatureServer or MapServer layer.
+pub struct ArcGisFeatureLayerJob {
+    /// Used when the service doesn't report the layer's name.
+    pub name: String,
+    pub layer_url: String,
+    /// Revalidate against and store into this cache. `None` always downloads.
+    pub cache: Option<crate::HttpCache>,
+}
+
+impl bevy_jobs::Job for ArcGisFeatureLayerJob {
+    type Outcome = Result<crate::FetchedFile, crate::Error>;
+    const JOB_TYPE: bevy_jobs::JobType = bevy_jobs::JobType::Io;
+
+    fn name(&self) -> String {
+        format!("Fetching '{}'", self.name)
+    }
+
+    fn perform(self, ctx: bevy_jobs::Context) -> bevy_jobs::AsyncReturn<Self::Outcome> {
+        Box::pin(
+            async move { crate::await_future(async move { fetch_layer(self, ctx).await }).await? },
+        )
+    }
+}
+
+/// Whether `url` points at a layer of an ArcGIS REST service, e.g.
+/// `https://services.arcgis.com/…/FeatureServer/0`.
+pub fn is_arcgis_layer_url(url: &str) -> bool {
+    layer_url(url).is_ok()
+}
+
+/// Normalize a layer URL, dropping its query string and a trailing `/query`.
+fn layer_url(url: &str) -> Result<String, crate::Error> {
+    let not_a_layer_url = || crate::Error::NotArcGisLayerUrl(url.to_owned());
+    let mut parsed = reqwest::Url::parse(url.trim()).map_err(|_| not_a_layer_url())?;
+    let segments = parsed
+        .path_segments()
+        .ok_or_else(not_a_layer_url)?
+        .filter(|segment| !segment.is_empty())
+        .map(String::from)
+        .collect::<Vec<_>>();
+    let segments = match segments.as_slice() {
+        [rest @ .., query] if query == "query" => rest,
+        segments => segments,
+    };
+    match segments {
+        [.., service_type, layer_id]
+            if (service_type == "FeatureServer" || service_type == "MapServer")
+                && layer_id.parse::<u32>().is_ok() => {}
+        _ => return Err(not_a_layer_url()),
+    }
+    parsed.set_path(&segments.join("/"));
+    parsed.set_query(None);
+    parsed.set_fragment(None);
+    Ok(parsed.into())
+}
+
+async fn fetch_layer(
+    job: ArcGisFeatureLayerJob,
+    ctx: bevy_jobs::Context,
+) -> Result<crate::FetchedFile, crate::Error> {
+    let layer_url = layer_url(&job.layer_url)?;
+    let client = reqwest::Client::new();
+
+    let cache = job.cache.as_ref();
+
+    let info = get_json(
+        &client,
+        reqwest::Url::parse_with_params(&layer_url, [("f", "json")])?,
+        cache,
+    )
+    .await?;
+    let name = info
+        .get("name")
+        .and_then(Value::as_str)
+        .map_or(job.name, String::from);
+    let page_size = info
+        .get("maxRecordCount")
+        .and_then(Value::as_u64)
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_MAX_RECORD_COUNT);
+    // Layers published before `advancedQueryCapabilities` existed may still support paging
+    let supports_pagination = info
+        .get("advancedQueryCapabilities")
+        .and_then(|capabilities| capabilities.get("supportsPagination"))
+        .and_then(Value::as_bool)
+        .unwrap_or(true);
+    let object_id_field = object_id_field(&info);
+
+    let query_url = format!("{layer_url}/query");
+    let count = get_json(
+        &client,
+        reqwest::Url::parse_with_params(
+            &query_url,
+            [("where", "1=1"), ("returnCountOnly", "true"), ("f", "json")],
+        )?,
+        cache,
+    )
+    .await?
+    .get("count")
+    .and_then(Value::as_u64);
+
+    let mut pages = QueryPages {
+        client,
+        cache: job.cache.clone(),
+        query_url: reqwest::Url::parse(&query_url)?,
+        page_size,
+        supports_pagination,
+        object_id_field,
+        count,
+        offset: 0,
+        done: false,
+    };
+    let mut features = vec![];
+    while let Some(page) = pages.next_page().await? {
+        features.extend(page);
+        if let Some(count) = count.filter(|n| *n > 0) {
+            let percent = (100 * features.len() as u64 / count).min(100) as u8;
+            crate::send_progress(&ctx, percent).await?;
+        }
+    }
+
+    Ok(crate::FetchedFile {
+        name,
+        url: layer_url,
+        bytes: crate::geojson_feature_collection(features)?,
+        crs_epsg_code: 4326,
+        file_format: Some(geo_file_loader::FileFormat::GeoJson),
+        content_type: None,
+    })
+}
+
+/// The field uniquely identifying the layer's features, from the layer's info.
+fn object_id_field(info: &Value) -> Option<String> {
+    info.get("objectIdField")
+        .and_then(Value::as_str)
+        .or_else(|| {
+            info.get("fields")?
+                .as_array()?
+                .iter()
+                .find(|field| {
+                    field.get("type").and_then(Value::as_str) == Some("esriFieldTypeOID")
+                })?
+                .get("name")?
+                .as_str()
+        })
+        .map(String::from)
+}
+
+/// Fetch JSON from an ArcGIS REST endpoint, which reports errors in the body of a `200 OK`
+/// response.
+async fn get_json(
+    client: &reqwest::Client,
+    url: reqwest::Url,
+    cache: Option<&crate::HttpCache>,
+) -> Result<Value, crate::Error> {
+    let response = crate::fetch(client, url, reqwest::header::HeaderMap::new(), cache, None)
+        .await?
+        .error_for_status()?;
+    let value: Value = serde_json::from_slice(&response.bytes)?;
+    if let Some(error) = value.get("error") {
+        let message = error
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown error");
+        return Err(crate::Error::ServiceException(message.into()));
+    }
+    Ok(value)
+}
+
+/// Pages through a layer's `query` endpoint with `resultOffset`.
+struct QueryPages {
+    client: reqwest::Client,
+    cache: Option<crate::HttpCache>,
+    query_url: reqwest::Url,
+    page_size: u64,
+    supports_pagination: bool,
+    /// Pages are ordered by this field, without which the server may return them in a
+    /// different order for each request.
+    object_id_field: Option<String>,
+    /// Total number of features, guarding against servers that ignore `resultOffset`.
+    count: Option<u64>,
+    offset: u64,
+    done: bool,
+}
+
+impl QueryPages {
+    async fn next_page(&mut self) -> Result<Option<Vec<Value>>, crate::Error> {
+        if self.done {
+            return Ok(None);
+        }
+
+        let mut url = self.query_url.clone();
+        {
+            let mut query = url.query_pairs_mut();
+            query
+                .append_pair("where", "1=1")
+                .append_pair("outFields", "*")
+                .append_pair("outSR", "4326")
+                .append_pair("f", "geojson");
+            if self.supports_pagination {
+                if let Some(object_id_field) = &self.object_id_field {
+                    query.append_pair("orderByFields", object_id_field);
+                }
+                query
+                    .append_pair("resultOffset", &self.offset.to_string())
+                    .append_pair("resultRecordCount", &self.page_size.to_string());
+            }
+        }
+
+        let mut value = get_json(&self.client, url, self.cache.as_ref()).await?;
+        let Some(Value::Array(features)) = value.get_mut("features").map(Value::take) else {
+            return Err(crate::Error::UnexpectedResponse("missing `features`"));
+        };
+        // GeoJSON responses report it under `properties`, JSON responses at the top level
+        let exceeded_transfer_limit = value
+            .get("properties")
+            .and_then(|properties| properties.get("exceededTransferLimit"))
+            .or_else(|| value.get("exceededTransferLimit"))
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        self.offset += features.len() as u64;
+        self.done = features.is_empty()
+            || !self.supports_pagination
+            || (!exceeded_transfer_limit && (features.len() as u64) < self.page_size)
+            || self.count.is_some_and(|count| self.offset >= count);
+
+        if exceeded_transfer_limit && !self.supports_pagination {
+            bevy::log::warn!(
+                "Layer doesn't support pagination, only the first {} features were loaded",
+                features.len()
+            );
+        }
+
+        Ok(Some(features))
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layer_url() {
+        assert_eq!(
+            layer_url("https://example.com/arcgis/rest/services/Parcels/FeatureServer/3/query?where=1%3D1")
+                .unwrap(),
+            "https://example.com/arcgis/rest/services/Parcels/FeatureServer/3"
+        );
+        assert!(!is_arcgis_layer_url(
+            "https://example.com/arcgis/rest/services/Parcels/FeatureServer"
+        ));
+    }
+
+    #[test]
+    fn test_query_pages_use_result_offset() {
+        let (base_url, requests) = crate::test_server::serve(vec![
+            serde_json::json!({
+                "type": "FeatureCollection",
+                "features": [{}, {}],
+                "properties": {"exceededTransferLimit": true},
+            }),
+            serde_json::json!({"type": "FeatureCollection", "features": [{}]}),
+        ]);
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let pages = runtime.block_on(async {
+            let mut pages = QueryPages {
+                client: reqwest::Client::new(),
+                cache: None,
+                query_url: reqwest::Url::parse(&format!("{base_url}/FeatureServer/0/query"))
+                    .unwrap(),
+                page_size: 2,
+                supports_pagination: true,
+                object_id_field: Some("OBJECTID".into()),
+                count: Some(3),
+                offset: 0,
+                done: false,
+            };
+            let mut result = vec![];
+            while let Some(page) = pages.next_page().await.unwrap() {
+                result.push(page.len());
+            }
+            result
+        });
+
+        assert_eq!(pages, [2, 1]);
+        assert!(requests
+            .recv()
+            .unwrap()
+            .contains("&orderByFields=OBJECTID&resultOffset=0&resultRecordCount=2 "));
+        assert!(requests
+            .recv()
+            .unwrap()
+            .contains("&orderByFields=OBJECTID&resultOffset=2&resultRecordCount=2 "));
+    }
+
+    #[test]
+    fn test_object_id_field() {
+        assert_eq!(
+            object_id_field(&serde_json::json!({"objectIdField": "FID"})).as_deref(),
+            Some("FID")
+        );
+        assert_eq!(
+            object_id_field(&serde_json::json!({
+                "fields": [
+                    {"name": "NAME", "type": "esriFieldTypeString"},
+                    {"name": "OBJECTID", "type": "esriFieldTypeOID"},
+                ],
+            }))
+            .as_deref(),
+            Some("OBJECTID")
+        );
+        assert_eq!(object_id_field(&serde_json::json!({})), None);
+    }
+}
@@ -0,0 +1,481 @@
+use bevy::prelude::*;
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// Responses of the network jobs, kept on disk (native) or in the browser's Cache API (wasm) and
+/// revalidated with their `ETag` or `Last-Modified` validators.
+#[derive(Clone, Resource)]
+pub struct HttpCache {
+    /// Least recently used responses are evicted once the cache grows past this size.
+    pub max_size_bytes: u64,
+    /// `None` on platforms without a cache directory, where nothing is cached.
+    #[cfg(not(target_arch = "wasm32"))]
+    directory: Option<std::path::PathBuf>,
+}
+
+impl Default for HttpCache {
+    fn default() -> Self {
+        HttpCache {
+            max_size_bytes: 512 * BYTES_PER_MB,
+            #[cfg(not(target_arch = "wasm32"))]
+            directory: dirs::cache_dir().map(|directory| directory.join("rgis").join("http")),
+        }
+    }
+}
+
+impl HttpCache {
+    pub fn max_size_mb(&self) -> u64 {
+        self.max_size_bytes / BYTES_PER_MB
+    }
+
+    pub fn set_max_size_mb(&mut self, max_size_mb: u64) {
+        self.max_size_bytes = max_size_mb.saturating_mul(BYTES_PER_MB);
+    }
+
+    /// Failing to read the cache is logged and treated as a miss.
+    pub(crate) async fn get(&self, url: &str) -> Option<CachedResponse> {
+        #[cfg(not(target_arch = "wasm32"))]
+        let result = match &self.directory {
+            Some(directory) => native::get(directory.clone(), url.to_owned()).await,
+            None => Ok(None),
+        };
+        #[cfg(target_arch = "wasm32")]
+        let result = web::get(url).await;
+
+        result.unwrap_or_else(|e| {
+            bevy::log::warn!("Could not read '{}' from the HTTP cache: {:?}", url, e);
+            None
+        })
+    }
+
+    /// Failing to write the cache is logged and otherwise ignored.
+    pub(crate) async fn put(&self, url: &str, response: &CachedResponse) {
+        #[cfg(not(target_arch = "wasm32"))]
+        let result = match &self.directory {
+            Some(directory) => {
+                native::put(
+                    directory.clone(),
+                    url.to_owned(),
+                    response.clone(),
+                    self.max_size_bytes,
+                )
+                .await
+            }
+            None => Ok(()),
+        };
+        #[cfg(target_arch = "wasm32")]
+        let result = web::put(url, response, self.max_size_bytes).await;
+
+        if let Err(e) = result {
+            bevy::log::warn!("Could not write '{}' to the HTTP cache: {:?}", url, e);
+        }
+    }
+
+    pub async fn clear(&self) -> Result<(), crate::Error> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            match &self.directory {
+                Some(directory) => Ok(native::clear(directory.clone()).await?),
+                None => Ok(()),
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            web::clear()
+                .await
+                .map_err(|e| crate::Error::Cache(format!("{e:?}")))
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct CachedResponse {
+    pub bytes: bytes::Bytes,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub content_type: Option<String>,
+}
+
+impl CachedResponse {
+    /// Only responses with a validator are worth keeping, since they can be revalidated.
+    pub fn is_revalidatable(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+}
+
+pub struct ClearHttpCacheJob(pub HttpCache);
+
+impl bevy_jobs::Job for ClearHttpCacheJob {
+    type Outcome = Result<(), crate::Error>;
+    const JOB_TYPE: bevy_jobs::JobType = bevy_jobs::JobType::Io;
+
+    fn name(&self) -> String {
+        "Clearing the download cache".into()
+    }
+
+    fn perform(self, _: bevy_jobs::Context) -> bevy_jobs::AsyncReturn<Self::Outcome> {
+        Box::pin(async move { crate::await_future(async move { self.0.clear().await }).await? })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use serde_json::Value;
+    use std::{fs, io, path, time};
+
+    /// FNV-1a, which unlike `DefaultHasher` is stable across Rust releases.
+    fn key(url: &str) -> String {
+        let hash = url.bytes().fold(0xcbf2_9ce4_8422_2325, |hash: u64, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+        format!("{hash:016x}")
+    }
+
+    /// The file holding `url`'s response body (`body`) or its metadata (`json`).
+    pub(super) fn path(directory: &path::Path, url: &str, extension: &str) -> path::PathBuf {
+        directory.join(format!("{}.{extension}", key(url)))
+    }
+
+    /// Run file system calls on a thread where blocking doesn't hold up other jobs.
+    async fn blocking<T: Send + 'static>(
+        f: impl FnOnce() -> io::Result<T> + Send + 'static,
+    ) -> io::Result<T> {
+        tokio::task::spawn_blocking(f)
+            .await
+            .unwrap_or_else(|e| Err(io::Error::other(e)))
+    }
+
+    pub(super) async fn get(
+        directory: path::PathBuf,
+        url: String,
+    ) -> io::Result<Option<super::CachedResponse>> {
+        blocking(move || read(&directory, &url)).await
+    }
+
+    pub(super) async fn put(
+        directory: path::PathBuf,
+        url: String,
+        response: super::CachedResponse,
+        max_size_bytes: u64,
+    ) -> io::Result<()> {
+        blocking(move || write(&directory, &url, &response, max_size_bytes)).await
+    }
+
+    pub(super) async fn clear(directory: path::PathBuf) -> io::Result<()> {
+        blocking(move || match fs::remove_dir_all(directory) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        })
+        .await
+    }
+
+    fn read(directory: &path::Path, url: &str) -> io::Result<Option<super::CachedResponse>> {
+        let metadata = match fs::read(path(directory, url, "json")) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let metadata: Value = serde_json::from_slice(&metadata)?;
+        // Hash collision
+        if metadata.get("url").and_then(Value::as_str) != Some(url) {
+            return Ok(None);
+        }
+
+        let body_path = path(directory, url, "body");
+        let bytes = fs::read(&body_path)?;
+        // Record the access for least-recently-used eviction
+        fs::File::options()
+            .write(true)
+            .open(&body_path)?
+            .set_modified(time::SystemTime::now())?;
+
+        let field = |name| metadata.get(name).and_then(Value::as_str).map(String::from);
+        Ok(Some(super::CachedResponse {
+            bytes: bytes.into(),
+            etag: field("etag"),
+            last_modified: field("last_modified"),
+            content_type: field("content_type"),
+        }))
+    }
+
+    fn write(
+        directory: &path::Path,
+        url: &str,
+        response: &super::CachedResponse,
+        max_size_bytes: u64,
+    ) -> io::Result<()> {
+        fs::create_dir_all(directory)?;
+        fs::write(path(directory, url, "body"), &response.bytes)?;
+        fs::write(
+            path(directory, url, "json"),
+            serde_json::to_vec(&serde_json::json!({
+                "url": url,
+                "etag": response.etag,
+                "last_modified": response.last_modified,
+                "content_type": response.content_type,
+            }))?,
+        )?;
+        prune(directory, max_size_bytes)
+    }
+
+    fn prune(directory: &path::Path, max_size_bytes: u64) -> io::Result<()> {
+        let mut entries = vec![];
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "body")
+            {
+                let metadata = fs::metadata(&path)?;
+                entries.push((metadata.modified()?, metadata.len(), path));
+            }
+        }
+        entries.sort_by_key(|(modified, ..)| *modified);
+
+        let mut total_size: u64 = entries.iter().map(|(_, len, _)| len).sum();
+        for (_, len, path) in entries {
+            if total_size <= max_size_bytes {
+                break;
+            }
+            fs::remove_file(path.with_extension("json"))?;
+            fs::remove_file(path)?;
+            total_size -= len;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use wasm_bindgen::{JsCast, JsValue};
+    use wasm_bindgen_futures::JsFuture;
+
+    const CACHE_NAME: &str = "rgis-http";
+    const SIZE_HEADER: &str = "x-rgis-size";
+    const STORED_AT_HEADER: &str = "x-rgis-stored-at";
+
+    fn cache_storage() -> Result<web_sys::CacheStorage, JsValue> {
+        web_sys::window()
+            .ok_or_else(|| JsValue::from_str("no window"))?
+            .caches()
+    }
+
+    async fn open() -> Result<web_sys::Cache, JsValue> {
+        JsFuture::from(cache_storage()?.open(CACHE_NAME))
+            .await?
+            .dyn_into()
+    }
+
+    pub(super) async fn get(url: &str) -> Result<Option<super::CachedResponse>, JsValue> {
+        let cache = open().await?;
+        let response = JsFuture::from(cache.match_with_str(url)).await?;
+        if response.is_undefined() {
+            return Ok(None);
+        }
+        let response: web_sys::Response = response.dyn_into()?;
+        let headers = response.headers();
+        let array_buffer = JsFuture::from(response.array_buffer()?).await?;
+        Ok(Some(super::CachedResponse {
+            bytes: js_sys::Uint8Array::new(&array_buffer).to_vec().into(),
+            etag: headers.get("etag")?,
+            last_modified: headers.get("last-modified")?,
+            content_type: headers.get("content-type")?,
+        }))
+    }
+
+    pub(super) async fn put(
+        url: &str,
+        response: &super::CachedResponse,
+        max_size_bytes: u64,
+    ) -> Result<(), JsValue> {
+        let cache = open().await?;
+        let headers = web_sys::Headers::new()?;
+        for (name, value) in [
+            ("etag", &response.etag),
+            ("last-modified", &response.last_modified),
+            ("content-type", &response.content_type),
+        ] {
+            if let Some(value) = value {
+                headers.set(name, value)?;
+            }
+        }
+        headers.set(SIZE_HEADER, &response.bytes.len().to_string())?;
+        headers.set(STORED_AT_HEADER, &js_sys::Date::now().to_string())?;
+
+        let init = web_sys::ResponseInit::new();
+        init.set_headers(&headers);
+        let body: js_sys::Object = js_sys::Uint8Array::from(&response.bytes[..]).into();
+        let cached = web_sys::Response::new_with_opt_buffer_source_and_init(Some(&body), &init)?;
+        JsFuture::from(cache.put_with_str(url, &cached)).await?;
+
+        prune(&cache, max_size_bytes).await
+    }
+
+    async fn prune(cache: &web_sys::Cache, max_size_bytes: u64) -> Result<(), JsValue> {
+        let requests: js_sys::Array = JsFuture::from(cache.keys()).await?.dyn_into()?;
+        let mut entries = vec![];
+        for request in requests.iter() {
+            let request: web_sys::Request = request.dyn_into()?;
+            let response: web_sys::Response = JsFuture::from(cache.match_with_request(&request))
+                .await?
+                .dyn_into()?;
+            let header = |name: &str| {
+                response
+                    .headers()
+                    .get(name)
+                    .ok()
+                    .flatten()
+                    .and_then(|value| value.parse::<f64>().ok())
+                    .unwrap_or(0.)
+            };
+            entries.push((
+                header(STORED_AT_HEADER),
+                header(SIZE_HEADER) as u64,
+                request,
+            ));
+        }
+        entries.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut total_size: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        for (_, size, request) in entries {
+            if total_size <= max_size_bytes {
+                break;
+            }
+            JsFuture::from(cache.delete_with_request(&request)).await?;
+            total_size = total_size.saturating_sub(size);
+        }
+        Ok(())
+    }
+
+    pub(super) async fn clear() -> Result<(), JsValue> {
+        JsFuture::from(cache_storage()?.delete(CACHE_NAME)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use std::{fs, time};
+
+    fn cache(name: &str, max_size_bytes: u64) -> HttpCache {
+        let directory = std::env::temp_dir().join(format!(
+            "rgis-http-cache-test-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&directory);
+        HttpCache {
+            max_size_bytes,
+            directory: Some(directory),
+        }
+    }
+
+    fn response(body: &'static str) -> CachedResponse {
+        CachedResponse {
+            bytes: body.into(),
+            etag: Some("\"v1\"".into()),
+            last_modified: None,
+            content_type: None,
+        }
+    }
+
+    #[test]
+    fn test_revalidate() {
+        let not_modified =
+            || "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n".into();
+        let (base_url, requests) = crate::test_server::serve_responses(vec![
+            "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Type: application/geo+json\r\nContent-Length: 3\r\nConnection: close\r\n\r\none".into(),
+            not_modified(),
+            not_modified(),
+        ]);
+        let cache = cache("revalidate", u64::MAX);
+        let url = reqwest::Url::parse(&format!("{base_url}/parcels.geojson")).unwrap();
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let client = reqwest::Client::new();
+            let fetch = |cache| {
+                crate::fetch(
+                    &client,
+                    url.clone(),
+                    reqwest::header::HeaderMap::new(),
+                    cache,
+                    None,
+                )
+            };
+
+            let stored = fetch(Some(&cache)).await.unwrap();
+            assert_eq!(stored.bytes, "one");
+            let revalidated = fetch(Some(&cache)).await.unwrap();
+            assert_eq!(revalidated.bytes, "one");
+            assert_eq!(
+                revalidated.content_type.as_deref(),
+                Some("application/geo+json")
+            );
+            // Without a cached copy, there's no body to return
+            assert!(matches!(
+                fetch(None).await,
+                Err(crate::Error::UnexpectedResponse(_))
+            ));
+            cache.clear().await.unwrap();
+        });
+
+        let if_none_match = "if-none-match: \"v1\"";
+        assert!(!requests
+            .recv()
+            .unwrap()
+            .to_ascii_lowercase()
+            .contains(if_none_match));
+        assert!(requests
+            .recv()
+            .unwrap()
+            .to_ascii_lowercase()
+            .contains(if_none_match));
+    }
+
+    #[test]
+    fn test_evict_least_recently_used() {
+        let cache = cache("evict", 8);
+        let directory = cache.directory.clone().unwrap();
+        let set_used_at = |url, secs| {
+            fs::File::options()
+                .write(true)
+                .open(native::path(&directory, url, "body"))
+                .unwrap()
+                .set_modified(time::UNIX_EPOCH + time::Duration::from_secs(secs))
+                .unwrap();
+        };
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            cache.put("https://example.com/a", &response("aaaa")).await;
+            cache.put("https://example.com/b", &response("bbbb")).await;
+            // Modification times may not tell apart files written in quick succession
+            set_used_at("https://example.com/a", 1);
+            set_used_at("https://example.com/b", 2);
+            assert!(cache.get("https://example.com/a").await.is_some());
+
+            cache.put("https://example.com/c", &response("cccc")).await;
+            assert!(cache.get("https://example.com/a").await.is_some());
+            assert!(cache.get("https://example.com/b").await.is_none());
+            assert!(cache.get("https://example.com/c").await.is_some());
+            cache.clear().await.unwrap();
+        });
+    }
+
+    #[test]
+    fn test_clear() {
+        let cache = cache("clear", u64::MAX);
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            cache.put("https://example.com/a", &response("aaaa")).await;
+            assert!(cache.get("https://example.com/a").await.is_some());
+            cache.clear().await.unwrap();
+            assert!(cache.get("https://example.com/a").await.is_none());
+            // Clearing an empty cache is fine too
+            cache.clear().await.unwrap();
+        });
+    }
+}
@@ -9,16 +9,52 @@
 use futures_util::StreamExt;
 use std::{io, sync};
 
+mod arcgis;
+mod cache;
+mod ogc_api_features;
+mod systems;
+mod wfs;
+
+pub use arcgis::{is_arcgis_layer_url, ArcGisFeatureLayerJob};
+pub use cache::{ClearHttpCacheJob, HttpCache};
+pub use ogc_api_features::{
+    OgcApiCollection, OgcApiCollectionsJob, OgcApiItemsJob, OgcApiNextPage,
+};
+pub use wfs::{WfsCapabilities, WfsCapabilitiesJob, WfsFeatureType, WfsGetFeatureJob};
+
 pub struct FetchedFile {
     pub name: String,
+    pub url: String,
     pub bytes: bytes::Bytes,
     pub crs_epsg_code: u16,
+    /// The format requested by the user, if any.
+    pub file_format: Option<geo_file_loader::FileFormat>,
+    /// Value of the response's `Content-Type` header.
+    pub content_type: Option<String>,
+}
+
+/// Features of a page fetched from a service that pages its responses.
+pub struct FeaturesPage<J> {
+    pub features: geo_file_loader::Features,
+    /// The job fetching the next page, `None` after the last one.
+    pub next_job: Option<J>,
+}
+
+/// A job fetching a page of features, so they can be shown while the following pages arrive.
+pub trait PageJob: bevy_jobs::Job<Outcome = Result<FeaturesPage<Self>, Error>> + Sized {
+    fn is_first_page(&self) -> bool;
 }
 
 pub struct NetworkFetchJob {
     pub url: String,
     pub crs_epsg_code: u16,
     pub name: String,
+    pub file_format: Option<geo_file_loader::FileFormat>,
+    /// Extra HTTP headers sent with the request, as `(name, value)` pairs.
+    pub headers: Vec<(String, String)>,
+    /// Revalidate against and store into this cache. `None` always downloads, as do requests
+    /// with `headers`, which may hold credentials.
+    pub cache: Option<HttpCache>,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -29,6 +65,28 @@ pub enum Error {
     IoRef(#[from] &'static io::Error),
     #[error("{0}")]
     Reqwest(#[from] reqwest::Error),
+    #[error("{0}")]
+    InvalidHeaderName(#[from] reqwest::header::InvalidHeaderName),
+    #[error("{0}")]
+    InvalidHeaderValue(#[from] reqwest::header::InvalidHeaderValue),
+    #[error("{0}")]
+    Url(#[from] url::ParseError),
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
+    #[error("{0}")]
+    FileLoader(#[from] geo_file_loader::Error),
+    #[error("Unexpected response: {0}")]
+    UnexpectedResponse(&'static str),
+    #[error("{0}")]
+    Xml(#[from] roxmltree::Error),
+    #[error("Service exception: {0}")]
+    ServiceException(String),
+    #[error("Not an ArcGIS REST layer URL: {0}")]
+    NotArcGisLayerUrl(String),
+    #[error("Could not access the download cache: {0}")]
+    Cache(String),
+    #[error("Cancelled")]
+    Cancelled,
 }
 
 impl bevy_jobs::Job for NetworkFetchJob {
@@ -41,10 +99,7 @@ impl bevy_jobs::Job for NetworkFetchJob {
 
     fn perform(self, ctx: bevy_jobs::Context) -> bevy_jobs::AsyncReturn<Self::Outcome> {
         Box::pin(async move {
-            await_future(async move {
-                build_request_future(self.url, self.crs_epsg_code, self.name, ctx).await
-            })
-            .await?
+            await_future(async move { build_request_future(self, ctx).await }).await?
         })
     }
 }
@@ -52,16 +107,137 @@ impl bevy_jobs::Job for NetworkFetchJob {
 pub struct Plugin;
 
 impl bevy::app::Plugin for Plugin {
-    fn build(&self, _app: &mut bevy::app::App) {}
+    fn build(&self, app: &mut bevy::app::App) {
+        systems::configure(app);
+    }
+}
+
+fn build_header_map(headers: &[(String, String)]) -> Result<reqwest::header::HeaderMap, Error> {
+    let mut header_map = reqwest::header::HeaderMap::with_capacity(headers.len());
+    for (name, value) in headers {
+        header_map.append(
+            reqwest::header::HeaderName::from_bytes(name.trim().as_bytes())?,
+            reqwest::header::HeaderValue::from_str(value.trim())?,
+        );
+    }
+    Ok(header_map)
 }
 
 async fn build_request_future(
-    url: String,
-    crs_epsg_code: u16,
-    name: String,
+    job: NetworkFetchJob,
     ctx: bevy_jobs::Context,
 ) -> Result<FetchedFile, Error> {
-    let response = reqwest::get(url).await?;
+    let cache = job.cache.as_ref().filter(|_| job.headers.is_empty());
+    let response = fetch(
+        &reqwest::Client::new(),
+        reqwest::Url::parse(&job.url)?,
+        build_header_map(&job.headers)?,
+        cache,
+        Some(&ctx),
+    )
+    .await?
+    .error_for_status()?;
+    Ok(job.into_fetched_file(response.bytes, response.content_type))
+}
+
+/// A response, downloaded or from the cache.
+struct Response {
+    /// After redirects
+    url: reqwest::Url,
+    bytes: bytes::Bytes,
+    content_type: Option<String>,
+    /// Set for error statuses, whose body may describe the error
+    status_error: Option<reqwest::Error>,
+}
+
+impl Response {
+    fn error_for_status(self) -> Result<Self, Error> {
+        match self.status_error {
+            Some(e) => Err(e.into()),
+            None => Ok(self),
+        }
+    }
+
+    fn from_cache(url: reqwest::Url, cached: cache::CachedResponse) -> Self {
+        Response {
+            url,
+            bytes: cached.bytes,
+            content_type: cached.content_type,
+            status_error: None,
+        }
+    }
+}
+
+/// Send a GET request, revalidating the copy of the response in `cache` if there's one and
+/// storing the response there otherwise. Download progress is reported to `ctx`, if any.
+async fn fetch(
+    client: &reqwest::Client,
+    url: reqwest::Url,
+    mut headers: reqwest::header::HeaderMap,
+    cache: Option<&HttpCache>,
+    ctx: Option<&bevy_jobs::Context>,
+) -> Result<Response, Error> {
+    let cached = match cache {
+        Some(cache) => cache.get(url.as_str()).await,
+        None => None,
+    };
+
+    if let Some(cached) = &cached {
+        if let Some(etag) = &cached.etag {
+            headers.insert(
+                reqwest::header::IF_NONE_MATCH,
+                reqwest::header::HeaderValue::from_str(etag)?,
+            );
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            headers.insert(
+                reqwest::header::IF_MODIFIED_SINCE,
+                reqwest::header::HeaderValue::from_str(last_modified)?,
+            );
+        }
+    }
+
+    let response = match client.get(url.clone()).headers(headers).send().await {
+        Ok(response) => response,
+        // Offline: fall back to the cached copy, however stale
+        Err(e) => match cached {
+            Some(cached) => {
+                bevy::log::warn!(
+                    "Could not revalidate '{}', using the cached copy: {}",
+                    url,
+                    e
+                );
+                return Ok(Response::from_cache(url, cached));
+            }
+            None => return Err(e.into()),
+        },
+    };
+
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        // Only sent for a request with validators, e.g. after the cached copy was evicted
+        let Some(cached) = cached else {
+            return Err(Error::UnexpectedResponse(
+                "304 Not Modified without a cached copy",
+            ));
+        };
+        if let Some(ctx) = ctx {
+            let _ = ctx.send_progress(100).await;
+        }
+        return Ok(Response::from_cache(response.url().clone(), cached));
+    }
+    let status_error = response.error_for_status_ref().err();
+
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value: &reqwest::header::HeaderValue| value.to_str().ok())
+            .map(String::from)
+    };
+    let content_type = header(reqwest::header::CONTENT_TYPE);
+    let etag = header(reqwest::header::ETAG);
+    let last_modified = header(reqwest::header::LAST_MODIFIED);
+    let final_url = response.url().clone();
     let total_size = response.content_length().unwrap_or(0);
     let mut bytes_stream = response.bytes_stream();
     let mut bytes = Vec::<u8>::with_capacity(total_size as usize);
@@ -70,22 +246,64 @@ async fn build_request_future(
     while let Some(bytes_chunk) = bytes_stream.next().await {
         let mut bytes_chunk = Vec::from(bytes_chunk?);
         bytes.append(&mut bytes_chunk);
-        if total_size > 0 {
+        if let Some(ctx) = ctx.filter(|_| total_size > 0) {
             let new_percent = (100 * bytes.len() / total_size as usize) as u8;
             if new_percent != last_percent {
-                let _ = ctx.send_progress(new_percent).await;
+                send_progress(ctx, new_percent).await?;
                 last_percent = new_percent;
             }
         }
     }
 
-    Ok(FetchedFile {
+    let response = cache::CachedResponse {
         bytes: bytes::Bytes::from(bytes),
-        crs_epsg_code,
-        name,
+        etag,
+        last_modified,
+        content_type,
+    };
+    if let Some(cache) = cache {
+        if status_error.is_none() && response.is_revalidatable() {
+            cache.put(url.as_str(), &response).await;
+        }
+    }
+
+    Ok(Response {
+        url: final_url,
+        bytes: response.bytes,
+        content_type: response.content_type,
+        status_error,
     })
 }
 
+impl NetworkFetchJob {
+    fn into_fetched_file(self, bytes: bytes::Bytes, content_type: Option<String>) -> FetchedFile {
+        FetchedFile {
+            bytes,
+            crs_epsg_code: self.crs_epsg_code,
+            name: self.name,
+            url: self.url,
+            file_format: self.file_format,
+            content_type,
+        }
+    }
+}
+
+/// Report progress, failing once the job has been cancelled and nothing receives it anymore.
+async fn send_progress(ctx: &bevy_jobs::Context, percent: u8) -> Result<(), Error> {
+    ctx.send_progress(percent)
+        .await
+        .map_err(|_| Error::Cancelled)
+}
+
+/// Combine features fetched page by page into a single GeoJSON feature collection.
+fn geojson_feature_collection(features: Vec<serde_json::Value>) -> Result<bytes::Bytes, Error> {
+    let bytes = serde_json::to_vec(&serde_json::json!({
+        "type": "FeatureCollection",
+        "features": features,
+    }))?;
+    Ok(bytes.into())
+}
+
 async fn await_future<Output>(
     future: impl std::future::Future<Output = Output> + 'static,
 ) -> Result<Output, &'static io::Error> {
@@ -107,3 +325,54 @@ async fn await_future<Output>(
         Ok(future.await)
     }
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+#[allow(clippy::unwrap_used)]
+mod test_server {
+    use std::io::{Read, Write};
+    use std::{net, sync::mpsc, thread};
+
+    /// Answer successive connections with JSON `bodies`. Returns the server's base URL and a
+    /// receiver of each request line.
+    pub fn serve(bodies: Vec<serde_json::Value>) -> (String, mpsc::Receiver<String>) {
+        let responses = bodies
+            .into_iter()
+            .map(|body| {
+                let body = body.to_string();
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len(),
+                )
+            })
+            .collect();
+        spawn(responses, |request| {
+            request.lines().next().unwrap_or_default().to_owned()
+        })
+    }
+
+    /// Answer successive connections with raw HTTP `responses`. Returns the server's base URL
+    /// and a receiver of each request, headers included.
+    pub fn serve_responses(responses: Vec<String>) -> (String, mpsc::Receiver<String>) {
+        spawn(responses, str::to_owned)
+    }
+
+    fn spawn(
+        responses: Vec<String>,
+        summarize: fn(&str) -> String,
+    ) -> (String, mpsc::Receiver<String>) {
+        let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0; 4096];
+                let len = stream.read(&mut request).unwrap();
+                let request = String::from_utf8_lossy(request.get(..len).unwrap());
+                sender.send(summarize(&request)).unwrap();
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (base_url, receiver)
+    }
+}
@@ -0,0 +1,335 @@
+use serde_json::Value;
+
+const GEOJSON_MEDIA_TYPES: [&str; 2] = ["application/geo+json", "application/vnd.geo+json"];
+
+// Number of features requested per page
+const PAGE_LIMIT: u32 = 1000;
+
+/// A feature collection advertised by an OGC API – Features service.
+#[derive(Clone, Debug)]
+pub struct OgcApiCollection {
+    pub id: String,
+    pub title: String,
+    pub items_url: String,
+}
+
+pub struct OgcApiCollectionsJob {
+    pub landing_page_url: String,
+}
+
+impl bevy_jobs::Job for OgcApiCollectionsJob {
+    type Outcome = Result<Vec<OgcApiCollection>, crate::Error>;
+    const JOB_TYPE: bevy_jobs::JobType = bevy_jobs::JobType::Io;
+
+    fn name(&self) -> String {
+        format!("Fetching collections of '{}'", self.landing_page_url)
+    }
+
+    fn perform(self, _: bevy_jobs::Context) -> bevy_jobs::AsyncReturn<Self::Outcome> {
+        Box::pin(async move {
+            crate::await_future(async move { fetch_collections(&self.landing_page_url).await })
+                .await?
+        })
+    }
+}
+
+/// Fetches a page of an OGC API – Features collection's `/items` endpoint. The outcome holds the
+/// job fetching the next page, so features can be shown while the following pages arrive.
+#[derive(Clone)]
+pub struct OgcApiItemsJob {
+    pub name: String,
+    pub items_url: String,
+    /// `[min_lon, min_lat, max_lon, max_lat]` in CRS84.
+    pub bbox: Option<[f64; 4]>,
+    /// Where the previous page left off, `None` to fetch the first page.
+    pub next_page: Option<OgcApiNextPage>,
+    /// Revalidate against and store into this cache. `None` always downloads.
+    pub cache: Option<crate::HttpCache>,
+}
+
+/// The `next` link of a page of items, and how much was fetched before it.
+#[derive(Clone)]
+pub struct OgcApiNextPage {
+    url: reqwest::Url,
+    /// Starting from 1
+    number: u32,
+    fetched_count: u64,
+    number_matched: Option<u64>,
+}
+
+impl crate::PageJob for OgcApiItemsJob {
+    fn is_first_page(&self) -> bool {
+        self.next_page.is_none()
+    }
+}
+
+impl bevy_jobs::Job for OgcApiItemsJob {
+    type Outcome = Result<crate::FeaturesPage<Self>, crate::Error>;
+    const JOB_TYPE: bevy_jobs::JobType = bevy_jobs::JobType::Io;
+
+    fn name(&self) -> String {
+        match &self.next_page {
+            Some(next_page) => format!("Fetching '{}', page {}", self.name, next_page.number),
+            None => format!("Fetching '{}'", self.name),
+        }
+    }
+
+    fn perform(self, ctx: bevy_jobs::Context) -> bevy_jobs::AsyncReturn<Self::Outcome> {
+        Box::pin(
+            async move { crate::await_future(async move { fetch_items(self, ctx).await }).await? },
+        )
+    }
+}
+
+async fn fetch_collections(landing_page_url: &str) -> Result<Vec<OgcApiCollection>, crate::Error> {
+    let landing_page_url =
+        reqwest::Url::parse(&format!("{}/", landing_page_url.trim_end_matches('/')))?;
+    let response = reqwest::Client::new()
+        .get(landing_page_url.join("collections")?)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .send()
+        .await?
+        .error_for_status()?;
+    let base_url = response.url().clone();
+    let value: Value = serde_json::from_slice(&response.bytes().await?)?;
+    let collections = value
+        .get("collections")
+        .and_then(Value::as_array)
+        .ok_or(crate::Error::UnexpectedResponse("missing `collections`"))?;
+    Ok(collections
+        .iter()
+        .filter_map(|collection| parse_collection(collection, &base_url))
+        .collect())
+}
+
+fn parse_collection(collection: &Value, base_url: &reqwest::Url) -> Option<OgcApiCollection> {
+    let id = collection.get("id")?.as_str()?;
+    let title = collection
+        .get("title")
+        .and_then(Value::as_str)
+        .unwrap_or(id);
+    let links = collection
+        .get("links")
+        .and_then(Value::as_array)
+        .map_or(&[][..], Vec::as_slice);
+    let items_url = match find_link(links, "items") {
+        Some(href) => base_url.join(href).ok()?,
+        None => base_url.join(&format!("collections/{id}/items")).ok()?,
+    };
+    Some(OgcApiCollection {
+        id: id.into(),
+        title: title.into(),
+        items_url: items_url.into(),
+    })
+}
+
+/// Find the `href` of a link with the given relation, preferring GeoJSON links when the
+/// service offers several encodings.
+fn find_link<'a>(links: &'a [Value], rel: &str) -> Option<&'a str> {
+    let links = links
+        .iter()
+        .filter(|link| link.get("rel").and_then(Value::as_str) == Some(rel))
+        .collect::<Vec<_>>();
+    let link = links
+        .iter()
+        .find(|link| {
+            link.get("type")
+                .and_then(Value::as_str)
+                .is_none_or(|media_type| GEOJSON_MEDIA_TYPES.contains(&media_type))
+        })
+        .or_else(|| links.first())?;
+    link.get("href")?.as_str()
+}
+
+async fn fetch_items(
+    mut job: OgcApiItemsJob,
+    ctx: bevy_jobs::Context,
+) -> Result<crate::FeaturesPage<OgcApiItemsJob>, crate::Error> {
+    let (url, number, fetched_count) = match job.next_page.take() {
+        Some(next_page) => {
+            if let Some(number_matched) = next_page.number_matched.filter(|n| *n > 0) {
+                let percent = (100 * next_page.fetched_count / number_matched).min(100) as u8;
+                crate::send_progress(&ctx, percent).await?;
+            }
+            (next_page.url, next_page.number, next_page.fetched_count)
+        }
+        None => (first_page_url(&job.items_url, job.bbox, PAGE_LIMIT)?, 1, 0),
+    };
+
+    let page = fetch_page(&reqwest::Client::new(), url, job.cache.as_ref()).await?;
+    let next_page = page.next_url.map(|url| OgcApiNextPage {
+        url,
+        number: number + 1,
+        fetched_count: fetched_count + page.feature_count,
+        number_matched: page.number_matched,
+    });
+    Ok(crate::FeaturesPage {
+        features: page.features,
+        next_job: next_page.map(|next_page| OgcApiItemsJob {
+            next_page: Some(next_page),
+            ..job
+        }),
+    })
+}
+
+fn first_page_url(
+    items_url: &str,
+    bbox: Option<[f64; 4]>,
+    limit: u32,
+) -> Result<reqwest::Url, crate::Error> {
+    let mut url = reqwest::Url::parse(items_url)?;
+    {
+        let mut query = url.query_pairs_mut();
+        query.append_pair("limit", &limit.to_string());
+        if let Some([min_x, min_y, max_x, max_y]) = bbox {
+            query.append_pair("bbox", &format!("{min_x},{min_y},{max_x},{max_y}"));
+        }
+    }
+    Ok(url)
+}
+
+struct ItemPage {
+    features: geo_file_loader::Features,
+    /// Including features without a geometry, which aren't loaded
+    feature_count: u64,
+    number_matched: Option<u64>,
+    next_url: Option<reqwest::Url>,
+}
+
+/// The members of an `/items` response besides the features' content, which is parsed by
+/// `geo_file_loader`.
+#[derive(serde::Deserialize)]
+struct ItemsResponse {
+    features: Vec<serde::de::IgnoredAny>,
+    #[serde(rename = "numberMatched")]
+    number_matched: Option<u64>,
+    #[serde(default)]
+    links: Vec<Value>,
+}
+
+async fn fetch_page(
+    client: &reqwest::Client,
+    url: reqwest::Url,
+    cache: Option<&crate::HttpCache>,
+) -> Result<ItemPage, crate::Error> {
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        reqwest::header::ACCEPT,
+        reqwest::header::HeaderValue::from_str(&GEOJSON_MEDIA_TYPES.join(", "))?,
+    );
+    let crate::Response {
+        url: base_url,
+        bytes,
+        ..
+    } = crate::fetch(client, url, headers, cache, None)
+        .await?
+        .error_for_status()?;
+
+    let items: ItemsResponse = serde_json::from_slice(&bytes)?;
+    // Some servers keep advertising a `next` link after the last feature
+    let next_url = if items.features.is_empty() {
+        None
+    } else {
+        find_link(&items.links, "next").and_then(|href| base_url.join(href).ok())
+    };
+
+    Ok(ItemPage {
+        features: geo_file_loader::load_file(geo_file_loader::FileFormat::GeoJson, bytes)?,
+        feature_count: items.features.len() as u64,
+        number_matched: items.number_matched,
+        next_url,
+    })
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn feature(geometry: Option<serde_json::Value>) -> serde_json::Value {
+        serde_json::json!({"type": "Feature", "geometry": geometry, "properties": {}})
+    }
+
+    #[test]
+    fn test_pages_follow_next_links() {
+        let point = || Some(serde_json::json!({"type": "Point", "coordinates": [-73.5, 40.5]}));
+        let (base_url, requests) = crate::test_server::serve(vec![
+            serde_json::json!({
+                "type": "FeatureCollection",
+                "numberMatched": 3,
+                "features": [feature(point()), feature(None)],
+                "links": [
+                    {"rel": "next", "type": "text/html", "href": "/items.html?offset=2"},
+                    {"rel": "next", "type": "application/geo+json", "href": "/items?offset=2"},
+                ],
+            }),
+            serde_json::json!({
+                "type": "FeatureCollection",
+                "features": [feature(point())],
+                "links": [{"rel": "next", "href": "/items?offset=3"}],
+            }),
+            // Some servers keep advertising a `next` link after the last feature
+            serde_json::json!({
+                "type": "FeatureCollection",
+                "features": [],
+                "links": [{"rel": "next", "href": "/items?offset=3"}],
+            }),
+        ]);
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let pages = runtime.block_on(async {
+            let client = reqwest::Client::new();
+            let mut next_url = Some(
+                first_page_url(
+                    &format!("{base_url}/collections/parcels/items"),
+                    Some([-74., 40., -73., 41.]),
+                    2,
+                )
+                .unwrap(),
+            );
+            let mut pages = vec![];
+            while let Some(url) = next_url {
+                let page = fetch_page(&client, url, None).await.unwrap();
+                next_url = page.next_url.clone();
+                pages.push(page);
+            }
+            pages
+        });
+
+        assert_eq!(
+            pages
+                .iter()
+                .map(|page| (page.features.len(), page.feature_count, page.number_matched))
+                .collect::<Vec<_>>(),
+            [(1, 2, Some(3)), (1, 1, None), (0, 0, None)]
+        );
+        assert_eq!(
+            requests.recv().unwrap(),
+            "GET /collections/parcels/items?limit=2&bbox=-74%2C40%2C-73%2C41 HTTP/1.1"
+        );
+        assert_eq!(requests.recv().unwrap(), "GET /items?offset=2 HTTP/1.1");
+        assert_eq!(requests.recv().unwrap(), "GET /items?offset=3 HTTP/1.1");
+    }
+
+    #[test]
+    fn test_job_name_counts_pages() {
+        let job = |next_page| OgcApiItemsJob {
+            name: "Parcels".into(),
+            items_url: "https://example.com/collections/parcels/items".into(),
+            bbox: None,
+            next_page,
+            cache: None,
+        };
+        assert_eq!(bevy_jobs::Job::name(&job(None)), "Fetching 'Parcels'");
+        let next_page = OgcApiNextPage {
+            url: reqwest::Url::parse("https://example.com/items?offset=1000").unwrap(),
+            number: 2,
+            fetched_count: 1000,
+            number_matched: None,
+        };
+        assert_eq!(
+            bevy_jobs::Job::name(&job(Some(next_page))),
+            "Fetching 'Parcels', page 2"
+        );
+    }
+}
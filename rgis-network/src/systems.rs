@@ -0,0 +1,39 @@
+use bevy::prelude::*;
+
+fn handle_clear_http_cache_events(
+    mut clear_http_cache_events: ResMut<Events<rgis_events::ClearHttpCacheEvent>>,
+    mut job_spawner: bevy_jobs::JobSpawner,
+    http_cache: Res<crate::HttpCache>,
+) {
+    if clear_http_cache_events.drain().count() > 0 {
+        job_spawner.spawn(crate::ClearHttpCacheJob(http_cache.clone()));
+    }
+}
+
+fn handle_clear_http_cache_finished_jobs(
+    mut finished_jobs: bevy_jobs::FinishedJobs,
+    mut error_notification_event_writer: EventWriter<rgis_events::ErrorNotificationEvent>,
+) {
+    while let Some(outcome) = finished_jobs.take_next::<crate::ClearHttpCacheJob>() {
+        match outcome {
+            Ok(()) => bevy::log::info!("Cleared the download cache"),
+            Err(e) => {
+                bevy::log::error!("Could not clear the download cache: {:?}", e);
+                error_notification_event_writer.write(rgis_events::ErrorNotificationEvent::new(
+                    "Could not clear the download cache",
+                    &e,
+                ));
+            }
+        }
+    }
+}
+
+pub fn configure(app: &mut App) {
+    app.init_resource::<crate::HttpCache>().add_systems(
+        Update,
+        (
+            handle_clear_http_cache_events,
+            handle_clear_http_cache_finished_jobs,
+        ),
+    );
+}
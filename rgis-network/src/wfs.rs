@@ -0,0 +1,558 @@
+use geo::MapCoordsInPlace;
+use roxmltree::Node;
+
+// Most features requested per `GetFeature` request
+const PAGE_SIZE: u32 = 1000;
+
+/// A feature type advertised in a WFS `GetCapabilities` response.
+#[derive(Clone, Debug)]
+pub struct WfsFeatureType {
+    pub name: String,
+    pub title: String,
+    pub default_crs_epsg_code: Option<u16>,
+}
+
+#[derive(Clone, Debug)]
+pub struct WfsCapabilities {
+    pub feature_types: Vec<WfsFeatureType>,
+    /// A `GetFeature` output format producing GeoJSON, if the service advertises one.
+    pub geojson_output_format: Option<String>,
+    /// Whether `GetFeature` responses can be paged with `COUNT` and `STARTINDEX`.
+    pub implements_result_paging: bool,
+    /// Most features the service returns per response, if it says.
+    pub count_default: Option<u32>,
+}
+
+impl WfsCapabilities {
+    /// Features to request per `GetFeature` request, `None` to request all of them at once.
+    pub fn page_size(&self) -> Option<u32> {
+        self.implements_result_paging.then(|| {
+            self.count_default
+                .filter(|count| *count > 0)
+                .map_or(PAGE_SIZE, |count| count.min(PAGE_SIZE))
+        })
+    }
+}
+
+pub struct WfsCapabilitiesJob {
+    pub service_url: String,
+}
+
+impl bevy_jobs::Job for WfsCapabilitiesJob {
+    type Outcome = Result<WfsCapabilities, crate::Error>;
+    const JOB_TYPE: bevy_jobs::JobType = bevy_jobs::JobType::Io;
+
+    fn name(&self) -> String {
+        format!("Fetching capabilities of '{}'", self.service_url)
+    }
+
+    fn perform(self, _: bevy_jobs::Context) -> bevy_jobs::AsyncReturn<Self::Outcome> {
+        Box::pin(async move {
+            crate::await_future(async move { fetch_capabilities(&self.service_url).await }).await?
+        })
+    }
+}
+
+/// Fetches the features of a WFS 2.0 feature type with `GetFeature` requests, a page at a time
+/// when `page_size` is set.
+#[derive(Clone)]
+pub struct WfsGetFeatureJob {
+    pub name: String,
+    pub service_url: String,
+    pub type_name: String,
+    /// Without one, the server responds with GML 3.2.
+    pub output_format: Option<String>,
+    pub crs_epsg_code: u16,
+    /// `[min_x, min_y, max_x, max_y]` in the CRS `crs_epsg_code`.
+    pub bbox: Option<[f64; 4]>,
+    pub page_size: Option<u32>,
+    /// Features fetched by the previous requests
+    pub start_index: u64,
+    /// As reported by the previous response
+    pub number_matched: Option<u64>,
+    /// Revalidate against and store into this cache. `None` always downloads.
+    pub cache: Option<crate::HttpCache>,
+}
+
+impl crate::PageJob for WfsGetFeatureJob {
+    fn is_first_page(&self) -> bool {
+        self.start_index == 0
+    }
+}
+
+impl bevy_jobs::Job for WfsGetFeatureJob {
+    type Outcome = Result<crate::FeaturesPage<Self>, crate::Error>;
+    const JOB_TYPE: bevy_jobs::JobType = bevy_jobs::JobType::Io;
+
+    fn name(&self) -> String {
+        match self.page_size {
+            Some(page_size) if self.start_index > 0 => format!(
+                "Fetching '{}', page {}",
+                self.name,
+                self.start_index / u64::from(page_size.max(1)) + 1
+            ),
+            _ => format!("Fetching '{}'", self.name),
+        }
+    }
+
+    fn perform(self, ctx: bevy_jobs::Context) -> bevy_jobs::AsyncReturn<Self::Outcome> {
+        Box::pin(async move {
+            crate::await_future(async move { fetch_features(self, ctx).await }).await?
+        })
+    }
+}
+
+/// Whether WFS 2.0 servers give coordinates in the CRS latitude first, as its definition says.
+/// Only the one most services offer is listed.
+fn has_lat_lon_axis_order(crs_epsg_code: u16) -> bool {
+    crs_epsg_code == 4326
+}
+
+/// Build a WFS 2.0 `GetFeature` URL for the job's page.
+///
+/// The CRS is requested as `EPSG:<code>`, which most servers answer in easting/northing axis
+/// order, except for CRSs with a latitude-first axis order. Those are requested as a URN, which
+/// servers answer latitude first, and the bbox is given latitude first too.
+fn get_feature_url(job: &WfsGetFeatureJob) -> Result<reqwest::Url, crate::Error> {
+    let crs_epsg_code = job.crs_epsg_code;
+    let lat_lon = has_lat_lon_axis_order(crs_epsg_code);
+    let crs_name = if lat_lon {
+        format!("urn:ogc:def:crs:EPSG::{crs_epsg_code}")
+    } else {
+        format!("EPSG:{crs_epsg_code}")
+    };
+    let mut url = service_request_url(&job.service_url, "GetFeature")?;
+    {
+        let mut query = url.query_pairs_mut();
+        query
+            .append_pair("VERSION", "2.0.0")
+            .append_pair("TYPENAMES", &job.type_name)
+            .append_pair("SRSNAME", &crs_name);
+        if let Some(output_format) = &job.output_format {
+            query.append_pair("OUTPUTFORMAT", output_format);
+        }
+        if let Some([min_x, min_y, max_x, max_y]) = job.bbox {
+            let bbox = if lat_lon {
+                format!("{min_y},{min_x},{max_y},{max_x},{crs_name}")
+            } else {
+                format!("{min_x},{min_y},{max_x},{max_y},{crs_name}")
+            };
+            query.append_pair("BBOX", &bbox);
+        }
+        if let Some(page_size) = job.page_size {
+            query
+                .append_pair("COUNT", &page_size.to_string())
+                .append_pair("STARTINDEX", &job.start_index.to_string());
+        }
+    }
+    Ok(url)
+}
+
+async fn fetch_features(
+    job: WfsGetFeatureJob,
+    ctx: bevy_jobs::Context,
+) -> Result<crate::FeaturesPage<WfsGetFeatureJob>, crate::Error> {
+    if let Some(number_matched) = job.number_matched.filter(|n| *n > 0) {
+        let percent = (100 * job.start_index / number_matched).min(100) as u8;
+        crate::send_progress(&ctx, percent).await?;
+    }
+
+    let response = crate::fetch(
+        &reqwest::Client::new(),
+        get_feature_url(&job)?,
+        reqwest::header::HeaderMap::new(),
+        job.cache.as_ref(),
+        None,
+    )
+    .await?;
+    // WFS 2.0 servers report exceptions with an error status, older ones with `200 OK`
+    let page = parse_features(&job, response.bytes)?;
+    if let Some(e) = response.status_error {
+        return Err(e.into());
+    }
+
+    let next_job = next_job(job, &page);
+    Ok(crate::FeaturesPage {
+        features: page.features,
+        next_job,
+    })
+}
+
+/// The job fetching the page after `page`, if there's one.
+fn next_job(job: WfsGetFeatureJob, page: &FeaturesResponse) -> Option<WfsGetFeatureJob> {
+    let fetched_count = job.start_index + page.number_returned;
+    // Fewer features than requested means there are none left
+    let has_next_page = job
+        .page_size
+        .is_some_and(|page_size| page.number_returned >= u64::from(page_size.max(1)))
+        && page
+            .number_matched
+            .is_none_or(|number_matched| fetched_count < number_matched);
+    has_next_page.then(|| WfsGetFeatureJob {
+        start_index: fetched_count,
+        number_matched: page.number_matched,
+        ..job
+    })
+}
+
+struct FeaturesResponse {
+    features: geo_file_loader::Features,
+    number_returned: u64,
+    number_matched: Option<u64>,
+}
+
+/// The members of a GeoJSON `GetFeature` response besides the features' content, which is
+/// parsed by `geo_file_loader`.
+#[derive(serde::Deserialize)]
+struct GeoJsonResponse {
+    features: Vec<serde::de::IgnoredAny>,
+    #[serde(rename = "numberMatched")]
+    number_matched: Option<u64>,
+}
+
+fn parse_features(
+    job: &WfsGetFeatureJob,
+    bytes: bytes::Bytes,
+) -> Result<FeaturesResponse, crate::Error> {
+    let xml = std::str::from_utf8(&bytes)
+        .ok()
+        .filter(|text| text.trim_start().starts_with('<'));
+    // Exceptions are XML whichever output format was requested
+    let Some(xml) = xml else {
+        let response: GeoJsonResponse = serde_json::from_slice(&bytes)?;
+        return Ok(FeaturesResponse {
+            features: geo_file_loader::load_file(geo_file_loader::FileFormat::GeoJson, bytes)?,
+            number_returned: response.features.len() as u64,
+            number_matched: response.number_matched,
+        });
+    };
+
+    let document = roxmltree::Document::parse(xml)?;
+    let root = document.root_element();
+    check_exception_report(root)?;
+    // `numberMatched` may be `unknown`
+    let count = |name| root.attribute(name).and_then(|n| n.parse().ok());
+    let number_returned = count("numberReturned");
+    let number_matched = count("numberMatched");
+
+    let mut features = geo_file_loader::load_file(geo_file_loader::FileFormat::Gml, bytes)?;
+    // Unlike GeoJSON, which is always longitude first, GML follows the CRS's axis order
+    if has_lat_lon_axis_order(job.crs_epsg_code) {
+        for feature in &mut features {
+            feature.geometry.map_coords_in_place(|coord| {
+                geo::coord! {
+                    x: coord.y,
+                    y: coord.x,
+                }
+            });
+        }
+    }
+    Ok(FeaturesResponse {
+        number_returned: number_returned.unwrap_or(features.len() as u64),
+        number_matched,
+        features,
+    })
+}
+
+/// Fails with the exception texts of an OWS exception report.
+fn check_exception_report(root: Node) -> Result<(), crate::Error> {
+    if root.tag_name().name() != "ExceptionReport" {
+        return Ok(());
+    }
+    let message = root
+        .descendants()
+        .filter(|node| node.tag_name().name() == "ExceptionText")
+        .filter_map(|node| node.text())
+        .collect::<Vec<_>>()
+        .join("\n");
+    Err(crate::Error::ServiceException(message))
+}
+
+/// Replace any request parameters already present in `service_url`, keeping the others (e.g.
+/// MapServer's `map`).
+fn service_request_url(service_url: &str, request: &str) -> Result<reqwest::Url, crate::Error> {
+    let mut url = reqwest::Url::parse(service_url.trim())?;
+    let retained = url
+        .query_pairs()
+        .filter(|(key, _)| {
+            !["service", "request", "version", "acceptversions"]
+                .contains(&key.to_ascii_lowercase().as_str())
+        })
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect::<Vec<_>>();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(retained)
+        .append_pair("SERVICE", "WFS")
+        .append_pair("REQUEST", request);
+    Ok(url)
+}
+
+async fn fetch_capabilities(service_url: &str) -> Result<WfsCapabilities, crate::Error> {
+    let mut url = service_request_url(service_url, "GetCapabilities")?;
+    url.query_pairs_mut().append_pair("ACCEPTVERSIONS", "2.0.0");
+    let text = reqwest::Client::new()
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    parse_capabilities(&text)
+}
+
+fn parse_capabilities(text: &str) -> Result<WfsCapabilities, crate::Error> {
+    let document = roxmltree::Document::parse(text)?;
+    let root = document.root_element();
+
+    check_exception_report(root)?;
+
+    let feature_types = root
+        .descendants()
+        .filter(|node| node.tag_name().name() == "FeatureType")
+        .filter_map(|feature_type| {
+            let name = child_text(feature_type, "Name")?;
+            Some(WfsFeatureType {
+                name: name.into(),
+                title: child_text(feature_type, "Title").unwrap_or(name).into(),
+                default_crs_epsg_code: child_text(feature_type, "DefaultCRS")
+                    .or_else(|| child_text(feature_type, "DefaultSRS"))
+                    .and_then(epsg_code_from_crs_name),
+            })
+        })
+        .collect();
+
+    // `outputFormat` can be declared for every operation or for `GetFeature` only
+    let geojson_output_format = root
+        .descendants()
+        .filter(|node| {
+            node.tag_name().name() == "Parameter"
+                && node
+                    .attribute("name")
+                    .is_some_and(|name| name.eq_ignore_ascii_case("outputFormat"))
+        })
+        .filter(|parameter| match parameter.parent_element() {
+            Some(parent) if parent.tag_name().name() == "Operation" => {
+                parent.attribute("name") == Some("GetFeature")
+            }
+            _ => true,
+        })
+        .flat_map(|parameter| parameter.descendants())
+        .filter(|node| node.tag_name().name() == "Value")
+        .filter_map(|node| node.text())
+        .find(|output_format| output_format.to_ascii_lowercase().contains("json"))
+        .map(String::from);
+
+    Ok(WfsCapabilities {
+        feature_types,
+        geojson_output_format,
+        implements_result_paging: constraint(root, "ImplementsResultPaging")
+            .is_some_and(|value| value.eq_ignore_ascii_case("true")),
+        count_default: constraint(root, "CountDefault").and_then(|value| value.parse().ok()),
+    })
+}
+
+/// The default value of a constraint declared in `OperationsMetadata`.
+fn constraint<'a>(root: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    root.descendants()
+        .filter(|node| {
+            node.tag_name().name() == "Constraint" && node.attribute("name") == Some(name)
+        })
+        .find_map(|node| child_text(node, "DefaultValue"))
+}
+
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children()
+        .find(|child| child.tag_name().name() == name)?
+        .text()
+        .map(str::trim)
+}
+
+/// Parses `EPSG:4326`, `urn:ogc:def:crs:EPSG::4326`, `http://www.opengis.net/def/crs/EPSG/0/4326`
+/// and similar spellings.
+fn epsg_code_from_crs_name(crs_name: &str) -> Option<u16> {
+    if crs_name.ends_with("CRS84") {
+        return Some(4326);
+    }
+    if !crs_name.to_ascii_lowercase().contains("epsg") {
+        return None;
+    }
+    crs_name
+        .rsplit(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_capabilities() {
+        let capabilities = parse_capabilities(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<wfs:WFS_Capabilities version="2.0.0" xmlns:wfs="http://www.opengis.net/wfs/2.0"
+    xmlns:ows="http://www.opengis.net/ows/1.1">
+  <ows:OperationsMetadata>
+    <ows:Constraint name="ImplementsResultPaging">
+      <ows:NoValues/><ows:DefaultValue>TRUE</ows:DefaultValue>
+    </ows:Constraint>
+    <ows:Constraint name="CountDefault">
+      <ows:NoValues/><ows:DefaultValue>500</ows:DefaultValue>
+    </ows:Constraint>
+    <ows:Operation name="DescribeFeatureType">
+      <ows:Parameter name="outputFormat">
+        <ows:AllowedValues><ows:Value>application/json</ows:Value></ows:AllowedValues>
+      </ows:Parameter>
+    </ows:Operation>
+    <ows:Operation name="GetFeature">
+      <ows:Parameter name="outputFormat">
+        <ows:AllowedValues>
+          <ows:Value>application/gml+xml; version=3.2</ows:Value>
+          <ows:Value>application/json; subtype=geojson</ows:Value>
+        </ows:AllowedValues>
+      </ows:Parameter>
+    </ows:Operation>
+  </ows:OperationsMetadata>
+  <wfs:FeatureTypeList>
+    <wfs:FeatureType>
+      <wfs:Name>city:parcels</wfs:Name>
+      <wfs:Title>Parcels</wfs:Title>
+      <wfs:DefaultCRS>urn:ogc:def:crs:EPSG::2263</wfs:DefaultCRS>
+    </wfs:FeatureType>
+    <wfs:FeatureType>
+      <wfs:Name>city:hydrants</wfs:Name>
+    </wfs:FeatureType>
+  </wfs:FeatureTypeList>
+</wfs:WFS_Capabilities>"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            capabilities.geojson_output_format.as_deref(),
+            Some("application/json; subtype=geojson")
+        );
+        let [parcels, hydrants] = capabilities.feature_types.as_slice() else {
+            panic!("expected 2 feature types");
+        };
+        assert_eq!(parcels.title, "Parcels");
+        assert_eq!(parcels.default_crs_epsg_code, Some(2263));
+        assert_eq!(hydrants.title, "city:hydrants");
+        assert_eq!(hydrants.default_crs_epsg_code, None);
+        assert_eq!(capabilities.page_size(), Some(500));
+    }
+
+    fn job(crs_epsg_code: u16, page_size: Option<u32>) -> WfsGetFeatureJob {
+        WfsGetFeatureJob {
+            name: "Parcels".into(),
+            service_url: "https://example.com/mapserv?map=city.map&request=GetCapabilities".into(),
+            type_name: "parcels".into(),
+            output_format: None,
+            crs_epsg_code,
+            bbox: Some([1., 2., 3., 4.]),
+            page_size,
+            start_index: 0,
+            number_matched: None,
+            cache: None,
+        }
+    }
+
+    fn features_response(number_returned: u64, number_matched: Option<u64>) -> FeaturesResponse {
+        FeaturesResponse {
+            features: vec![],
+            number_returned,
+            number_matched,
+        }
+    }
+
+    #[test]
+    fn test_get_feature_url() {
+        assert_eq!(
+            get_feature_url(&job(3857, None)).unwrap().as_str(),
+            "https://example.com/mapserv?map=city.map&SERVICE=WFS&REQUEST=GetFeature&VERSION=2.0.0&TYPENAMES=parcels&SRSNAME=EPSG%3A3857&BBOX=1%2C2%2C3%2C4%2CEPSG%3A3857"
+        );
+    }
+
+    #[test]
+    fn test_paging() {
+        let second_page = next_job_after(job(3857, Some(2)), 2, None).unwrap();
+        assert!(get_feature_url(&second_page)
+            .unwrap()
+            .as_str()
+            .ends_with("&COUNT=2&STARTINDEX=2"));
+        assert_eq!(
+            bevy_jobs::Job::name(&second_page),
+            "Fetching 'Parcels', page 2"
+        );
+
+        let third_page = next_job_after(second_page, 2, Some(5)).unwrap();
+        assert_eq!(third_page.start_index, 4);
+        // The last page, as reported by `numberMatched` or by fewer features than requested
+        assert!(next_job_after(third_page, 1, Some(5)).is_none());
+        assert!(next_job_after(job(3857, Some(2)), 1, None).is_none());
+        // Services without paging return every feature at once
+        assert!(next_job_after(job(3857, None), 2, None).is_none());
+    }
+
+    fn next_job_after(
+        job: WfsGetFeatureJob,
+        number_returned: u64,
+        number_matched: Option<u64>,
+    ) -> Option<WfsGetFeatureJob> {
+        next_job(job, &features_response(number_returned, number_matched))
+    }
+
+    #[test]
+    fn test_lat_lon_axis_order() {
+        let job = job(4326, None);
+        assert!(get_feature_url(&job).unwrap().as_str().ends_with(
+            "&SRSNAME=urn%3Aogc%3Adef%3Acrs%3AEPSG%3A%3A4326&BBOX=2%2C1%2C4%2C3%2Curn%3Aogc%3Adef%3Acrs%3AEPSG%3A%3A4326"
+        ));
+
+        let response = parse_features(
+            &job,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<wfs:FeatureCollection numberMatched="unknown" numberReturned="1"
+    xmlns:wfs="http://www.opengis.net/wfs/2.0" xmlns:gml="http://www.opengis.net/gml/3.2"
+    xmlns:city="https://example.com/city">
+  <wfs:member>
+    <city:hydrants gml:id="hydrants.1">
+      <city:geom><gml:Point><gml:pos>40.7 -74.0</gml:pos></gml:Point></city:geom>
+    </city:hydrants>
+  </wfs:member>
+</wfs:FeatureCollection>"#
+                .into(),
+        )
+        .unwrap();
+        assert_eq!(response.number_returned, 1);
+        assert_eq!(response.number_matched, None);
+        let [hydrant] = response.features.as_slice() else {
+            panic!("expected 1 feature");
+        };
+        assert_eq!(hydrant.geometry, geo::point!(x: -74.0, y: 40.7).into());
+    }
+
+    #[test]
+    fn test_get_feature_exception() {
+        let job = WfsGetFeatureJob {
+            output_format: Some("application/json".into()),
+            ..job(3857, None)
+        };
+        let result = parse_features(
+            &job,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<ows:ExceptionReport version="2.0.0" xmlns:ows="http://www.opengis.net/ows/1.1">
+  <ows:Exception exceptionCode="InvalidParameterValue" locator="typeName">
+    <ows:ExceptionText>Feature type parcels unknown</ows:ExceptionText>
+  </ows:Exception>
+</ows:ExceptionReport>"#
+                .into(),
+        );
+        assert!(matches!(
+            result,
+            Err(crate::Error::ServiceException(message)) if message == "Feature type parcels unknown"
+        ));
+    }
+}
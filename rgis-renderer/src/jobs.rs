@@ -2,16 +2,24 @@ pub struct MeshBuildingJob {
     pub layer_id: rgis_layer_id::LayerId,
     pub geometry: geo::Geometry<geo_projected::ProjectedScalar>,
     pub is_selected: bool,
+    /// Set when the geometry is of a single feature with meshes of its own
+    pub feature_id: Option<geo_features::FeatureId>,
 }
 
 pub struct MeshBuildingJobOutcome {
     pub geometry_mesh: geo_bevy::GeometryMesh,
     pub layer_id: rgis_layer_id::LayerId,
     pub is_selected: bool,
+    pub feature_id: Option<geo_features::FeatureId>,
+}
+
+pub struct MeshBuildingJobError {
+    pub layer_id: rgis_layer_id::LayerId,
+    pub error: geo_bevy::Error,
 }
 
 impl bevy_jobs::Job for MeshBuildingJob {
-    type Outcome = Result<MeshBuildingJobOutcome, geo_bevy::Error>;
+    type Outcome = Result<MeshBuildingJobOutcome, MeshBuildingJobError>;
 
     fn name(&self) -> String {
         "Building Bevy meshes".to_string()
@@ -19,11 +27,17 @@ impl bevy_jobs::Job for MeshBuildingJob {
 
     fn perform(self, _: bevy_jobs::Context) -> bevy_jobs::AsyncReturn<Self::Outcome> {
         Box::pin(async move {
-            let geometry_mesh = geo_bevy::geometry_to_mesh(&self.geometry)?;
+            let geometry_mesh = geo_bevy::geometry_to_mesh(&self.geometry).map_err(|error| {
+                MeshBuildingJobError {
+                    layer_id: self.layer_id,
+                    error,
+                }
+            })?;
             Ok(MeshBuildingJobOutcome {
                 geometry_mesh,
                 layer_id: self.layer_id,
                 is_selected: self.is_selected,
+                feature_id: self.feature_id,
             })
         })
     }
@@ -7,6 +7,7 @@
 )]
 
 use bevy::prelude::*;
+use std::collections;
 
 mod jobs;
 mod systems;
@@ -25,11 +26,35 @@ pub enum RenderEntityType {
     SelectedPoint,
 }
 
+impl RenderEntityType {
+    fn is_selected(self) -> bool {
+        matches!(
+            self,
+            RenderEntityType::SelectedPolygon
+                | RenderEntityType::SelectedLineString
+                | RenderEntityType::SelectedPoint
+        )
+    }
+}
+
+/// Marks the meshes of a single feature, which are spawned apart from the meshes of the rest of
+/// its layer.
+#[derive(Clone, Copy, Component, PartialEq, Eq)]
+struct FeatureMesh(geo_features::FeatureId);
+
+/// Features with meshes of their own, by layer. They're left out of their layer's meshes, so that
+/// after one of them is edited, only its own meshes are rebuilt.
+#[derive(Default, Resource)]
+struct SeparateFeatures(
+    collections::HashMap<rgis_layer_id::LayerId, collections::BTreeSet<geo_features::FeatureId>>,
+);
+
 pub struct Plugin;
 
 impl bevy::app::Plugin for Plugin {
     fn build(&self, app: &mut App) {
         systems::configure(app);
+        app.init_resource::<SeparateFeatures>();
     }
 }
 
@@ -44,6 +69,7 @@ fn spawn_geometry_meshes(
     layer_index: rgis_layers::LayerIndex,
     asset_server: &AssetServer,
     is_selected: bool,
+    feature_id: Option<geo_features::FeatureId>,
 ) {
     match geometry_mesh {
         geo_bevy::GeometryMesh::Point(points) => {
@@ -64,6 +90,9 @@ fn spawn_geometry_meshes(
                     spawn_sprite_bundle(asset_server, transform, commands, layer.color.stroke);
                 entity_commands.insert(layer.id);
                 entity_commands.insert(stroke_entity_type);
+                if let Some(feature_id) = feature_id {
+                    entity_commands.insert(FeatureMesh(feature_id));
+                }
 
                 // Fill
                 let z_index = ZIndex::calculate(layer_index, fill_entity_type);
@@ -81,6 +110,9 @@ fn spawn_geometry_meshes(
                 );
                 entity_commands.insert(layer.id);
                 entity_commands.insert(fill_entity_type);
+                if let Some(feature_id) = feature_id {
+                    entity_commands.insert(FeatureMesh(feature_id));
+                }
             }
         }
         geo_bevy::GeometryMesh::Polygon(polygon_mesh) => {
@@ -108,6 +140,7 @@ fn spawn_geometry_meshes(
                 assets_meshes,
                 layer,
                 polygon_entity_type,
+                feature_id,
             );
             // Exterior border
             spawn_helper(
@@ -119,6 +152,7 @@ fn spawn_geometry_meshes(
                 assets_meshes,
                 layer,
                 line_string_entity_type,
+                feature_id,
             );
             // Interior borders
             for mesh in polygon_mesh.interior_meshes {
@@ -131,6 +165,7 @@ fn spawn_geometry_meshes(
                     assets_meshes,
                     layer,
                     line_string_entity_type,
+                    feature_id,
                 );
             }
         }
@@ -153,6 +188,7 @@ fn spawn_geometry_meshes(
                 assets_meshes,
                 layer,
                 entity_type,
+                feature_id,
             );
         }
     }
@@ -167,6 +203,7 @@ fn spawn_helper<'a>(
     assets_meshes: &'a mut Assets<Mesh>,
     layer: &rgis_layers::Layer,
     entity_type: RenderEntityType,
+    feature_id: Option<geo_features::FeatureId>,
 ) -> bevy::ecs::system::EntityCommands<'a> {
     let material = materials.add(color);
     let z_index = ZIndex::calculate(layer_index, entity_type);
@@ -180,6 +217,9 @@ fn spawn_helper<'a>(
     );
     entity_commands.insert(layer.id);
     entity_commands.insert(entity_type);
+    if let Some(feature_id) = feature_id {
+        entity_commands.insert(FeatureMesh(feature_id));
+    }
     entity_commands
 }
 
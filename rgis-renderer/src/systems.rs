@@ -5,21 +5,76 @@ use crate::{jobs::MeshBuildingJob, RenderEntityType};
 fn layer_loaded(
     layers: Res<rgis_layers::Layers>,
     mut event_reader: EventReader<rgis_events::LayerReprojectedEvent>,
+    mut commands: Commands,
+    query: Query<(&rgis_layer_id::LayerId, Entity, &RenderEntityType)>,
+    mut separate_features: ResMut<crate::SeparateFeatures>,
     mut job_spawner: bevy_jobs::JobSpawner,
 ) {
     for layer in event_reader.read().flat_map(|event| layers.get(event.0)) {
-        let Some(feature_collection) = layer.projected_feature_collection.as_ref() else {
-            continue;
-        };
+        // Left from before more of the layer's pages were loaded. Selected features are
+        // highlighted again by `handle_selection_updated_event`.
+        for (_, entity, _) in query
+            .iter()
+            .filter(|(i, _, entity_type)| **i == layer.id && !entity_type.is_selected())
+        {
+            commands.entity(entity).despawn();
+        }
+        separate_features.0.remove(&layer.id);
+        spawn_layer_mesh_building_job(layer, &separate_features, &mut job_spawner);
+    }
+}
 
-        job_spawner.spawn(MeshBuildingJob {
-            layer_id: layer.id,
-            geometry: geo::Geometry::GeometryCollection(
-                feature_collection.to_geometry_collection(),
-            ),
-            is_selected: false,
-        });
+/// Builds the meshes of the layer's projected features that match its filter, except for the
+/// ones with meshes of their own.
+fn spawn_layer_mesh_building_job(
+    layer: &rgis_layers::Layer,
+    separate_features: &crate::SeparateFeatures,
+    job_spawner: &mut bevy_jobs::JobSpawner,
+) {
+    if layer.projected_feature_collection.is_none() {
+        return;
     }
+    let separate_feature_ids = separate_features.0.get(&layer.id);
+
+    job_spawner.spawn(MeshBuildingJob {
+        layer_id: layer.id,
+        geometry: geo::Geometry::GeometryCollection(
+            layer
+                .filtered_projected_features()
+                .filter(|feature| {
+                    separate_feature_ids
+                        .is_none_or(|feature_ids| !feature_ids.contains(&feature.id))
+                })
+                .filter_map(|feature| feature.geometry.clone())
+                .collect(),
+        ),
+        is_selected: false,
+        feature_id: None,
+    });
+}
+
+/// Builds the meshes of a single feature, if it matches its layer's filter.
+fn spawn_feature_mesh_building_job(
+    layer: &rgis_layers::Layer,
+    feature_id: geo_features::FeatureId,
+    job_spawner: &mut bevy_jobs::JobSpawner,
+) {
+    let Some(geometry) = layer
+        .projected_feature_collection
+        .as_ref()
+        .and_then(|projected| projected.get(feature_id))
+        .filter(|feature| layer.matches_filter(feature))
+        .and_then(|feature| feature.geometry.clone())
+    else {
+        return;
+    };
+
+    job_spawner.spawn(MeshBuildingJob {
+        layer_id: layer.id,
+        geometry,
+        is_selected: false,
+        feature_id: Some(feature_id),
+    });
 }
 
 fn handle_mesh_building_job_outcome(
@@ -30,16 +85,23 @@ fn handle_mesh_building_job_outcome(
     mut meshes_spawned_event_writer: EventWriter<rgis_events::MeshesSpawnedEvent>,
     mut finished_jobs: bevy_jobs::FinishedJobs,
     asset_server: Res<AssetServer>,
+    mut error_notification_event_writer: EventWriter<rgis_events::ErrorNotificationEvent>,
 ) {
     while let Some(outcome) = finished_jobs.take_next::<MeshBuildingJob>() {
         let crate::jobs::MeshBuildingJobOutcome {
             geometry_mesh,
             layer_id,
             is_selected,
+            feature_id,
         } = match outcome {
             Ok(outcome) => outcome,
             Err(e) => {
-                bevy::log::error!("Error processing MeshBuildingJobOutcome: {:?}", e);
+                bevy::log::error!("Error processing MeshBuildingJobOutcome: {:?}", e.error);
+                error_notification_event_writer.write(rgis_events::ErrorNotificationEvent {
+                    summary: "Could not build the layer's meshes".into(),
+                    layer_id: Some(e.layer_id),
+                    details: vec![format!("{:?}", e.error)],
+                });
                 continue;
             }
         };
@@ -56,6 +118,7 @@ fn handle_mesh_building_job_outcome(
             layer_index,
             &asset_server,
             is_selected,
+            feature_id,
         );
 
         meshes_spawned_event_writer.write(layer_id.into());
@@ -93,8 +156,10 @@ fn handle_despawn_meshes_event(
     mut layer_deleted_event_reader: bevy::ecs::event::EventReader<rgis_events::DespawnMeshesEvent>,
     mut commands: Commands,
     query: LayerEntitiesWithColorMaterialsOrImagesQuery,
+    mut separate_features: ResMut<crate::SeparateFeatures>,
 ) {
     for event in layer_deleted_event_reader.read() {
+        separate_features.0.remove(&event.0);
         for (_, entity) in query.iter().filter(|(i, _)| **i == event.0) {
             commands.entity(entity).despawn();
         }
@@ -142,6 +207,15 @@ fn handle_layer_color_updated_event(
         let Some(layer) = layers.get(*layer_id) else {
             continue;
         };
+        // Undoing can take the fill away from a layer that had none
+        let color = if is_fill {
+            let Some(fill) = layer.color.fill else {
+                continue;
+            };
+            fill
+        } else {
+            layer.color.stroke
+        };
 
         if layer.geom_type == geo_geom_type::GeomType::POINT {
             let render_entity_type = if is_fill {
@@ -152,18 +226,14 @@ fn handle_layer_color_updated_event(
             for (_, mut sprite, _) in sprite_query.iter_mut().filter(|(i, _, entity_type)| {
                 **i == layer.id && **entity_type == render_entity_type
             }) {
-                sprite.color = if is_fill {
-                    layer.color.fill.unwrap()
-                } else {
-                    layer.color.stroke
-                };
+                sprite.color = color;
             }
         } else if is_fill {
             for (_, handle, _) in color_material_query.iter().filter(|(i, _, entity_type)| {
                 **i == layer.id && **entity_type == RenderEntityType::Polygon
             }) {
                 if let Some(color_material) = materials.get_mut(handle) {
-                    color_material.color = layer.color.fill.unwrap();
+                    color_material.color = color;
                 }
             }
         } else {
@@ -171,13 +241,89 @@ fn handle_layer_color_updated_event(
                 **i == layer.id && **entity_type == RenderEntityType::LineString
             }) {
                 if let Some(color_material) = materials.get_mut(handle) {
-                    color_material.color = layer.color.stroke;
+                    color_material.color = color;
                 }
             }
         }
     }
 }
 
+/// Rebuilds the meshes of a layer after its filter or its features changed.
+fn handle_layer_filter_or_features_updated_event(
+    mut layer_filter_updated_event_reader: EventReader<rgis_events::LayerFilterUpdatedEvent>,
+    mut layer_features_updated_event_reader: EventReader<rgis_events::LayerFeaturesUpdatedEvent>,
+    layers: Res<rgis_layers::Layers>,
+    mut commands: Commands,
+    query: Query<(&rgis_layer_id::LayerId, Entity, &RenderEntityType)>,
+    mut separate_features: ResMut<crate::SeparateFeatures>,
+    mut job_spawner: bevy_jobs::JobSpawner,
+) {
+    let layer_ids = layer_filter_updated_event_reader
+        .read()
+        .map(|event| event.0)
+        .chain(
+            layer_features_updated_event_reader
+                .read()
+                .map(|event| event.0),
+        );
+    for layer in layer_ids.filter_map(|layer_id| layers.get(layer_id)) {
+        // Selected features are left highlighted
+        for (_, entity, _) in query
+            .iter()
+            .filter(|(i, _, entity_type)| **i == layer.id && !entity_type.is_selected())
+        {
+            commands.entity(entity).despawn();
+        }
+        // The features with meshes of their own were despawned too
+        separate_features.0.remove(&layer.id);
+        spawn_layer_mesh_building_job(layer, &separate_features, &mut job_spawner);
+    }
+}
+
+/// Rebuilds the meshes of a feature after its geometry was edited. The first time one of a
+/// layer's features is edited, the layer's meshes are rebuilt once without it, after which the
+/// feature has meshes of its own.
+fn handle_feature_geometry_updated_event(
+    mut feature_geometry_updated_event_reader: EventReader<
+        rgis_events::FeatureGeometryUpdatedEvent,
+    >,
+    layers: Res<rgis_layers::Layers>,
+    mut commands: Commands,
+    query: Query<(
+        &rgis_layer_id::LayerId,
+        Entity,
+        &RenderEntityType,
+        Option<&crate::FeatureMesh>,
+    )>,
+    mut separate_features: ResMut<crate::SeparateFeatures>,
+    mut job_spawner: bevy_jobs::JobSpawner,
+) {
+    for event in feature_geometry_updated_event_reader.read() {
+        let rgis_events::FeatureGeometryUpdatedEvent(layer_id, feature_id) = *event;
+        let Some(layer) = layers.get(layer_id) else {
+            continue;
+        };
+        let was_separate = !separate_features
+            .0
+            .entry(layer_id)
+            .or_default()
+            .insert(feature_id);
+        // The feature's own meshes, or else the layer's meshes it was part of
+        let despawned_feature_id = was_separate.then_some(feature_id);
+        for (_, entity, _, _) in query.iter().filter(|(i, _, entity_type, feature_mesh)| {
+            **i == layer_id
+                && !entity_type.is_selected()
+                && feature_mesh.map(|feature_mesh| feature_mesh.0) == despawned_feature_id
+        }) {
+            commands.entity(entity).despawn();
+        }
+        if !was_separate {
+            spawn_layer_mesh_building_job(layer, &separate_features, &mut job_spawner);
+        }
+        spawn_feature_mesh_building_job(layer, feature_id, &mut job_spawner);
+    }
+}
+
 fn handle_crs_changed_events(
     mut crs_changed_event_reader: bevy::ecs::event::EventReader<rgis_events::CrsChangedEvent>,
     query: Query<(&rgis_layer_id::LayerId, Entity), With<MeshMaterial2d<ColorMaterial>>>,
@@ -216,49 +362,59 @@ fn handle_camera_scale_changed_event(
     }
 }
 
-type SelectedFeatureQuery<'world, 'state, 'a> = Query<
-    'world,
-    'state,
-    (Entity, &'a RenderEntityType),
-    Or<(With<MeshMaterial2d<ColorMaterial>>, With<Sprite>)>,
->;
-
-fn handle_feature_selected_event_despawn(
-    event_reader: EventReader<rgis_events::FeatureSelectedEvent>,
+/// Rebuilds the highlight of a layer's selected features, also after the layer is reprojected, its
+/// features changed or one of the selected features was edited.
+fn handle_selection_updated_event(
+    mut selection_updated_event_reader: EventReader<rgis_events::SelectionUpdatedEvent>,
+    mut layer_reprojected_event_reader: EventReader<rgis_events::LayerReprojectedEvent>,
+    mut layer_features_updated_event_reader: EventReader<rgis_events::LayerFeaturesUpdatedEvent>,
+    mut feature_geometry_updated_event_reader: EventReader<
+        rgis_events::FeatureGeometryUpdatedEvent,
+    >,
+    layers: Res<rgis_layers::Layers>,
     mut commands: Commands,
-    query: SelectedFeatureQuery,
+    query: Query<(&rgis_layer_id::LayerId, Entity, &RenderEntityType)>,
+    mut job_spawner: bevy_jobs::JobSpawner,
 ) {
-    if !event_reader.is_empty() {
-        for (entity, entity_type) in query.iter() {
-            match entity_type {
-                RenderEntityType::SelectedPolygon
-                | RenderEntityType::SelectedLineString
-                | RenderEntityType::SelectedPoint => commands.entity(entity).despawn(),
-                _ => (),
-            }
+    let layer_ids = selection_updated_event_reader
+        .read()
+        .map(|event| event.0)
+        .chain(layer_reprojected_event_reader.read().map(|event| event.0))
+        .chain(
+            layer_features_updated_event_reader
+                .read()
+                .map(|event| event.0),
+        )
+        .chain(
+            feature_geometry_updated_event_reader
+                .read()
+                .filter(|event| {
+                    layers
+                        .get(event.0)
+                        .is_some_and(|layer| layer.selected_feature_ids.contains(&event.1))
+                })
+                .map(|event| event.0),
+        );
+    for layer in layer_ids.filter_map(|layer_id| layers.get(layer_id)) {
+        for (_, entity, _) in query
+            .iter()
+            .filter(|(i, _, entity_type)| **i == layer.id && entity_type.is_selected())
+        {
+            commands.entity(entity).despawn();
         }
-    }
-}
 
-fn handle_feature_selected_event_spawn(
-    mut event_reader: EventReader<rgis_events::FeatureSelectedEvent>,
-    layers: Res<rgis_layers::Layers>,
-    mut job_spawner: bevy_jobs::JobSpawner,
-) {
-    for event in event_reader.read() {
-        let Some(layer) = layers.get(event.0) else {
-            return;
-        };
-        let Some(feature) = layer.get_projected_feature(event.1) else {
-            return;
-        };
-        let Some(geometry) = feature.geometry.as_ref() else {
-            return;
-        };
+        let geometries = layer
+            .selected_projected_features()
+            .filter_map(|feature| feature.geometry.clone())
+            .collect::<geo::GeometryCollection<_>>();
+        if geometries.is_empty() {
+            continue;
+        }
         job_spawner.spawn(MeshBuildingJob {
-            layer_id: event.0,
-            geometry: geometry.clone(),
+            layer_id: layer.id,
+            geometry: geo::Geometry::GeometryCollection(geometries),
             is_selected: true,
+            feature_id: None,
         });
     }
 }
@@ -271,13 +427,14 @@ pub fn configure(app: &mut App) {
             handle_layer_became_hidden_event,
             handle_layer_became_visible_event,
             handle_layer_color_updated_event,
+            handle_layer_filter_or_features_updated_event,
+            handle_feature_geometry_updated_event,
             handle_layer_z_index_updated_event,
             handle_despawn_meshes_event,
             handle_mesh_building_job_outcome,
             handle_crs_changed_events,
             handle_camera_scale_changed_event,
-            handle_feature_selected_event_despawn,
-            handle_feature_selected_event_spawn,
+            handle_selection_updated_event,
         ),
     );
 }
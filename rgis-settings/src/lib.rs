@@ -8,30 +8,83 @@
 
 use bevy::prelude::*;
 
+mod recent_sources;
 mod systems;
 
+pub use recent_sources::RecentSources;
+
 static DEFAULT_TARGET_CRS: u16 = 3857;
 
+const DEFAULT_SNAPPING_TOLERANCE: f32 = 10.;
+
 #[derive(PartialEq, Eq, Copy, Clone)]
 pub enum Tool {
     Pan,
     Query,
+    /// Select the features in a rectangle dragged on the map
+    SelectRect,
+    /// Select the features in a freehand shape drawn on the map
+    SelectLasso,
+    /// Measure geodesic lengths and areas of a shape clicked on the map
+    Measure,
+    /// Add points to the scratch layer
+    DrawPoint,
+    /// Add lines to the scratch layer by clicking their vertices
+    DrawLine,
+    /// Add polygons to the scratch layer by clicking their vertices
+    DrawPolygon,
+    /// Move, insert and delete the vertices of a layer's selected feature
+    EditVertices,
+}
+
+impl Tool {
+    pub fn is_drawing(self) -> bool {
+        matches!(self, Tool::DrawPoint | Tool::DrawLine | Tool::DrawPolygon)
+    }
+
+    /// Whether the cursor snaps to nearby vertices and segments while using the tool.
+    pub fn snaps(self) -> bool {
+        self.is_drawing() || self == Tool::EditVertices
+    }
 }
 
 #[derive(Resource)]
 pub struct RgisSettings {
     pub target_crs_epsg_code: u16,
     pub current_tool: Tool,
+    pub length_unit: rgis_units::LengthUnit,
+    pub area_unit: rgis_units::AreaUnit,
+    /// Whether drawing and vertex editing snap to the vertices and segments of visible layers
+    pub is_snapping_enabled: bool,
+    /// Pixels from the cursor within which it snaps
+    pub snapping_tolerance: f32,
+}
+
+impl Default for RgisSettings {
+    fn default() -> Self {
+        RgisSettings {
+            target_crs_epsg_code: DEFAULT_TARGET_CRS,
+            current_tool: Tool::Pan,
+            length_unit: rgis_units::LengthUnit::default(),
+            area_unit: rgis_units::AreaUnit::default(),
+            is_snapping_enabled: true,
+            snapping_tolerance: DEFAULT_SNAPPING_TOLERANCE,
+        }
+    }
 }
 
 pub struct Plugin;
 
 impl bevy::prelude::Plugin for Plugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.insert_resource(RgisSettings {
-            target_crs_epsg_code: DEFAULT_TARGET_CRS,
-            current_tool: Tool::Pan,
-        })
-        .add_systems(Update, systems::handle_crs_changed_events);
+        app.init_resource::<RgisSettings>()
+            .insert_resource(RecentSources::load())
+            .add_systems(
+                Update,
+                (
+                    systems::handle_crs_changed_events,
+                    systems::save_recent_sources,
+                ),
+            );
     }
 }
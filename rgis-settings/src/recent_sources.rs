@@ -0,0 +1,347 @@
+use bevy::prelude::*;
+use geo_file_loader::FileFormat;
+use std::{collections, path};
+
+const MAX_RECENT_SOURCES: usize = 10;
+
+/// Files and URLs that were recently loaded, most recent first. The list is kept across
+/// sessions in the config directory (native) or `localStorage` (wasm).
+#[derive(Default, Resource)]
+pub struct RecentSources(collections::VecDeque<rgis_events::LoadFileEvent>);
+
+impl RecentSources {
+    /// Sources that only exist in memory, like pasted text, can't be re-opened and aren't
+    /// recorded.
+    pub fn push(&mut self, event: rgis_events::LoadFileEvent) {
+        if matches!(event, rgis_events::LoadFileEvent::FromBytes { .. }) {
+            return;
+        }
+        self.0.retain(|recent| !recent.is_same_source(&event));
+        self.0.push_front(event);
+        self.0.truncate(MAX_RECENT_SOURCES);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &rgis_events::LoadFileEvent> {
+        self.0.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Failing to read the list is logged and treated as an empty list.
+    pub(crate) fn load() -> Self {
+        let contents = match storage::read() {
+            Ok(Some(contents)) => contents,
+            Ok(None) => return RecentSources::default(),
+            Err(e) => {
+                bevy::log::warn!("Could not read the recent sources: {}", e);
+                return RecentSources::default();
+            }
+        };
+        RecentSources::from_json(&contents).unwrap_or_else(|e| {
+            bevy::log::warn!("Could not parse the recent sources: {}", e);
+            RecentSources::default()
+        })
+    }
+
+    /// Failing to write the list is logged and otherwise ignored.
+    pub(crate) fn save(&self) {
+        let result = self
+            .to_json()
+            .map_err(|e| e.to_string())
+            .and_then(|contents| storage::write(&contents));
+        if let Err(e) = result {
+            bevy::log::warn!("Could not write the recent sources: {}", e);
+        }
+    }
+
+    fn from_json(contents: &str) -> Result<Self, serde_json::Error> {
+        let stored: Vec<StoredSource> = serde_json::from_str(contents)?;
+        Ok(RecentSources(
+            stored
+                .into_iter()
+                .map(StoredSource::into_event)
+                .take(MAX_RECENT_SOURCES)
+                .collect(),
+        ))
+    }
+
+    /// Sources sent with extra HTTP headers are left out, since the headers may hold
+    /// credentials.
+    fn to_json(&self) -> Result<String, serde_json::Error> {
+        let stored: Vec<StoredSource> = self.0.iter().filter_map(StoredSource::new).collect();
+        serde_json::to_string(&stored)
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+enum StoredSource {
+    Path {
+        file_name: String,
+        path: path::PathBuf,
+        file_format: FileFormat,
+        crs_epsg_code: u16,
+    },
+    Network {
+        name: String,
+        url: String,
+        crs_epsg_code: u16,
+        file_format: Option<FileFormat>,
+    },
+    OgcApiFeatures {
+        name: String,
+        items_url: String,
+        bbox: Option<[f64; 4]>,
+    },
+    ArcGisFeatureLayer {
+        name: String,
+        layer_url: String,
+    },
+    Wfs {
+        name: String,
+        service_url: String,
+        type_name: String,
+        output_format: Option<String>,
+        crs_epsg_code: u16,
+        bbox: Option<[f64; 4]>,
+        page_size: Option<u32>,
+    },
+}
+
+impl StoredSource {
+    fn new(event: &rgis_events::LoadFileEvent) -> Option<Self> {
+        Some(match event {
+            rgis_events::LoadFileEvent::FromPath {
+                file_name,
+                path,
+                file_format,
+                crs_epsg_code,
+            } => StoredSource::Path {
+                file_name: file_name.clone(),
+                path: path.clone(),
+                file_format: *file_format,
+                crs_epsg_code: *crs_epsg_code,
+            },
+            rgis_events::LoadFileEvent::FromNetwork {
+                name,
+                url,
+                crs_epsg_code,
+                file_format,
+                headers,
+            } if headers.is_empty() => StoredSource::Network {
+                name: name.clone(),
+                url: url.clone(),
+                crs_epsg_code: *crs_epsg_code,
+                file_format: *file_format,
+            },
+            rgis_events::LoadFileEvent::FromOgcApiFeatures {
+                name,
+                items_url,
+                bbox,
+            } => StoredSource::OgcApiFeatures {
+                name: name.clone(),
+                items_url: items_url.clone(),
+                bbox: *bbox,
+            },
+            rgis_events::LoadFileEvent::FromArcGisFeatureLayer { name, layer_url } => {
+                StoredSource::ArcGisFeatureLayer {
+                    name: name.clone(),
+                    layer_url: layer_url.clone(),
+                }
+            }
+            rgis_events::LoadFileEvent::FromWfs {
+                name,
+                service_url,
+                type_name,
+                output_format,
+                crs_epsg_code,
+                bbox,
+                page_size,
+            } => StoredSource::Wfs {
+                name: name.clone(),
+                service_url: service_url.clone(),
+                type_name: type_name.clone(),
+                output_format: output_format.clone(),
+                crs_epsg_code: *crs_epsg_code,
+                bbox: *bbox,
+                page_size: *page_size,
+            },
+            rgis_events::LoadFileEvent::FromNetwork { .. }
+            | rgis_events::LoadFileEvent::FromBytes { .. } => return None,
+        })
+    }
+
+    fn into_event(self) -> rgis_events::LoadFileEvent {
+        match self {
+            StoredSource::Path {
+                file_name,
+                path,
+                file_format,
+                crs_epsg_code,
+            } => rgis_events::LoadFileEvent::FromPath {
+                file_name,
+                path,
+                file_format,
+                crs_epsg_code,
+            },
+            StoredSource::Network {
+                name,
+                url,
+                crs_epsg_code,
+                file_format,
+            } => rgis_events::LoadFileEvent::FromNetwork {
+                name,
+                url,
+                crs_epsg_code,
+                file_format,
+                headers: vec![],
+            },
+            StoredSource::OgcApiFeatures {
+                name,
+                items_url,
+                bbox,
+            } => rgis_events::LoadFileEvent::FromOgcApiFeatures {
+                name,
+                items_url,
+                bbox,
+            },
+            StoredSource::ArcGisFeatureLayer { name, layer_url } => {
+                rgis_events::LoadFileEvent::FromArcGisFeatureLayer { name, layer_url }
+            }
+            StoredSource::Wfs {
+                name,
+                service_url,
+                type_name,
+                output_format,
+                crs_epsg_code,
+                bbox,
+                page_size,
+            } => rgis_events::LoadFileEvent::FromWfs {
+                name,
+                service_url,
+                type_name,
+                output_format,
+                crs_epsg_code,
+                bbox,
+                page_size,
+            },
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod storage {
+    use std::{fs, io, path};
+
+    fn path() -> Option<path::PathBuf> {
+        Some(dirs::config_dir()?.join("rgis").join("recent_sources.json"))
+    }
+
+    pub(super) fn read() -> Result<Option<String>, String> {
+        let Some(path) = path() else {
+            return Ok(None);
+        };
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub(super) fn write(contents: &str) -> Result<(), String> {
+        let Some(path) = path() else {
+            return Ok(());
+        };
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(|e| e.to_string())?;
+        }
+        fs::write(path, contents).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod storage {
+    const KEY: &str = "rgis-recent-sources";
+
+    fn local_storage() -> Result<web_sys::Storage, String> {
+        web_sys::window()
+            .ok_or("no window")?
+            .local_storage()
+            .map_err(|e| format!("{e:?}"))?
+            .ok_or_else(|| "localStorage is unavailable".into())
+    }
+
+    pub(super) fn read() -> Result<Option<String>, String> {
+        local_storage()?.get_item(KEY).map_err(|e| format!("{e:?}"))
+    }
+
+    pub(super) fn write(contents: &str) -> Result<(), String> {
+        local_storage()?
+            .set_item(KEY, contents)
+            .map_err(|e| format!("{e:?}"))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn network_source(url: &str, headers: Vec<(String, String)>) -> rgis_events::LoadFileEvent {
+        rgis_events::LoadFileEvent::FromNetwork {
+            name: url.into(),
+            url: url.into(),
+            crs_epsg_code: 4326,
+            file_format: None,
+            headers,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut recent_sources = RecentSources::default();
+        recent_sources.push(rgis_events::LoadFileEvent::FromPath {
+            file_name: "roads.shp".into(),
+            path: "/data/roads.shp".into(),
+            file_format: FileFormat::Shapefile,
+            crs_epsg_code: 3857,
+        });
+        recent_sources.push(network_source("https://example.com/a.geojson", vec![]));
+        recent_sources.push(rgis_events::LoadFileEvent::FromArcGisFeatureLayer {
+            name: "Parcels".into(),
+            layer_url: "https://example.com/arcgis/rest/services/Parcels/FeatureServer/0".into(),
+        });
+
+        let loaded = RecentSources::from_json(&recent_sources.to_json().unwrap()).unwrap();
+
+        assert_eq!(loaded.0.len(), 3);
+        for (a, b) in loaded.iter().zip(recent_sources.iter()) {
+            assert!(a.is_same_source(b));
+            assert_eq!(a.name(), b.name());
+            assert_eq!(a.crs_epsg_code(), b.crs_epsg_code());
+        }
+    }
+
+    #[test]
+    fn test_in_memory_and_authenticated_sources_are_not_stored() {
+        let mut recent_sources = RecentSources::default();
+        recent_sources.push(rgis_events::LoadFileEvent::FromBytes {
+            file_name: "Inputted file".into(),
+            file_format: FileFormat::Wkt,
+            bytes: "POINT (1 2)".into(),
+            crs_epsg_code: 4326,
+        });
+        assert!(recent_sources.is_empty());
+
+        recent_sources.push(network_source(
+            "https://example.com/private.geojson",
+            vec![("Authorization".into(), "Bearer secret".into())],
+        ));
+        assert_eq!(recent_sources.iter().count(), 1);
+
+        let json = recent_sources.to_json().unwrap();
+        assert!(!json.contains("secret"));
+        assert!(RecentSources::from_json(&json).unwrap().is_empty());
+    }
+}
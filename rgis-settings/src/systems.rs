@@ -11,3 +11,9 @@ pub fn handle_crs_changed_events(
         });
     }
 }
+
+pub fn save_recent_sources(recent_sources: bevy::ecs::system::Res<crate::RecentSources>) {
+    if recent_sources.is_changed() && !recent_sources.is_added() {
+        recent_sources.save();
+    }
+}
@@ -3,18 +3,27 @@ use geo_projected::CastTo;
 pub struct ReprojectGeometryJob {
     pub feature_collection: geo_features::FeatureCollection<geo_projected::UnprojectedScalar>,
     pub layer_id: rgis_layer_id::LayerId,
+    /// Revision of the layer's features when the job was spawned
+    pub revision: u64,
     pub source_epsg_code: u16,
     pub target_epsg_code: u16,
 }
 
 pub struct ReprojectGeometryJobOutcome {
     pub feature_collection: geo_features::FeatureCollection<geo_projected::ProjectedScalar>,
+    pub spatial_index: rgis_layers::SpatialIndex,
     pub layer_id: rgis_layer_id::LayerId,
+    pub revision: u64,
     pub target_crs_epsg_code: u16,
 }
 
+pub struct ReprojectGeometryJobError {
+    pub layer_id: rgis_layer_id::LayerId,
+    pub error: geo_geodesy::Error,
+}
+
 impl bevy_jobs::Job for ReprojectGeometryJob {
-    type Outcome = Result<ReprojectGeometryJobOutcome, geo_geodesy::Error>;
+    type Outcome = Result<ReprojectGeometryJobOutcome, ReprojectGeometryJobError>;
 
     fn name(&self) -> String {
         "Projecting layer".to_string()
@@ -22,28 +31,42 @@ impl bevy_jobs::Job for ReprojectGeometryJob {
 
     fn perform(self, progress_sender: bevy_jobs::Context) -> bevy_jobs::AsyncReturn<Self::Outcome> {
         Box::pin(async move {
-            let total = self.feature_collection.features.len();
+            let total = self.feature_collection.features().len();
+
+            let layer_id = self.layer_id;
+            let error = |error| ReprojectGeometryJobError { layer_id, error };
 
             let transformer =
-                geo_geodesy::Transformer::setup(self.source_epsg_code, self.target_epsg_code)?;
+                geo_geodesy::Transformer::setup(self.source_epsg_code, self.target_epsg_code)
+                    .map_err(error)?;
 
             let mut feature_collection = self.feature_collection.cast::<geo_projected::Projected>();
 
-            for (i, feature) in feature_collection.features.iter_mut().enumerate() {
-                let _ = progress_sender.send_progress((100 * i / total) as u8).await;
+            for (i, feature) in feature_collection.iter_mut().enumerate() {
+                if progress_sender
+                    .send_progress((100 * i / total) as u8)
+                    .await
+                    .is_err()
+                {
+                    // Cancelled, nothing will receive the outcome
+                    break;
+                }
 
                 if let Some(ref mut geometry) = &mut feature.geometry {
-                    transformer.transform(geometry)?;
+                    transformer.transform(geometry).map_err(error)?;
                 }
 
                 feature.recalculate_bounding_rect();
             }
 
             feature_collection.recalculate_bounding_rect();
+            let spatial_index = rgis_layers::SpatialIndex::new(&feature_collection);
 
             Ok(ReprojectGeometryJobOutcome {
                 feature_collection,
+                spatial_index,
                 layer_id: self.layer_id,
+                revision: self.revision,
                 target_crs_epsg_code: self.target_epsg_code,
             })
         })
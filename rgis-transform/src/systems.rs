@@ -11,15 +11,41 @@ fn handle_layer_created_events(
             continue;
         };
 
-        job_spawner.spawn(crate::jobs::ReprojectGeometryJob {
-            feature_collection: layer.unprojected_feature_collection.clone(),
-            layer_id: event.0,
-            source_epsg_code: layer.crs_epsg_code,
-            target_epsg_code: rgis_settings.target_crs_epsg_code,
-        });
+        spawn_reproject_geometry_job(layer, &rgis_settings, &mut job_spawner);
     }
 }
 
+fn handle_reproject_layer_events(
+    mut reproject_layer_event_reader: bevy::ecs::event::EventReader<
+        rgis_events::ReprojectLayerEvent,
+    >,
+    layers: bevy::ecs::system::Res<rgis_layers::Layers>,
+    rgis_settings: bevy::ecs::system::Res<rgis_settings::RgisSettings>,
+    mut job_spawner: bevy_jobs::JobSpawner,
+) {
+    for event in reproject_layer_event_reader.read() {
+        let Some(layer) = layers.get(event.0) else {
+            continue;
+        };
+
+        spawn_reproject_geometry_job(layer, &rgis_settings, &mut job_spawner);
+    }
+}
+
+fn spawn_reproject_geometry_job(
+    layer: &rgis_layers::Layer,
+    rgis_settings: &rgis_settings::RgisSettings,
+    job_spawner: &mut bevy_jobs::JobSpawner,
+) {
+    job_spawner.spawn(crate::jobs::ReprojectGeometryJob {
+        feature_collection: layer.unprojected_feature_collection.clone(),
+        layer_id: layer.id,
+        revision: layer.revision(),
+        source_epsg_code: layer.crs_epsg_code,
+        target_epsg_code: rgis_settings.target_crs_epsg_code,
+    });
+}
+
 fn handle_reproject_geometry_job_completion_events(
     mut finished_jobs: bevy_jobs::FinishedJobs,
     mut layers: bevy::ecs::system::ResMut<rgis_layers::Layers>,
@@ -27,12 +53,29 @@ fn handle_reproject_geometry_job_completion_events(
         rgis_events::LayerReprojectedEvent,
     >,
     rgis_settings: bevy::ecs::system::Res<rgis_settings::RgisSettings>,
+    mut job_failed_event_writer: bevy::ecs::event::EventWriter<rgis_events::JobFailedEvent>,
+    mut error_notification_event_writer: bevy::ecs::event::EventWriter<
+        rgis_events::ErrorNotificationEvent,
+    >,
 ) {
     while let Some(outcome) = finished_jobs.take_next::<crate::jobs::ReprojectGeometryJob>() {
         let outcome = match outcome {
             Ok(o) => o,
             Err(e) => {
-                bevy::log::error!("Encountered an error reprojecting geometry: {:?}", e);
+                bevy::log::error!("Encountered an error reprojecting geometry: {:?}", e.error);
+                let name = layers.get(e.layer_id).map_or_else(
+                    || "Projecting layer".into(),
+                    |layer| format!("Projecting '{}'", layer.name),
+                );
+                let notification =
+                    rgis_events::ErrorNotificationEvent::new("Could not project layer", &e.error)
+                        .with_layer_id(e.layer_id);
+                job_failed_event_writer.write(rgis_events::JobFailedEvent {
+                    name,
+                    error: notification.details.join(": "),
+                    retry: rgis_events::RetryJob::ReprojectLayer(e.layer_id),
+                });
+                error_notification_event_writer.write(notification);
                 continue;
             }
         };
@@ -45,8 +88,12 @@ fn handle_reproject_geometry_job_completion_events(
         let Some(layer) = layers.get_mut(outcome.layer_id) else {
             continue;
         };
+        // The features changed since, and projecting them again was requested along with it
+        if outcome.revision != layer.revision() {
+            continue;
+        }
 
-        layer.projected_feature_collection = Some(outcome.feature_collection);
+        layer.set_projected_feature_collection(outcome.feature_collection, outcome.spatial_index);
 
         layer_reprojected_event_writer.write(rgis_events::LayerReprojectedEvent(outcome.layer_id));
     }
@@ -62,12 +109,7 @@ fn handle_crs_changed_events(
         layers.clear_projected();
 
         for layer in layers.iter() {
-            job_spawner.spawn(crate::jobs::ReprojectGeometryJob {
-                feature_collection: layer.unprojected_feature_collection.clone(),
-                layer_id: layer.id,
-                source_epsg_code: layer.crs_epsg_code,
-                target_epsg_code: rgis_settings.target_crs_epsg_code,
-            });
+            spawn_reproject_geometry_job(layer, &rgis_settings, &mut job_spawner);
         }
     }
 }
@@ -77,6 +119,7 @@ pub fn configure(app: &mut App) {
         Update,
         (
             handle_layer_created_events,
+            handle_reproject_layer_events,
             handle_reproject_geometry_job_completion_events,
             handle_crs_changed_events,
         ),
@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 use bevy_egui::egui;
+use geo::BoundingRect;
 use geo_file_loader::FileFormat;
 use std::mem;
 use std::str::FromStr;
@@ -27,9 +28,14 @@ impl bevy_jobs::Job for OpenFileJob {
         Box::pin(async move {
             let task = rfd::AsyncFileDialog::new().pick_file();
             let file_handle = task.await?;
-            let file_name = file_handle.file_name();
-            let bytes = file_handle.read().await;
-            Some(OpenedFile { file_name, bytes })
+            Some(OpenedFile {
+                file_name: file_handle.file_name(),
+                // Native files are read when the layer is loaded, so only their path is kept
+                #[cfg(not(target_arch = "wasm32"))]
+                path: file_handle.path().to_owned(),
+                #[cfg(target_arch = "wasm32")]
+                bytes: file_handle.read().await,
+            })
         })
     }
 }
@@ -38,6 +44,10 @@ pub(crate) struct AddLayerWindow<'a, 'w1, 's1, 'w2, 's2> {
     pub state: &'a mut State,
     pub is_visible: &'a mut bool,
     pub selected_file: &'a mut SelectedFile,
+    pub ogc_api_collections: &'a mut OgcApiCollections,
+    pub wfs_capabilities: &'a mut WfsCapabilities,
+    pub visible_extent: rgis_units::VisibleExtent,
+    pub target_crs_epsg_code: u16,
     pub egui_ctx: &'a mut bevy_egui::egui::Context,
     pub job_spawner: &'a mut bevy_jobs::JobSpawner<'w1, 's1>,
     pub events: &'a mut Events<'w2, 's2>,
@@ -49,6 +59,9 @@ enum Source {
     Library,
     File,
     Text,
+    Url,
+    OgcApiFeatures,
+    Wfs,
 }
 
 pub struct State {
@@ -57,6 +70,20 @@ pub struct State {
     selected_source: Source,
     selected_format: Option<FileFormat>,
     crs_input_outcome: Option<crate::widgets::crs_input::Outcome>,
+    url_input: String,
+    // `None` means the format is detected from the response
+    url_format: Option<FileFormat>,
+    headers: Vec<(String, String)>,
+    ogc_api_landing_page_url: String,
+    wfs_service_url: String,
+    wfs_output_format: WfsOutputFormat,
+    restrict_to_visible_extent: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum WfsOutputFormat {
+    GeoJson,
+    Gml,
 }
 
 const DEFAULT_CRS_INPUT: &str = "4326";
@@ -69,6 +96,13 @@ impl Default for State {
             crs_input_outcome: None,
             selected_format: None,
             selected_source: Source::Unselected,
+            url_input: String::new(),
+            url_format: None,
+            headers: vec![],
+            ogc_api_landing_page_url: String::new(),
+            wfs_service_url: String::new(),
+            wfs_output_format: WfsOutputFormat::GeoJson,
+            restrict_to_visible_extent: true,
         }
     }
 }
@@ -76,18 +110,63 @@ impl Default for State {
 #[derive(Default, Resource)]
 pub struct SelectedFile(pub Option<OpenedFile>);
 
+/// Outcome of the last [`rgis_network::OgcApiCollectionsJob`].
+#[derive(Default, Resource)]
+pub struct OgcApiCollections(pub Option<Result<Vec<rgis_network::OgcApiCollection>, String>>);
+
+/// Outcome of the last [`rgis_network::WfsCapabilitiesJob`].
+#[derive(Default, Resource)]
+pub struct WfsCapabilities(pub Option<Result<rgis_network::WfsCapabilities, String>>);
+
 impl State {
     pub fn reset(&mut self) {
         self.text_edit_contents = String::new();
         self.crs_input = DEFAULT_CRS_INPUT.into();
         self.selected_source = Source::Unselected;
         self.selected_format = None;
+        self.url_input = String::new();
+        self.url_format = None;
+        self.headers.clear();
+        self.ogc_api_landing_page_url = String::new();
+        self.wfs_service_url = String::new();
+        self.wfs_output_format = WfsOutputFormat::GeoJson;
+        self.restrict_to_visible_extent = true;
     }
 }
 
 pub struct OpenedFile {
-    bytes: Vec<u8>,
     file_name: String,
+    #[cfg(not(target_arch = "wasm32"))]
+    path: std::path::PathBuf,
+    #[cfg(target_arch = "wasm32")]
+    bytes: Vec<u8>,
+}
+
+impl OpenedFile {
+    fn into_load_file_event(
+        self,
+        file_format: FileFormat,
+        crs_epsg_code: u16,
+    ) -> rgis_events::LoadFileEvent {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            rgis_events::LoadFileEvent::FromPath {
+                file_name: self.file_name,
+                path: self.path,
+                file_format,
+                crs_epsg_code,
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            rgis_events::LoadFileEvent::FromBytes {
+                file_name: self.file_name,
+                file_format,
+                bytes: self.bytes.into(),
+                crs_epsg_code,
+            }
+        }
+    }
 }
 
 impl AddLayerWindow<'_, '_, '_, '_, '_> {
@@ -105,6 +184,13 @@ impl AddLayerWindow<'_, '_, '_, '_, '_> {
                 ui.radio_value(&mut self.state.selected_source, Source::Library, "Library");
                 ui.radio_value(&mut self.state.selected_source, Source::File, "File");
                 ui.radio_value(&mut self.state.selected_source, Source::Text, "Text");
+                ui.radio_value(&mut self.state.selected_source, Source::Url, "URL");
+                ui.radio_value(
+                    &mut self.state.selected_source,
+                    Source::OgcApiFeatures,
+                    "OGC API – Features",
+                );
+                ui.radio_value(&mut self.state.selected_source, Source::Wfs, "WFS");
 
                 if self.state.selected_source == Source::Unselected {
                     return;
@@ -130,6 +216,18 @@ impl AddLayerWindow<'_, '_, '_, '_, '_> {
                     return;
                 }
 
+                if self.state.selected_source == Source::OgcApiFeatures {
+                    ui.add(OgcApiFeaturesWidget {
+                        state: self.state,
+                        collections: self.ogc_api_collections,
+                        visible_extent: self.visible_extent,
+                        target_crs_epsg_code: self.target_crs_epsg_code,
+                        job_spawner: self.job_spawner,
+                        events: self.events,
+                    });
+                    return;
+                }
+
                 ui.label("Source CRS:");
                 let crs_input_widget = crate::widgets::CrsInput::new(
                     &mut self.state.crs_input,
@@ -139,6 +237,26 @@ impl AddLayerWindow<'_, '_, '_, '_, '_> {
 
                 ui.separator();
 
+                if self.state.selected_source == Source::Url {
+                    ui.add(UrlSourceWidget {
+                        state: self.state,
+                        events: self.events,
+                    });
+                    return;
+                }
+
+                if self.state.selected_source == Source::Wfs {
+                    ui.add(WfsWidget {
+                        state: self.state,
+                        capabilities: self.wfs_capabilities,
+                        visible_extent: self.visible_extent,
+                        target_crs_epsg_code: self.target_crs_epsg_code,
+                        job_spawner: self.job_spawner,
+                        events: self.events,
+                    });
+                    return;
+                }
+
                 if self.state.selected_source == Source::File
                     || self.state.selected_source == Source::Text
                 {
@@ -177,6 +295,12 @@ impl AddLayerWindow<'_, '_, '_, '_, '_> {
                         Some(FileFormat::Wkt),
                         "WKT",
                     );
+
+                    ui.radio_value(
+                        &mut self.state.selected_format,
+                        Some(FileFormat::Gml),
+                        "GML",
+                    );
                 }
 
                 let Some(selected_format) = self.state.selected_format else {
@@ -212,12 +336,8 @@ impl AddLayerWindow<'_, '_, '_, '_, '_> {
                         match self.selected_file.0.take() {
                             Some(loaded_file) => {
                                 self.events.load_file_event_writer.write(
-                                    rgis_events::LoadFileEvent::FromBytes {
-                                        file_name: loaded_file.file_name,
-                                        file_format: selected_format,
-                                        bytes: loaded_file.bytes.into(),
-                                        crs_epsg_code,
-                                    },
+                                    loaded_file
+                                        .into_load_file_event(selected_format, crs_epsg_code),
                                 );
                             }
                             None => {
@@ -256,7 +376,8 @@ impl AddLayerWindow<'_, '_, '_, '_, '_> {
                             }
                             file_format @ (FileFormat::Wkt
                             | FileFormat::GeoJson
-                            | FileFormat::Gpx) => {
+                            | FileFormat::Gpx
+                            | FileFormat::Gml) => {
                                 self.events.load_file_event_writer.write(
                                     rgis_events::LoadFileEvent::FromBytes {
                                         file_name: "Inputted file".into(),
@@ -288,6 +409,7 @@ const fn hint_text(format: FileFormat) -> &'static str {
         FileFormat::Shapefile => panic!("Shapefiles are not textual"),
         FileFormat::Wkt => "LINESTRING (30 10, 10 30, 40 40)",
         FileFormat::Gpx => "", // TODO: add example GPX
+        FileFormat::Gml => "<gml:Point xmlns:gml=\"http://www.opengis.net/gml/3.2\">\n  <gml:pos>30 10</gml:pos>\n</gml:Point>",
     }
 }
 
@@ -331,6 +453,8 @@ impl egui::Widget for LibraryEntryWidget<'_, '_, '_> {
                         name: format!("{}: {}", self.folder.name, self.entry.name),
                         url: self.entry.url.into(),
                         crs_epsg_code: self.entry.crs,
+                        file_format: Some(FileFormat::GeoJson),
+                        headers: vec![],
                     });
                 self.events.hide_add_layer_window_events.send_default();
             }
@@ -339,3 +463,412 @@ impl egui::Widget for LibraryEntryWidget<'_, '_, '_> {
         .response
     }
 }
+
+struct UrlSourceWidget<'a, 'w, 's> {
+    state: &'a mut State,
+    events: &'a mut Events<'w, 's>,
+}
+
+impl egui::Widget for UrlSourceWidget<'_, '_, '_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        ui.vertical(|ui| {
+            ui.label("Format:");
+            ui.radio_value(&mut self.state.url_format, None, "Auto-detect");
+            for file_format in [
+                FileFormat::GeoJson,
+                FileFormat::Gpx,
+                FileFormat::Shapefile,
+                FileFormat::Wkt,
+                FileFormat::Gml,
+            ] {
+                ui.radio_value(
+                    &mut self.state.url_format,
+                    Some(file_format),
+                    file_format.display_name(),
+                );
+            }
+
+            ui.separator();
+
+            ui.label("URL:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.state.url_input)
+                    .hint_text("https://example.com/data.geojson"),
+            );
+            let is_arcgis_layer = rgis_network::is_arcgis_layer_url(&self.state.url_input);
+            if is_arcgis_layer {
+                ui.label("ArcGIS REST layer: every feature is fetched, page by page, in EPSG:4326");
+            }
+
+            ui.separator();
+
+            ui.label("HTTP headers:");
+            ui.add(HeadersWidget {
+                headers: &mut self.state.headers,
+            });
+
+            ui.separator();
+
+            let crs_epsg_code = match self.state.crs_input_outcome {
+                Some(Ok(_)) => u16::from_str(&self.state.crs_input).ok(),
+                _ => None,
+            };
+            let url = self.state.url_input.trim();
+            let submittable = !url.is_empty() && (is_arcgis_layer || crs_epsg_code.is_some());
+
+            if ui
+                .add_enabled(submittable, egui::Button::new("Add layer"))
+                .clicked()
+            {
+                if is_arcgis_layer {
+                    self.events.load_file_event_writer.write(
+                        rgis_events::LoadFileEvent::FromArcGisFeatureLayer {
+                            name: arcgis_service_name(url).unwrap_or_else(|| name_from_url(url)),
+                            layer_url: url.into(),
+                        },
+                    );
+                    self.events.hide_add_layer_window_events.send_default();
+                    self.state.reset();
+                    return;
+                }
+                let Some(crs_epsg_code) = crs_epsg_code else {
+                    return;
+                };
+                let headers = mem::take(&mut self.state.headers)
+                    .into_iter()
+                    .filter(|(name, _)| !name.trim().is_empty())
+                    .collect();
+                self.events
+                    .load_file_event_writer
+                    .write(rgis_events::LoadFileEvent::FromNetwork {
+                        name: name_from_url(url),
+                        url: url.into(),
+                        crs_epsg_code,
+                        file_format: self.state.url_format,
+                        headers,
+                    });
+                self.events.hide_add_layer_window_events.send_default();
+                self.state.reset();
+            }
+        })
+        .response
+    }
+}
+
+struct HeadersWidget<'a> {
+    headers: &'a mut Vec<(String, String)>,
+}
+
+impl egui::Widget for HeadersWidget<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        ui.vertical(|ui| {
+            let mut removed = None;
+            for (i, (name, value)) in self.headers.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(name)
+                            .hint_text("Name")
+                            .desired_width(100.),
+                    );
+                    ui.add(
+                        egui::TextEdit::singleline(value)
+                            .hint_text("Value")
+                            .desired_width(150.),
+                    );
+                    if ui.button("❌").clicked() {
+                        removed = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = removed {
+                self.headers.remove(i);
+            }
+            if ui.button("➕ Add header").clicked() {
+                self.headers.push(Default::default());
+            }
+        })
+        .response
+    }
+}
+
+struct OgcApiFeaturesWidget<'a, 'w1, 's1, 'w2, 's2> {
+    state: &'a mut State,
+    collections: &'a mut OgcApiCollections,
+    visible_extent: rgis_units::VisibleExtent,
+    target_crs_epsg_code: u16,
+    job_spawner: &'a mut bevy_jobs::JobSpawner<'w1, 's1>,
+    events: &'a mut Events<'w2, 's2>,
+}
+
+impl egui::Widget for OgcApiFeaturesWidget<'_, '_, '_, '_, '_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        ui.vertical(|ui| {
+            ui.label("Landing page URL:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.state.ogc_api_landing_page_url)
+                    .hint_text("https://demo.ldproxy.net/daraa"),
+            );
+            let landing_page_url = self.state.ogc_api_landing_page_url.trim();
+            if ui
+                .add_enabled(
+                    !landing_page_url.is_empty(),
+                    egui::Button::new("🔍 Load collections"),
+                )
+                .clicked()
+            {
+                self.job_spawner.spawn(rgis_network::OgcApiCollectionsJob {
+                    landing_page_url: landing_page_url.into(),
+                });
+                self.collections.0 = None;
+            }
+
+            ui.checkbox(
+                &mut self.state.restrict_to_visible_extent,
+                "Only load features in the current map view",
+            );
+
+            ui.separator();
+
+            let collections = match &self.collections.0 {
+                None => return,
+                Some(Err(e)) => {
+                    ui.colored_label(ui.visuals().error_fg_color, e.as_str());
+                    return;
+                }
+                Some(Ok(collections)) if collections.is_empty() => {
+                    ui.label("The service has no collections");
+                    return;
+                }
+                Some(Ok(collections)) => collections,
+            };
+
+            ui.label("Collections:");
+            let mut added = None;
+            egui::ScrollArea::vertical()
+                .max_height(300.)
+                .show(ui, |ui| {
+                    for collection in collections {
+                        ui.horizontal(|ui| {
+                            if ui.button("➕ Add").clicked() {
+                                added = Some(collection);
+                            }
+                            ui.label(collection.title.as_str())
+                                .on_hover_text(collection.id.as_str());
+                        });
+                    }
+                });
+
+            let Some(collection) = added else {
+                return;
+            };
+            let bbox = if self.state.restrict_to_visible_extent {
+                match visible_extent_in_crs(self.visible_extent, self.target_crs_epsg_code, 4326) {
+                    Ok(bbox) => bbox,
+                    Err(e) => {
+                        bevy::log::error!("Could not determine the visible extent: {:?}", e);
+                        return;
+                    }
+                }
+            } else {
+                None
+            };
+            self.events.load_file_event_writer.write(
+                rgis_events::LoadFileEvent::FromOgcApiFeatures {
+                    name: collection.title.clone(),
+                    items_url: collection.items_url.clone(),
+                    bbox,
+                },
+            );
+            self.events.hide_add_layer_window_events.send_default();
+            self.state.reset();
+        })
+        .response
+    }
+}
+
+struct WfsWidget<'a, 'w1, 's1, 'w2, 's2> {
+    state: &'a mut State,
+    capabilities: &'a mut WfsCapabilities,
+    visible_extent: rgis_units::VisibleExtent,
+    target_crs_epsg_code: u16,
+    job_spawner: &'a mut bevy_jobs::JobSpawner<'w1, 's1>,
+    events: &'a mut Events<'w2, 's2>,
+}
+
+impl egui::Widget for WfsWidget<'_, '_, '_, '_, '_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        ui.vertical(|ui| {
+            ui.label("Service URL:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.state.wfs_service_url)
+                    .hint_text("https://example.com/geoserver/wfs"),
+            );
+            let service_url = self.state.wfs_service_url.trim();
+            if ui
+                .add_enabled(
+                    !service_url.is_empty(),
+                    egui::Button::new("🔍 Load feature types"),
+                )
+                .clicked()
+            {
+                self.job_spawner.spawn(rgis_network::WfsCapabilitiesJob {
+                    service_url: service_url.into(),
+                });
+                self.capabilities.0 = None;
+            }
+
+            let capabilities = match &self.capabilities.0 {
+                None => return,
+                Some(Err(e)) => {
+                    ui.colored_label(ui.visuals().error_fg_color, e.as_str());
+                    return;
+                }
+                Some(Ok(capabilities)) => capabilities,
+            };
+
+            ui.separator();
+
+            ui.label("Output format:");
+            let geojson_output_format = capabilities.geojson_output_format.as_deref();
+            if geojson_output_format.is_none() {
+                self.state.wfs_output_format = WfsOutputFormat::Gml;
+            }
+            ui.add_enabled_ui(geojson_output_format.is_some(), |ui| {
+                ui.radio_value(
+                    &mut self.state.wfs_output_format,
+                    WfsOutputFormat::GeoJson,
+                    "GeoJSON",
+                )
+                .on_disabled_hover_text("The service doesn't offer GeoJSON output");
+            });
+            ui.radio_value(
+                &mut self.state.wfs_output_format,
+                WfsOutputFormat::Gml,
+                "GML",
+            );
+
+            ui.checkbox(
+                &mut self.state.restrict_to_visible_extent,
+                "Only load features in the current map view",
+            );
+
+            ui.separator();
+
+            if capabilities.feature_types.is_empty() {
+                ui.label("The service has no feature types");
+                return;
+            }
+
+            let crs_epsg_code = match self.state.crs_input_outcome {
+                Some(Ok(_)) => u16::from_str(&self.state.crs_input).ok(),
+                _ => None,
+            };
+
+            ui.label("Feature types:");
+            let mut added = None;
+            egui::ScrollArea::vertical()
+                .max_height(300.)
+                .show(ui, |ui| {
+                    for feature_type in &capabilities.feature_types {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .add_enabled(crs_epsg_code.is_some(), egui::Button::new("➕ Add"))
+                                .clicked()
+                            {
+                                added = Some(feature_type);
+                            }
+                            let label = ui.label(feature_type.title.as_str());
+                            match feature_type.default_crs_epsg_code {
+                                Some(code) => label.on_hover_text(format!(
+                                    "{}\nDefault CRS: EPSG:{code}",
+                                    feature_type.name
+                                )),
+                                None => label.on_hover_text(feature_type.name.as_str()),
+                            };
+                        });
+                    }
+                });
+
+            let (Some(feature_type), Some(crs_epsg_code)) = (added, crs_epsg_code) else {
+                return;
+            };
+            let bbox = if self.state.restrict_to_visible_extent {
+                match visible_extent_in_crs(
+                    self.visible_extent,
+                    self.target_crs_epsg_code,
+                    crs_epsg_code,
+                ) {
+                    Ok(bbox) => bbox,
+                    Err(e) => {
+                        bevy::log::error!("Could not determine the visible extent: {:?}", e);
+                        return;
+                    }
+                }
+            } else {
+                None
+            };
+            let output_format = match self.state.wfs_output_format {
+                WfsOutputFormat::GeoJson => geojson_output_format,
+                WfsOutputFormat::Gml => None,
+            };
+            self.events
+                .load_file_event_writer
+                .write(rgis_events::LoadFileEvent::FromWfs {
+                    name: feature_type.title.clone(),
+                    service_url: self.state.wfs_service_url.clone(),
+                    type_name: feature_type.name.clone(),
+                    output_format: output_format.map(String::from),
+                    crs_epsg_code,
+                    bbox,
+                    page_size: capabilities.page_size(),
+                });
+            self.events.hide_add_layer_window_events.send_default();
+            self.state.reset();
+        })
+        .response
+    }
+}
+
+/// The visible extent as `[min_x, min_y, max_x, max_y]` in the CRS `crs_epsg_code`.
+fn visible_extent_in_crs(
+    visible_extent: rgis_units::VisibleExtent,
+    target_crs_epsg_code: u16,
+    crs_epsg_code: u16,
+) -> Result<Option<[f64; 4]>, geo_geodesy::Error> {
+    let Some(rect) = visible_extent.0 else {
+        return Ok(None);
+    };
+    // Transform every corner since the rectangle isn't axis-aligned in other CRSs
+    let mut geometry: geo::Geometry<geo_projected::ProjectedScalar> = rect.to_polygon().into();
+    geo_geodesy::Transformer::setup(target_crs_epsg_code, crs_epsg_code)?
+        .transform(&mut geometry)?;
+    Ok(geometry.bounding_rect().map(|rect| {
+        [
+            rect.min().x.0,
+            rect.min().y.0,
+            rect.max().x.0,
+            rect.max().y.0,
+        ]
+    }))
+}
+
+/// Use the last segment of the URL's path as the layer name.
+fn name_from_url(url: &str) -> String {
+    url.split(['?', '#'])
+        .next()
+        .and_then(|path| path.trim_end_matches('/').rsplit('/').next())
+        .filter(|segment| !segment.is_empty())
+        .unwrap_or(url)
+        .into()
+}
+
+/// The service of an ArcGIS REST layer URL, e.g. `Parcels` for `…/Parcels/FeatureServer/0`.
+fn arcgis_service_name(url: &str) -> Option<String> {
+    url.split('/')
+        .collect::<Vec<_>>()
+        .windows(2)
+        .find_map(|pair| match pair {
+            [service, "FeatureServer" | "MapServer"] => Some((*service).to_owned()),
+            _ => None,
+        })
+}
@@ -0,0 +1,398 @@
+use bevy_egui::egui;
+use geo_features::Value;
+use std::{cmp, collections};
+
+const ID_COLUMN_WIDTH: f32 = 60.;
+const COLUMN_WIDTH: f32 = 120.;
+
+#[derive(Default)]
+pub(crate) struct AttributeTableWindowState {
+    pub layer_id: Option<rgis_layer_id::LayerId>,
+    pub is_visible: bool,
+    sort: Option<Sort>,
+    table: Option<Table>,
+}
+
+#[derive(Clone, PartialEq)]
+struct Sort {
+    column: String,
+    ascending: bool,
+}
+
+/// Columns and row order derived from a layer's features. Rebuilt when the layer, its revision,
+/// its filter or the sort order changes.
+struct Table {
+    layer_id: rgis_layer_id::LayerId,
+    revision: u64,
+    filter: Option<String>,
+    sort: Option<Sort>,
+    columns: Vec<Column>,
+    /// Positions of the layer's features, in display order.
+    rows: Vec<usize>,
+}
+
+impl Table {
+    fn new(layer: &rgis_layers::Layer, sort: Option<Sort>) -> Self {
+        let features = layer.unprojected_feature_collection.features();
+
+        let mut column_types = collections::BTreeMap::<&str, ColumnType>::new();
+        for feature in features {
+            for (name, value) in &feature.properties {
+                let column_type = column_types.entry(name).or_insert(ColumnType::Empty);
+                *column_type = column_type.merge(ColumnType::of(value));
+            }
+        }
+        let columns = column_types
+            .into_iter()
+            .map(|(name, column_type)| Column {
+                name: name.to_owned(),
+                column_type,
+            })
+            .collect();
+
+        let mut rows = features
+            .iter()
+            .enumerate()
+            .filter(|(_, feature)| layer.matches_filter(feature))
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        if let Some(ref sort) = sort {
+            rows.sort_by(|a, b| {
+                let value = |index: &usize| {
+                    features
+                        .get(*index)
+                        .and_then(|feature| feature.properties.get(&sort.column))
+                };
+                compare_values(value(a), value(b), sort.ascending)
+            });
+        }
+
+        Table {
+            layer_id: layer.id,
+            revision: layer.revision(),
+            filter: layer.filter().map(|filter| filter.as_str().to_owned()),
+            sort,
+            columns,
+            rows,
+        }
+    }
+
+    fn is_stale(&self, layer: &rgis_layers::Layer, sort: &Option<Sort>) -> bool {
+        self.layer_id != layer.id
+            || self.revision != layer.revision()
+            || self.filter.as_deref() != layer.filter().map(|filter| filter.as_str())
+            || self.sort != *sort
+    }
+}
+
+struct Column {
+    name: String,
+    column_type: ColumnType,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ColumnType {
+    /// Only null values, so far
+    Empty,
+    Integer,
+    Number,
+    Boolean,
+    Date,
+    DateTime,
+    Text,
+    Binary,
+    Json,
+    Mixed,
+}
+
+impl ColumnType {
+    fn of(value: &Value) -> Self {
+        match value {
+            Value::String(_) => ColumnType::Text,
+            Value::Integer(_) => ColumnType::Integer,
+            Value::Number(_) => ColumnType::Number,
+            Value::Boolean(_) => ColumnType::Boolean,
+            Value::Date(_) => ColumnType::Date,
+            Value::DateTime(_) => ColumnType::DateTime,
+            Value::Binary(_) => ColumnType::Binary,
+            Value::Array(_) | Value::Object(_) => ColumnType::Json,
+            Value::Null => ColumnType::Empty,
+        }
+    }
+
+    fn merge(self, other: Self) -> Self {
+        match (self, other) {
+            (ColumnType::Empty, other) | (other, ColumnType::Empty) => other,
+            (a, b) if a == b => a,
+            (ColumnType::Integer, ColumnType::Number)
+            | (ColumnType::Number, ColumnType::Integer) => ColumnType::Number,
+            _ => ColumnType::Mixed,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ColumnType::Empty => "empty",
+            ColumnType::Integer => "integer",
+            ColumnType::Number => "number",
+            ColumnType::Boolean => "boolean",
+            ColumnType::Date => "date",
+            ColumnType::DateTime => "date and time",
+            ColumnType::Text => "text",
+            ColumnType::Binary => "binary",
+            ColumnType::Json => "JSON",
+            ColumnType::Mixed => "mixed",
+        }
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(self, ColumnType::Integer | ColumnType::Number)
+    }
+}
+
+/// Missing and null values always sort last. Numbers are compared numerically, everything else
+/// by its text.
+fn compare_values(a: Option<&Value>, b: Option<&Value>, ascending: bool) -> cmp::Ordering {
+    let a = a.filter(|value| !matches!(value, Value::Null));
+    let b = b.filter(|value| !matches!(value, Value::Null));
+    let (a, b) = match (a, b) {
+        (None, None) => return cmp::Ordering::Equal,
+        (None, Some(_)) => return cmp::Ordering::Greater,
+        (Some(_), None) => return cmp::Ordering::Less,
+        (Some(a), Some(b)) => (a, b),
+    };
+    let ordering = match (a, b) {
+        (Value::Integer(a), Value::Integer(b)) => a.cmp(b),
+        (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
+        (
+            Value::String(a) | Value::Date(a) | Value::DateTime(a),
+            Value::String(b) | Value::Date(b) | Value::DateTime(b),
+        ) => a.cmp(b),
+        _ => match (as_number(a), as_number(b)) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            _ => a.to_string().cmp(&b.to_string()),
+        },
+    };
+    if ascending {
+        ordering
+    } else {
+        ordering.reverse()
+    }
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(n) => Some(*n as f64),
+        Value::Number(n) => Some(*n),
+        _ => None,
+    }
+}
+
+pub(crate) struct AttributeTableWindow<'a, 'w> {
+    pub state: &'a mut AttributeTableWindowState,
+    pub layers: &'a rgis_layers::Layers,
+    pub egui_ctx: &'a mut bevy_egui::egui::Context,
+    pub update_selection_event_writer:
+        &'a mut bevy::ecs::event::EventWriter<'w, rgis_events::UpdateSelectionEvent>,
+    pub center_camera_on_feature_event_writer:
+        &'a mut bevy::ecs::event::EventWriter<'w, rgis_events::CenterCameraOnFeatureEvent>,
+}
+
+impl AttributeTableWindow<'_, '_> {
+    pub(crate) fn render(&mut self) {
+        let (true, Some(layer_id)) = (self.state.is_visible, self.state.layer_id) else {
+            return;
+        };
+        let Some(layer) = self.layers.get(layer_id) else {
+            bevy::log::warn!(
+                "Could not find layer with ID {:?}, closing attribute table window",
+                layer_id
+            );
+            self.state.is_visible = false;
+            return;
+        };
+
+        let AttributeTableWindowState {
+            is_visible,
+            sort,
+            table,
+            ..
+        } = &mut *self.state;
+        if table
+            .as_ref()
+            .is_none_or(|table| table.is_stale(layer, sort))
+        {
+            *table = Some(Table::new(layer, sort.clone()));
+        }
+        let Some(table) = table else {
+            return;
+        };
+        let features = layer.unprojected_feature_collection.features();
+
+        egui::Window::new(format!("Attribute Table: {}", layer.name))
+            .id(egui::Id::new("Attribute Table Window"))
+            .default_size([600., 400.])
+            .open(is_visible)
+            .show(self.egui_ctx, |ui| {
+                if layer.filter().is_some() {
+                    ui.label(format!(
+                        "{} of {} features match the layer's filter",
+                        table.rows.len(),
+                        features.len()
+                    ));
+                } else {
+                    ui.label(format!("{} features", features.len()));
+                }
+                egui::ScrollArea::horizontal().show(ui, |ui| {
+                    ui.vertical(|ui| {
+                        let row_height = ui.spacing().interact_size.y;
+                        ui.horizontal(|ui| {
+                            ui.add_space(
+                                ui.spacing().interact_size.x + ui.spacing().item_spacing.x,
+                            );
+                            ui.add_sized([ID_COLUMN_WIDTH, row_height], egui::Label::new("ID"));
+                            for column in &table.columns {
+                                ui.add(ColumnHeader { column, sort });
+                            }
+                        });
+                        ui.separator();
+
+                        egui::ScrollArea::vertical().auto_shrink(false).show_rows(
+                            ui,
+                            row_height,
+                            table.rows.len(),
+                            |ui, range| {
+                                for feature in table
+                                    .rows
+                                    .get(range)
+                                    .unwrap_or_default()
+                                    .iter()
+                                    .filter_map(|index| features.get(*index))
+                                {
+                                    ui.horizontal(|ui| {
+                                        if ui
+                                            .button("🔎")
+                                            .on_hover_text("Zoom to feature")
+                                            .clicked()
+                                        {
+                                            self.center_camera_on_feature_event_writer.write(
+                                                rgis_events::CenterCameraOnFeatureEvent(
+                                                    layer_id, feature.id,
+                                                ),
+                                            );
+                                        }
+                                        let is_selected =
+                                            layer.selected_feature_ids.contains(&feature.id);
+                                        if ui
+                                            .add_sized(
+                                                [ID_COLUMN_WIDTH, row_height],
+                                                egui::SelectableLabel::new(
+                                                    is_selected,
+                                                    feature.id.to_string(),
+                                                ),
+                                            )
+                                            .on_hover_text(
+                                                "Shift-click to add to the selection, Ctrl-click to toggle",
+                                            )
+                                            .clicked()
+                                        {
+                                            self.update_selection_event_writer.write(
+                                                rgis_events::UpdateSelectionEvent::Features {
+                                                    layer_id,
+                                                    feature_ids: vec![feature.id],
+                                                    mode: selection_mode(ui),
+                                                },
+                                            );
+                                        }
+                                        for column in &table.columns {
+                                            let text = feature
+                                                .properties
+                                                .get(&column.name)
+                                                .map(ToString::to_string)
+                                                .unwrap_or_default();
+                                            ui.add(Cell {
+                                                text,
+                                                is_numeric: column.column_type.is_numeric(),
+                                                row_height,
+                                            });
+                                        }
+                                    });
+                                }
+                            },
+                        );
+                    });
+                });
+            });
+    }
+}
+
+/// Shift adds to the selection, and Ctrl (or ⌘) toggles, as when clicking on the map.
+fn selection_mode(ui: &egui::Ui) -> rgis_events::SelectionMode {
+    let modifiers = ui.input(|input| input.modifiers);
+    if modifiers.command {
+        rgis_events::SelectionMode::Toggle
+    } else if modifiers.shift {
+        rgis_events::SelectionMode::Add
+    } else {
+        rgis_events::SelectionMode::Replace
+    }
+}
+
+/// Clicking sorts by the column, and clicking again reverses the order.
+struct ColumnHeader<'a> {
+    column: &'a Column,
+    sort: &'a mut Option<Sort>,
+}
+
+impl egui::Widget for ColumnHeader<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let ascending = match self.sort {
+            Some(sort) if sort.column == self.column.name => Some(sort.ascending),
+            _ => None,
+        };
+        let text = match ascending {
+            Some(true) => format!("{} ⏶", self.column.name),
+            Some(false) => format!("{} ⏷", self.column.name),
+            None => self.column.name.clone(),
+        };
+        let row_height = ui.spacing().interact_size.y;
+        let response = ui
+            .add_sized(
+                [COLUMN_WIDTH, row_height],
+                egui::Button::new(text).truncate(),
+            )
+            .on_hover_text(format!(
+                "{} ({})",
+                self.column.name,
+                self.column.column_type.name()
+            ));
+        if response.clicked() {
+            *self.sort = Some(Sort {
+                column: self.column.name.clone(),
+                ascending: ascending != Some(true),
+            });
+        }
+        response
+    }
+}
+
+struct Cell {
+    text: String,
+    is_numeric: bool,
+    row_height: f32,
+}
+
+impl egui::Widget for Cell {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let layout = if self.is_numeric {
+            egui::Layout::right_to_left(egui::Align::Center)
+        } else {
+            egui::Layout::left_to_right(egui::Align::Center)
+        };
+        ui.allocate_ui_with_layout(egui::vec2(COLUMN_WIDTH, self.row_height), layout, |ui| {
+            ui.set_min_width(COLUMN_WIDTH);
+            ui.add(egui::Label::new(self.text).truncate())
+        })
+        .inner
+    }
+}
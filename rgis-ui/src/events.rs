@@ -4,4 +4,8 @@ use bevy::prelude::Event;
 pub struct OpenOperationWindowEvent {
     pub operation: Box<dyn Send + Sync + rgis_geo_ops::Operation>,
     pub feature_collection: geo_features::FeatureCollection<geo_projected::UnprojectedScalar>,
+    pub operation_name: &'static str,
+    pub layer_id: rgis_layer_id::LayerId,
+    pub layer_name: String,
+    pub crs_epsg_code: u16,
 }
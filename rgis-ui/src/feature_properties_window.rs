@@ -1,44 +1,58 @@
 use bevy_egui::egui;
 
-pub(crate) struct FeaturePropertiesWindow<'a> {
+pub(crate) struct FeaturePropertiesWindow<'a, 'w> {
     pub egui_ctx: &'a mut bevy_egui::egui::Context,
     pub state: &'a mut crate::FeaturePropertiesWindowState,
     pub layer: &'a rgis_layers::Layer,
+    pub update_feature_properties_event_writer:
+        &'a mut bevy::ecs::event::EventWriter<'w, rgis_events::UpdateFeaturePropertiesEvent>,
 }
 
-impl FeaturePropertiesWindow<'_> {
+impl FeaturePropertiesWindow<'_, '_> {
     pub(crate) fn render(&mut self) {
-        let Some(ref properties) = self.state.properties else {
+        let crate::FeaturePropertiesWindowState {
+            layer_id: _,
+            feature_id: Some(feature_id),
+            properties: Some(ref properties),
+            ref mut draft,
+            ref mut is_visible,
+        } = *self.state
+        else {
             return;
         };
         egui::Window::new("Layer Feature Properties")
             .id(egui::Id::new("Layer Feature Properties Window"))
-            .open(&mut self.state.is_visible)
+            .open(is_visible)
             .show(self.egui_ctx, |ui| {
                 ui.label(format!("Layer: {}", self.layer.name));
-                ui.add(FeaturePropertiesTable { properties })
-            });
-    }
-}
+                ui.separator();
+                ui.add(crate::widgets::PropertiesEditor { draft });
+                ui.separator();
 
-struct FeaturePropertiesTable<'a> {
-    properties: &'a geo_features::Properties,
-}
-
-impl egui::Widget for FeaturePropertiesTable<'_> {
-    fn ui(self, ui: &mut bevy_egui::egui::Ui) -> bevy_egui::egui::Response {
-        egui::Grid::new("feature_properties_window_grid")
-            .num_columns(2)
-            .striped(true)
-            .show(ui, |ui| {
-                let mut sorted = self.properties.iter().collect::<Vec<_>>();
-                sorted.sort_unstable_by_key(|n| n.0);
-                for (k, v) in sorted.iter() {
-                    ui.label(*k);
-                    ui.label(format!("{v:?}"));
-                    ui.end_row();
+                let edited = draft.to_properties();
+                if let Err(e) = &edited {
+                    ui.colored_label(ui.visuals().error_fg_color, e);
                 }
-            })
-            .response
+                let is_changed = edited.as_ref().is_ok_and(|edited| edited != properties);
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(is_changed, egui::Button::new("Apply"))
+                        .clicked()
+                    {
+                        if let Ok(edited) = edited {
+                            self.update_feature_properties_event_writer.write(
+                                rgis_events::UpdateFeaturePropertiesEvent {
+                                    layer_id: self.layer.id,
+                                    feature_id,
+                                    properties: edited,
+                                },
+                            );
+                        }
+                    }
+                    if ui.button("Revert").clicked() {
+                        *draft = crate::widgets::PropertiesDraft::from_properties(properties);
+                    }
+                });
+            });
     }
 }
@@ -0,0 +1,125 @@
+use bevy::prelude::*;
+use bevy_egui::egui::{self, Widget};
+
+/// Jobs that ended with an error, until they're retried or dismissed.
+#[derive(Default, Resource)]
+pub(crate) struct FailedJobs(pub Vec<rgis_events::JobFailedEvent>);
+
+pub(crate) struct JobsWindow<'a, 'w, 's> {
+    pub egui_ctx: &'a mut bevy_egui::egui::Context,
+    pub in_progress_jobs: Vec<(Entity, &'a bevy_jobs::InProgressJob)>,
+    pub failed_jobs: &'a mut FailedJobs,
+    pub commands: &'a mut Commands<'w, 's>,
+    pub load_file_events: &'a mut Events<rgis_events::LoadFileEvent>,
+    pub retry_page_events: &'a mut Events<rgis_events::RetryPageEvent>,
+    pub reproject_layer_events: &'a mut Events<rgis_events::ReprojectLayerEvent>,
+}
+
+impl JobsWindow<'_, '_, '_> {
+    pub(crate) fn render(&mut self) {
+        if self.in_progress_jobs.is_empty() && self.failed_jobs.0.is_empty() {
+            return;
+        }
+
+        egui::Window::new("Jobs")
+            .open(&mut true)
+            .title_bar(false)
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-5., -5.])
+            .resizable(false)
+            .show(self.egui_ctx, |ui| {
+                for (entity, in_progress_job) in &self.in_progress_jobs {
+                    if (InProgressJobRow { in_progress_job }).show(ui) {
+                        // Dropping the job's progress receiver also stops jobs that report
+                        // progress at their next report
+                        self.commands.entity(*entity).despawn();
+                    }
+                }
+
+                let mut retried_or_dismissed = None;
+                for (i, failed_job) in self.failed_jobs.0.iter().enumerate() {
+                    match (FailedJobRow { failed_job }).show(ui) {
+                        Some(FailedJobAction::Retry) => {
+                            match &failed_job.retry {
+                                rgis_events::RetryJob::LoadFile(event) => {
+                                    self.load_file_events.send(event.clone());
+                                }
+                                rgis_events::RetryJob::LoadPage(layer_id) => {
+                                    self.retry_page_events
+                                        .send(rgis_events::RetryPageEvent(*layer_id));
+                                }
+                                rgis_events::RetryJob::ReprojectLayer(layer_id) => {
+                                    self.reproject_layer_events
+                                        .send(rgis_events::ReprojectLayerEvent(*layer_id));
+                                }
+                            }
+                            retried_or_dismissed = Some(i);
+                        }
+                        Some(FailedJobAction::Dismiss) => retried_or_dismissed = Some(i),
+                        None => {}
+                    }
+                }
+                if let Some(i) = retried_or_dismissed {
+                    self.failed_jobs.0.remove(i);
+                }
+            });
+    }
+}
+
+struct InProgressJobRow<'a> {
+    in_progress_job: &'a bevy_jobs::InProgressJob,
+}
+
+impl InProgressJobRow<'_> {
+    /// Returns whether the job was cancelled.
+    fn show(self, ui: &mut egui::Ui) -> bool {
+        let Self { in_progress_job } = self;
+
+        let name = &in_progress_job.name;
+        let progress = in_progress_job.progress;
+
+        ui.horizontal(|ui| {
+            ui.add(egui::Spinner::new());
+            if progress > 0 {
+                egui::ProgressBar::new(f32::from(progress) / 100.)
+                    .desired_width(200.)
+                    .text(format!("Running '{name}'"))
+                    .ui(ui);
+            } else {
+                ui.label(format!("Running '{name}'"));
+            }
+            ui.button("✖").on_hover_text("Cancel").clicked()
+        })
+        .inner
+    }
+}
+
+enum FailedJobAction {
+    Retry,
+    Dismiss,
+}
+
+struct FailedJobRow<'a> {
+    failed_job: &'a rgis_events::JobFailedEvent,
+}
+
+impl FailedJobRow<'_> {
+    fn show(self, ui: &mut egui::Ui) -> Option<FailedJobAction> {
+        let mut action = None;
+        ui.horizontal(|ui| {
+            ui.colored_label(ui.visuals().error_fg_color, "⚠");
+            ui.label(&self.failed_job.name);
+            if ui.button("🔁 Retry").clicked() {
+                action = Some(FailedJobAction::Retry);
+            }
+            if ui.button("✖").on_hover_text("Dismiss").clicked() {
+                action = Some(FailedJobAction::Dismiss);
+            }
+        });
+        ui.label(
+            egui::RichText::new(&self.failed_job.error)
+                .small()
+                .color(ui.visuals().error_fg_color),
+        );
+        action
+    }
+}
@@ -9,17 +9,24 @@
 use bevy::prelude::*;
 
 mod add_layer_window;
+mod attribute_table_window;
 mod bottom_panel;
 mod change_crs_window;
 mod debug_window;
 mod events;
 mod feature_properties_window;
+mod jobs_window;
 mod manage_layer_window;
+mod measurement_window;
 mod message_window;
+mod new_feature_window;
+mod notifications;
 mod operation_window;
+mod recent_sources;
 mod side_panel;
 mod systems;
 mod top_panel;
+mod vertex_editor_window;
 mod welcome_window;
 mod widgets;
 
@@ -37,12 +44,20 @@ pub struct MessageWindowState {
 pub struct ManageLayerWindowState {
     layer_id: Option<rgis_layer_id::LayerId>,
     is_visible: bool,
+    /// Name being edited, applied to the layer once the text field loses focus
+    name_text: String,
+    /// Filter expression being edited, applied to the layer once it parses.
+    filter_text: String,
+    filter_error: Option<String>,
 }
 
 #[derive(Default)]
 pub struct FeaturePropertiesWindowState {
     layer_id: Option<rgis_layer_id::LayerId>,
+    feature_id: Option<geo_features::FeatureId>,
+    /// The feature's properties when `draft` was made from them
     properties: Option<geo_features::Properties>,
+    draft: widgets::PropertiesDraft,
     is_visible: bool,
 }
 
@@ -51,6 +66,11 @@ struct OperationWindowState {
     is_visible: bool,
     operation: Option<Box<dyn Send + Sync + rgis_geo_ops::Operation>>,
     feature_collection: geo_features::FeatureCollection<geo_projected::UnprojectedScalar>,
+    operation_name: &'static str,
+    layer_id: Option<rgis_layer_id::LayerId>,
+    layer_name: String,
+    /// CRS of `feature_collection`
+    crs_epsg_code: u16,
 }
 
 impl bevy::app::Plugin for Plugin {
@@ -59,6 +79,10 @@ impl bevy::app::Plugin for Plugin {
             enable_multipass_for_primary_context: false, // is this the right value?
         })
         .insert_resource(add_layer_window::SelectedFile(None))
+        .init_resource::<add_layer_window::OgcApiCollections>()
+        .init_resource::<add_layer_window::WfsCapabilities>()
+        .init_resource::<jobs_window::FailedJobs>()
+        .init_resource::<notifications::Notifications>()
         .insert_resource(rgis_units::TopPanelHeight(0.))
         .insert_resource(rgis_units::BottomPanelHeight(0.))
         .insert_resource(rgis_units::SidePanelWidth(0.))
@@ -1,14 +1,20 @@
 use bevy::color::ColorToComponents;
 use bevy_egui::egui;
 
-pub(crate) struct ManageLayerWindow<'a> {
+pub(crate) struct ManageLayerWindow<'a, 'w> {
     pub state: &'a mut crate::ManageLayerWindowState,
     pub layers: &'a rgis_layers::Layers,
     pub egui_ctx: &'a mut bevy_egui::egui::Context,
     pub color_events: &'a mut bevy::ecs::event::Events<rgis_events::UpdateLayerColorEvent>,
+    pub update_layer_filter_event_writer:
+        &'a mut bevy::ecs::event::EventWriter<'w, rgis_events::UpdateLayerFilterEvent>,
+    pub create_layer_event_writer:
+        &'a mut bevy::ecs::event::EventWriter<'w, rgis_events::CreateLayerEvent>,
+    pub rename_layer_event_writer:
+        &'a mut bevy::ecs::event::EventWriter<'w, rgis_events::RenameLayerEvent>,
 }
 
-impl ManageLayerWindow<'_> {
+impl ManageLayerWindow<'_, '_> {
     pub(crate) fn render(&mut self) {
         let (true, Some(layer_id)) = (self.state.is_visible, self.state.layer_id) else {
             return;
@@ -21,15 +27,26 @@ impl ManageLayerWindow<'_> {
             self.state.is_visible = false;
             return;
         };
+        let crate::ManageLayerWindowState {
+            is_visible,
+            name_text,
+            filter_text,
+            filter_error,
+            ..
+        } = &mut *self.state;
         egui::Window::new("Manage Layer")
-            .open(&mut self.state.is_visible)
+            .open(is_visible)
             .show(self.egui_ctx, |ui| {
                 egui::Grid::new("manage_layer_window_grid")
                     .num_columns(2)
                     .striped(true)
                     .show(ui, |ui| {
                         ui.label("Name");
-                        ui.label(&layer.name);
+                        ui.add(NameWidget {
+                            layer,
+                            name_text,
+                            rename_layer_event_writer: self.rename_layer_event_writer,
+                        });
                         ui.end_row();
                         ui.label("CRS");
                         ui.label(format!("EPSG {}", layer.crs_epsg_code));
@@ -54,8 +71,114 @@ impl ManageLayerWindow<'_> {
                             color_events: self.color_events,
                         });
                         ui.end_row();
+                        ui.label("Filter");
+                        ui.add(FilterWidget {
+                            layer_id,
+                            filter_text,
+                            filter_error,
+                            update_layer_filter_event_writer: self.update_layer_filter_event_writer,
+                        });
+                        ui.end_row();
                     });
+
+                if let Some(filter) = layer.filter() {
+                    ui.separator();
+                    ui.label(format!(
+                        "{} of {} features match",
+                        layer.filtered_feature_count(),
+                        layer.unprojected_feature_collection.features().len()
+                    ));
+                    if ui.button("Create layer from filter").clicked() {
+                        self.create_layer_event_writer
+                            .write(rgis_events::CreateLayerEvent {
+                                feature_collection: geo_features::FeatureCollection::from_features(
+                                    layer.filtered_unprojected_features().cloned().collect(),
+                                ),
+                                name: format!("{} ({})", layer.name, filter),
+                                source_crs_epsg_code: layer.crs_epsg_code,
+                            });
+                    }
+                }
+            });
+    }
+}
+
+/// Renames the layer once editing the name is done.
+struct NameWidget<'a, 'w> {
+    layer: &'a rgis_layers::Layer,
+    name_text: &'a mut String,
+    rename_layer_event_writer:
+        &'a mut bevy::ecs::event::EventWriter<'w, rgis_events::RenameLayerEvent>,
+}
+
+impl egui::Widget for NameWidget<'_, '_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let response = ui.text_edit_singleline(self.name_text);
+        if response.lost_focus() {
+            let name = self.name_text.trim();
+            if !name.is_empty() && name != self.layer.name {
+                self.rename_layer_event_writer
+                    .write(rgis_events::RenameLayerEvent(
+                        self.layer.id,
+                        name.to_owned(),
+                    ));
+            }
+        } else if !response.has_focus() {
+            // Follow renames made elsewhere, like undoing one
+            self.name_text.clone_from(&self.layer.name);
+        }
+        response
+    }
+}
+
+/// Applies the filter when Enter is pressed or "Apply" is clicked, and clears it with "Clear".
+struct FilterWidget<'a, 'w> {
+    layer_id: rgis_layer_id::LayerId,
+    filter_text: &'a mut String,
+    filter_error: &'a mut Option<String>,
+    update_layer_filter_event_writer:
+        &'a mut bevy::ecs::event::EventWriter<'w, rgis_events::UpdateLayerFilterEvent>,
+}
+
+impl egui::Widget for FilterWidget<'_, '_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        ui.vertical(|ui| {
+            let text_edit_response = ui.add(
+                egui::TextEdit::singleline(&mut *self.filter_text)
+                    .hint_text("population > 1e6 AND name LIKE 'New%'"),
+            );
+            let is_submitted = text_edit_response.lost_focus()
+                && ui.input(|input| input.key_pressed(egui::Key::Enter));
+            ui.horizontal(|ui| {
+                let is_applied = ui.button("Apply").clicked() || is_submitted;
+                let is_cleared = ui.button("Clear").clicked();
+                let filter = if is_cleared {
+                    self.filter_text.clear();
+                    Some(None)
+                } else if !is_applied {
+                    None
+                } else if self.filter_text.trim().is_empty() {
+                    Some(None)
+                } else {
+                    match geo_features::filter::Filter::parse(self.filter_text) {
+                        Ok(filter) => Some(Some(filter)),
+                        Err(e) => {
+                            *self.filter_error = Some(e.to_string());
+                            None
+                        }
+                    }
+                };
+                if let Some(filter) = filter {
+                    *self.filter_error = None;
+                    self.update_layer_filter_event_writer
+                        .write(rgis_events::UpdateLayerFilterEvent(self.layer_id, filter));
+                }
             });
+            if let Some(ref error) = self.filter_error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+        })
+        .response
     }
 }
 
@@ -0,0 +1,125 @@
+use bevy_egui::egui;
+
+pub(crate) struct MeasurementWindow<'a> {
+    pub egui_ctx: &'a mut bevy_egui::egui::Context,
+    pub measurement: &'a mut rgis_mouse::Measurement,
+    pub rgis_settings: &'a mut rgis_settings::RgisSettings,
+}
+
+impl MeasurementWindow<'_> {
+    pub(crate) fn render(&mut self) {
+        let mut is_open = true;
+        egui::Window::new("Measure")
+            .id(egui::Id::new("Measurement window"))
+            .open(&mut is_open)
+            .resizable(false)
+            .show(self.egui_ctx, |ui| {
+                ui.add(UnitComboBoxes {
+                    rgis_settings: self.rgis_settings,
+                });
+                ui.separator();
+                if self.measurement.is_empty() {
+                    ui.label(
+                        "Click on the map to add vertices. Double-click or press Enter to \
+                         finish, click the first vertex to close the shape, or press Esc to \
+                         start over.",
+                    );
+                    return;
+                }
+                ui.add(MeasurementTable {
+                    measurement: self.measurement,
+                    length_unit: self.rgis_settings.length_unit,
+                    area_unit: self.rgis_settings.area_unit,
+                });
+                ui.separator();
+                if ui.button("🗑 Clear").clicked() {
+                    self.measurement.clear();
+                }
+            });
+        if !is_open {
+            self.measurement.clear();
+            self.rgis_settings.current_tool = rgis_settings::Tool::Pan;
+        }
+    }
+}
+
+struct UnitComboBoxes<'a> {
+    rgis_settings: &'a mut rgis_settings::RgisSettings,
+}
+
+impl egui::Widget for UnitComboBoxes<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        ui.horizontal(|ui| {
+            ui.label("Units:");
+            egui::ComboBox::from_id_salt("measurement-length-unit")
+                .selected_text(self.rgis_settings.length_unit.symbol())
+                .show_ui(ui, |ui| {
+                    for unit in rgis_units::LengthUnit::ALL {
+                        ui.selectable_value(
+                            &mut self.rgis_settings.length_unit,
+                            unit,
+                            unit.symbol(),
+                        );
+                    }
+                });
+            egui::ComboBox::from_id_salt("measurement-area-unit")
+                .selected_text(self.rgis_settings.area_unit.symbol())
+                .show_ui(ui, |ui| {
+                    for unit in rgis_units::AreaUnit::ALL {
+                        ui.selectable_value(&mut self.rgis_settings.area_unit, unit, unit.symbol());
+                    }
+                });
+        })
+        .response
+    }
+}
+
+struct MeasurementTable<'a> {
+    measurement: &'a rgis_mouse::Measurement,
+    length_unit: rgis_units::LengthUnit,
+    area_unit: rgis_units::AreaUnit,
+}
+
+impl egui::Widget for MeasurementTable<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let segment_lengths = self.measurement.segment_lengths();
+        egui::ScrollArea::vertical()
+            .max_height(200.)
+            .show(ui, |ui| {
+                egui::Grid::new("measurement_window_segments_grid")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (i, length) in segment_lengths.iter().enumerate() {
+                            let is_cursor_segment =
+                                !self.measurement.is_finished() && i + 1 == segment_lengths.len();
+                            ui.label(if is_cursor_segment {
+                                String::from("Segment (cursor)")
+                            } else {
+                                format!("Segment {}", i + 1)
+                            });
+                            ui.label(self.length_unit.format(*length));
+                            ui.end_row();
+                        }
+                    });
+            });
+        ui.separator();
+        egui::Grid::new("measurement_window_totals_grid")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.strong(if self.measurement.is_closed() {
+                    "Perimeter"
+                } else {
+                    "Total length"
+                });
+                ui.label(self.length_unit.format(self.measurement.total_length()));
+                ui.end_row();
+                if let Some(area) = self.measurement.area() {
+                    ui.strong("Area");
+                    ui.label(self.area_unit.format(area));
+                    ui.end_row();
+                }
+            })
+            .response
+    }
+}
@@ -0,0 +1,75 @@
+use bevy_egui::egui;
+use std::collections;
+
+/// Features drawn on the map, waiting for their properties to be filled in.
+#[derive(Default)]
+pub struct NewFeatureWindowState {
+    pub pending: collections::VecDeque<geo::Geometry<geo_projected::ProjectedScalar>>,
+    properties: crate::widgets::PropertiesDraft,
+}
+
+pub(crate) struct NewFeatureWindow<'a, 'w> {
+    pub egui_ctx: &'a mut bevy_egui::egui::Context,
+    pub state: &'a mut NewFeatureWindowState,
+    pub add_feature_event_writer:
+        &'a mut bevy::ecs::event::EventWriter<'w, rgis_events::AddFeatureEvent>,
+}
+
+impl NewFeatureWindow<'_, '_> {
+    pub(crate) fn render(&mut self) {
+        let Some(geometry) = self.state.pending.front() else {
+            return;
+        };
+        let mut is_open = true;
+        let mut is_done = false;
+        egui::Window::new("New Feature")
+            .id(egui::Id::new("New Feature Window"))
+            .open(&mut is_open)
+            .show(self.egui_ctx, |ui| {
+                ui.label(match geometry {
+                    geo::Geometry::Point(_) => "Geometry: point",
+                    geo::Geometry::LineString(_) => "Geometry: line",
+                    _ => "Geometry: polygon",
+                });
+                if self.state.pending.len() > 1 {
+                    ui.label(format!("{} more drawn", self.state.pending.len() - 1));
+                }
+                ui.separator();
+                ui.add(crate::widgets::PropertiesEditor {
+                    draft: &mut self.state.properties,
+                });
+                ui.separator();
+
+                let properties = self.state.properties.to_properties();
+                if let Err(e) = &properties {
+                    ui.colored_label(ui.visuals().error_fg_color, e);
+                }
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(
+                            properties.is_ok(),
+                            egui::Button::new("Add to scratch layer"),
+                        )
+                        .clicked()
+                    {
+                        if let Ok(properties) = properties {
+                            self.add_feature_event_writer
+                                .write(rgis_events::AddFeatureEvent {
+                                    geometry: geometry.clone(),
+                                    properties,
+                                });
+                        }
+                        is_done = true;
+                    }
+                    if ui.button("Discard").clicked() {
+                        is_done = true;
+                    }
+                });
+            });
+
+        if is_done || !is_open {
+            // The properties are kept to prefill the next feature
+            self.state.pending.pop_front();
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+use bevy_egui::egui;
+use std::collections::VecDeque;
+
+// Older notifications are dropped beyond this
+const MAX_NOTIFICATIONS: usize = 5;
+
+#[derive(Default, Resource)]
+pub(crate) struct Notifications {
+    next_id: u64,
+    /// Newest first, each with an ID that keeps its egui state stable as others are dismissed.
+    notifications: VecDeque<(u64, rgis_events::ErrorNotificationEvent)>,
+}
+
+impl Notifications {
+    pub(crate) fn push(&mut self, notification: rgis_events::ErrorNotificationEvent) {
+        self.notifications.push_front((self.next_id, notification));
+        self.notifications.truncate(MAX_NOTIFICATIONS);
+        self.next_id += 1;
+    }
+}
+
+pub(crate) struct NotificationArea<'a> {
+    pub egui_ctx: &'a mut bevy_egui::egui::Context,
+    pub notifications: &'a mut Notifications,
+    pub layers: &'a rgis_layers::Layers,
+    pub top_panel_height: &'a rgis_units::TopPanelHeight,
+}
+
+impl NotificationArea<'_> {
+    pub(crate) fn render(&mut self) {
+        if self.notifications.notifications.is_empty() {
+            return;
+        }
+
+        let mut dismissed = None;
+        egui::Area::new(egui::Id::new("notification_area"))
+            .anchor(egui::Align2::RIGHT_TOP, [-5., self.top_panel_height.0 + 5.])
+            .order(egui::Order::Foreground)
+            .show(self.egui_ctx, |ui| {
+                ui.set_max_width(320.);
+                for (id, notification) in &self.notifications.notifications {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(ui.visuals().error_fg_color, "⚠");
+                            ui.strong(&notification.summary);
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+                                if ui.small_button("✖").on_hover_text("Dismiss").clicked() {
+                                    dismissed = Some(*id);
+                                }
+                            });
+                        });
+                        if let Some(layer) = notification
+                            .layer_id
+                            .and_then(|layer_id| self.layers.get(layer_id))
+                        {
+                            ui.label(format!("Layer: {}", layer.name));
+                        }
+                        if let Some((error, sources)) = notification.details.split_first() {
+                            ui.label(error);
+                            if !sources.is_empty() {
+                                egui::CollapsingHeader::new("Details")
+                                    .id_salt(("notification", *id))
+                                    .show(ui, |ui| {
+                                        for source in sources {
+                                            ui.label(format!("Caused by: {source}"));
+                                        }
+                                    });
+                            }
+                        }
+                    });
+                }
+            });
+
+        if let Some(dismissed) = dismissed {
+            self.notifications
+                .notifications
+                .retain(|(id, _)| *id != dismissed);
+        }
+    }
+}
@@ -6,6 +6,15 @@ pub(crate) struct OperationWindow<'w> {
     pub create_layer_event_writer: bevy::ecs::event::EventWriter<'w, rgis_events::CreateLayerEvent>,
     pub render_message_event_writer:
         bevy::ecs::event::EventWriter<'w, rgis_events::RenderMessageEvent>,
+    pub error_notification_event_writer:
+        bevy::ecs::event::EventWriter<'w, rgis_events::ErrorNotificationEvent>,
+}
+
+impl crate::OperationWindowState {
+    /// Name of the layer the operation creates
+    fn new_layer_name(&self) -> String {
+        format!("{} ({})", self.layer_name, self.operation_name)
+    }
 }
 
 impl OperationWindow<'_> {
@@ -26,16 +35,35 @@ impl OperationWindow<'_> {
                         self.create_layer_event_writer
                             .write(rgis_events::CreateLayerEvent {
                                 feature_collection,
-                                name: "FOOOOO".into(),      // FIXME
-                                source_crs_epsg_code: 4326, // FIXME
+                                name: self.state.new_layer_name(),
+                                source_crs_epsg_code: self.state.crs_epsg_code,
                             });
                     }
+                    Ok(rgis_geo_ops::Outcome::FeatureProperties {
+                        feature_collection,
+                        summary,
+                    }) => {
+                        self.create_layer_event_writer
+                            .write(rgis_events::CreateLayerEvent {
+                                feature_collection,
+                                name: self.state.new_layer_name(),
+                                source_crs_epsg_code: self.state.crs_epsg_code,
+                            });
+                        self.render_message_event_writer
+                            .write(rgis_events::RenderMessageEvent(summary));
+                    }
                     Ok(rgis_geo_ops::Outcome::Text(text)) => {
                         self.render_message_event_writer
                             .write(rgis_events::RenderMessageEvent(text));
                     }
                     Err(e) => {
                         bevy::log::error!("Encountered an error during the operation: {}", e);
+                        let mut notification = rgis_events::ErrorNotificationEvent::new(
+                            format!("Could not perform '{}'", self.state.operation_name),
+                            &*e,
+                        );
+                        notification.layer_id = self.state.layer_id;
+                        self.error_notification_event_writer.write(notification);
                     }
                 }
                 self.state.is_visible = false;
@@ -0,0 +1,85 @@
+use bevy_egui::egui;
+
+pub(crate) struct RecentSourcesWidget<'a, 'w> {
+    pub recent_sources: &'a rgis_settings::RecentSources,
+    pub load_file_event_writer:
+        &'a mut bevy::ecs::event::EventWriter<'w, rgis_events::LoadFileEvent>,
+}
+
+impl egui::Widget for RecentSourcesWidget<'_, '_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        ui.vertical(|ui| {
+            if self.recent_sources.is_empty() {
+                ui.label("No recent files");
+                return;
+            }
+            for source in self.recent_sources.iter() {
+                let button = ui
+                    .button(button_text(source))
+                    .on_hover_text(hover_text(source));
+                if button.clicked() {
+                    self.load_file_event_writer.write(source.clone());
+                    ui.close_menu();
+                }
+            }
+        })
+        .response
+    }
+}
+
+fn button_text(source: &rgis_events::LoadFileEvent) -> String {
+    match source {
+        rgis_events::LoadFileEvent::FromNetwork { name, .. } => format!("🌐 {name}"),
+        rgis_events::LoadFileEvent::FromBytes { file_name, .. }
+        | rgis_events::LoadFileEvent::FromPath { file_name, .. } => format!("📄 {file_name}"),
+        rgis_events::LoadFileEvent::FromOgcApiFeatures { name, .. }
+        | rgis_events::LoadFileEvent::FromArcGisFeatureLayer { name, .. }
+        | rgis_events::LoadFileEvent::FromWfs { name, .. } => format!("🌐 {name}"),
+    }
+}
+
+fn hover_text(source: &rgis_events::LoadFileEvent) -> String {
+    match source {
+        rgis_events::LoadFileEvent::FromNetwork {
+            url,
+            crs_epsg_code,
+            file_format,
+            ..
+        } => format!(
+            "{url}\n{}\nEPSG:{crs_epsg_code}",
+            file_format.map_or("Auto-detected format", |f| f.display_name())
+        ),
+        rgis_events::LoadFileEvent::FromBytes {
+            file_format,
+            crs_epsg_code,
+            ..
+        } => format!("{}\nEPSG:{crs_epsg_code}", file_format.display_name()),
+        rgis_events::LoadFileEvent::FromPath {
+            path,
+            file_format,
+            crs_epsg_code,
+            ..
+        } => format!(
+            "{}\n{}\nEPSG:{crs_epsg_code}",
+            path.display(),
+            file_format.display_name()
+        ),
+        rgis_events::LoadFileEvent::FromOgcApiFeatures {
+            items_url, bbox, ..
+        } => match bbox {
+            Some([min_x, min_y, max_x, max_y]) => {
+                format!("{items_url}\nOGC API – Features\nbbox: {min_x}, {min_y}, {max_x}, {max_y}")
+            }
+            None => format!("{items_url}\nOGC API – Features"),
+        },
+        rgis_events::LoadFileEvent::FromArcGisFeatureLayer { layer_url, .. } => {
+            format!("{layer_url}\nArcGIS REST layer")
+        }
+        rgis_events::LoadFileEvent::FromWfs {
+            service_url,
+            type_name,
+            crs_epsg_code,
+            ..
+        } => format!("{service_url}\nWFS: {type_name}\nEPSG:{crs_epsg_code}"),
+    }
+}
@@ -1,4 +1,5 @@
 use bevy_egui::egui::{self, Align, Layout, Widget};
+use geo::BoundingRect;
 use std::marker;
 
 // const MAX_SIDE_PANEL_WIDTH: f32 = 200.0f32;
@@ -18,6 +19,14 @@ pub struct Events<'w> {
         bevy::ecs::event::EventWriter<'w, crate::events::OpenOperationWindowEvent>,
     show_manage_layer_window_event_writer:
         bevy::ecs::event::EventWriter<'w, rgis_events::ShowManageLayerWindowEvent>,
+    show_attribute_table_window_event_writer:
+        bevy::ecs::event::EventWriter<'w, rgis_events::ShowAttributeTableWindowEvent>,
+    update_selection_event_writer:
+        bevy::ecs::event::EventWriter<'w, rgis_events::UpdateSelectionEvent>,
+    error_notification_event_writer:
+        bevy::ecs::event::EventWriter<'w, rgis_events::ErrorNotificationEvent>,
+    edit_layer_vertices_event_writer:
+        bevy::ecs::event::EventWriter<'w, rgis_events::EditLayerVerticesEvent>,
 }
 
 pub(crate) struct SidePanel<'a, 'w> {
@@ -91,19 +100,23 @@ impl<Op: rgis_geo_ops::OperationEntry> egui::Widget for OperationButton<'_, '_,
         );
         if button.clicked() {
             let mut operation = Op::build();
+            operation.set_crs_epsg_code(self.layer.crs_epsg_code);
             match operation.next_action() {
                 rgis_geo_ops::Action::RenderUi => {
                     self.events.open_operation_window_event_writer.write(
                         crate::events::OpenOperationWindowEvent {
                             operation,
-                            feature_collection: self.layer.unprojected_feature_collection.clone(), // TODO: clone?
+                            feature_collection: self.layer.operation_feature_collection(),
+                            operation_name: Op::NAME,
+                            layer_id: self.layer.id,
+                            layer_name: self.layer.name.clone(),
+                            crs_epsg_code: self.layer.crs_epsg_code,
                         },
                     );
                 }
                 rgis_geo_ops::Action::Perform => {
                     // TODO: perform in background job
-                    let outcome =
-                        operation.perform(self.layer.unprojected_feature_collection.clone()); // TODO: clone?
+                    let outcome = operation.perform(self.layer.operation_feature_collection());
 
                     match outcome {
                         Ok(rgis_geo_ops::Outcome::FeatureCollection(feature_collection)) => {
@@ -115,6 +128,21 @@ impl<Op: rgis_geo_ops::OperationEntry> egui::Widget for OperationButton<'_, '_,
                                 },
                             );
                         }
+                        Ok(rgis_geo_ops::Outcome::FeatureProperties {
+                            feature_collection,
+                            summary,
+                        }) => {
+                            self.events.create_layer_event_writer.write(
+                                rgis_events::CreateLayerEvent {
+                                    feature_collection,
+                                    name: format!("{} ({})", self.layer.name, Op::NAME),
+                                    source_crs_epsg_code: self.layer.crs_epsg_code,
+                                },
+                            );
+                            self.events
+                                .render_message_event_writer
+                                .write(rgis_events::RenderMessageEvent(summary));
+                        }
                         Ok(rgis_geo_ops::Outcome::Text(text)) => {
                             self.events
                                 .render_message_event_writer
@@ -122,6 +150,13 @@ impl<Op: rgis_geo_ops::OperationEntry> egui::Widget for OperationButton<'_, '_,
                         }
                         Err(e) => {
                             bevy::log::error!("Encountered an error during the operation: {}", e);
+                            self.events.error_notification_event_writer.write(
+                                rgis_events::ErrorNotificationEvent::new(
+                                    format!("Could not perform '{}'", Op::NAME),
+                                    &*e,
+                                )
+                                .with_layer_id(self.layer.id),
+                            );
                         }
                     }
                 }
@@ -172,7 +207,13 @@ impl Widget for Layer<'_, '_> {
             is_move_down_enabled,
             events: _,
         } = self;
-        egui::CollapsingHeader::new(&layer.name)
+        // Mark layers whose features were added or edited
+        let header = if layer.has_unsaved_changes() {
+            format!("{} *", layer.name)
+        } else {
+            layer.name.clone()
+        };
+        egui::CollapsingHeader::new(header)
             .id_salt(layer.id) // Instead of using the layer name as the ID (which is not unique), use the layer ID
             .show(ui, |ui| {
                 if !layer.is_active() {
@@ -189,6 +230,27 @@ impl Widget for Layer<'_, '_> {
                             .write(rgis_events::ShowManageLayerWindowEvent(layer.id));
                     }
 
+                    if ui.button("📋 Attribute table").clicked() {
+                        self.events
+                            .show_attribute_table_window_event_writer
+                            .write(rgis_events::ShowAttributeTableWindowEvent(layer.id));
+                    }
+
+                    ui.add(SelectionWidget {
+                        layer,
+                        events: self.events,
+                    });
+
+                    if ui
+                        .button("⬡ Edit vertices")
+                        .on_hover_text("Edits the vertices of the layer's selected feature")
+                        .clicked()
+                    {
+                        self.events
+                            .edit_layer_vertices_event_writer
+                            .write(rgis_events::EditLayerVerticesEvent(layer.id));
+                    }
+
                     ui.add(MoveUpMoveDownWidget {
                         layer,
                         is_move_up_enabled,
@@ -214,6 +276,12 @@ impl Widget for Layer<'_, '_> {
                     egui::CollapsingHeader::new("⚙ Operations")
                         .id_salt(format!("{:?}-operations", layer.id)) // Instead of using the layer name as the ID (which is not unique), use the layer ID
                         .show(ui, |ui| {
+                            if !layer.selected_feature_ids.is_empty() {
+                                ui.label(format!(
+                                    "Operations run on the {} selected features",
+                                    layer.selected_feature_ids.len()
+                                ));
+                            }
                             ui.with_layout(Layout::top_down_justified(Align::LEFT), |ui| {
                                 ui.add(OperationsWidget {
                                     layer,
@@ -227,6 +295,62 @@ impl Widget for Layer<'_, '_> {
     }
 }
 
+struct SelectionWidget<'a, 'w> {
+    layer: &'a rgis_layers::Layer,
+    events: &'a mut Events<'w>,
+}
+
+impl egui::Widget for SelectionWidget<'_, '_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let layer_id = self.layer.id;
+        let selected_count = self.layer.selected_feature_ids.len();
+        ui.vertical(|ui| {
+            ui.label(format!("{} selected features", selected_count));
+            ui.horizontal(|ui| {
+                if ui.button("Select all").clicked() {
+                    self.events
+                        .update_selection_event_writer
+                        .write(rgis_events::UpdateSelectionEvent::All(layer_id));
+                }
+                if ui.button("Invert").clicked() {
+                    self.events
+                        .update_selection_event_writer
+                        .write(rgis_events::UpdateSelectionEvent::Invert(layer_id));
+                }
+                if ui
+                    .add_enabled(selected_count > 0, egui::Button::new("Clear"))
+                    .clicked()
+                {
+                    self.events
+                        .update_selection_event_writer
+                        .write(rgis_events::UpdateSelectionEvent::Clear(layer_id));
+                }
+            });
+            if ui
+                .add_enabled(
+                    selected_count > 0,
+                    egui::Button::new("New layer from selection"),
+                )
+                .clicked()
+            {
+                self.events
+                    .create_layer_event_writer
+                    .write(rgis_events::CreateLayerEvent {
+                        feature_collection: geo_features::FeatureCollection::from_features(
+                            self.layer
+                                .selected_unprojected_features()
+                                .cloned()
+                                .collect(),
+                        ),
+                        name: format!("{} (selection)", self.layer.name),
+                        source_crs_epsg_code: self.layer.crs_epsg_code,
+                    });
+            }
+        })
+        .response
+    }
+}
+
 struct MoveUpMoveDownWidget<'a, 'w> {
     layer: &'a rgis_layers::Layer,
     is_move_up_enabled: bool,
@@ -297,7 +421,8 @@ impl egui::Widget for OperationsWidget<'_, '_> {
     fn ui(self, ui: &mut egui::Ui) -> egui::Response {
         ui.with_layout(Layout::top_down_justified(Align::LEFT), |ui| {
             if ui.button("Bounding rect").clicked() {
-                if let Ok(bounding_rect) = self.layer.unprojected_feature_collection.bounding_rect()
+                if let Some(bounding_rect) =
+                    self.layer.operation_feature_collection().bounding_rect()
                 {
                     let feature_collection =
                         geo_features::FeatureCollection::from_geometry(bounding_rect.into());
@@ -311,6 +436,10 @@ impl egui::Widget for OperationsWidget<'_, '_> {
                 }
             }
 
+            ui.add(OperationButton::<rgis_geo_ops::Buffer>::new(
+                self.events,
+                self.layer,
+            ));
             ui.add(OperationButton::<rgis_geo_ops::ConvexHull>::new(
                 self.events,
                 self.layer,
@@ -335,7 +464,11 @@ impl egui::Widget for OperationsWidget<'_, '_> {
                 self.events,
                 self.layer,
             ));
-            ui.add(OperationButton::<rgis_geo_ops::UnsignedArea>::new(
+            ui.add(OperationButton::<rgis_geo_ops::GeodesicArea>::new(
+                self.events,
+                self.layer,
+            ));
+            ui.add(OperationButton::<rgis_geo_ops::GeodesicLength>::new(
                 self.events,
                 self.layer,
             ));
@@ -1,9 +1,6 @@
 use crate::window;
 use bevy::{prelude::*, window::PrimaryWindow};
-use bevy_egui::{
-    egui::{self, Widget},
-    EguiContextPass, EguiContexts,
-};
+use bevy_egui::{egui, EguiContextPass, EguiContexts};
 
 fn render_bottom_panel(
     mut bevy_egui_ctx: EguiContexts,
@@ -51,11 +48,34 @@ fn handle_open_file_job(
     }
 }
 
+fn handle_ogc_api_collections_job(
+    mut finished_jobs: bevy_jobs::FinishedJobs,
+    mut collections: ResMut<crate::add_layer_window::OgcApiCollections>,
+) {
+    while let Some(outcome) = finished_jobs.take_next::<rgis_network::OgcApiCollectionsJob>() {
+        collections.0 = Some(outcome.map_err(|e| e.to_string()));
+    }
+}
+
+fn handle_wfs_capabilities_job(
+    mut finished_jobs: bevy_jobs::FinishedJobs,
+    mut capabilities: ResMut<crate::add_layer_window::WfsCapabilities>,
+) {
+    while let Some(outcome) = finished_jobs.take_next::<rgis_network::WfsCapabilitiesJob>() {
+        capabilities.0 = Some(outcome.map_err(|e| e.to_string()));
+    }
+}
+
 fn render_manage_layer_window(
     mut state: Local<crate::ManageLayerWindowState>,
     mut bevy_egui_ctx: EguiContexts,
     layers: Res<rgis_layers::Layers>,
     mut color_events: ResMut<bevy::ecs::event::Events<rgis_events::UpdateLayerColorEvent>>,
+    mut update_layer_filter_event_writer: bevy::ecs::event::EventWriter<
+        rgis_events::UpdateLayerFilterEvent,
+    >,
+    mut create_layer_event_writer: bevy::ecs::event::EventWriter<rgis_events::CreateLayerEvent>,
+    mut rename_layer_event_writer: bevy::ecs::event::EventWriter<rgis_events::RenameLayerEvent>,
     mut show_manage_layer_window_event_reader: bevy::ecs::event::EventReader<
         rgis_events::ShowManageLayerWindowEvent,
     >,
@@ -63,6 +83,12 @@ fn render_manage_layer_window(
     if let Some(event) = show_manage_layer_window_event_reader.read().last() {
         state.is_visible = true;
         state.layer_id = Some(event.0);
+        state.filter_text = layers
+            .get(event.0)
+            .and_then(|layer| layer.filter())
+            .map(|filter| filter.as_str().to_owned())
+            .unwrap_or_default();
+        state.filter_error = None;
     }
 
     crate::manage_layer_window::ManageLayerWindow {
@@ -70,6 +96,93 @@ fn render_manage_layer_window(
         layers: &layers,
         egui_ctx: bevy_egui_ctx.ctx_mut(),
         color_events: &mut color_events,
+        update_layer_filter_event_writer: &mut update_layer_filter_event_writer,
+        create_layer_event_writer: &mut create_layer_event_writer,
+        rename_layer_event_writer: &mut rename_layer_event_writer,
+    }
+    .render();
+}
+
+fn render_attribute_table_window(
+    mut state: Local<crate::attribute_table_window::AttributeTableWindowState>,
+    mut bevy_egui_ctx: EguiContexts,
+    layers: Res<rgis_layers::Layers>,
+    mut show_attribute_table_window_event_reader: bevy::ecs::event::EventReader<
+        rgis_events::ShowAttributeTableWindowEvent,
+    >,
+    mut update_selection_event_writer: bevy::ecs::event::EventWriter<
+        rgis_events::UpdateSelectionEvent,
+    >,
+    mut center_camera_on_feature_event_writer: bevy::ecs::event::EventWriter<
+        rgis_events::CenterCameraOnFeatureEvent,
+    >,
+) {
+    if let Some(event) = show_attribute_table_window_event_reader.read().last() {
+        state.is_visible = true;
+        state.layer_id = Some(event.0);
+    }
+
+    crate::attribute_table_window::AttributeTableWindow {
+        state: &mut state,
+        layers: &layers,
+        egui_ctx: bevy_egui_ctx.ctx_mut(),
+        update_selection_event_writer: &mut update_selection_event_writer,
+        center_camera_on_feature_event_writer: &mut center_camera_on_feature_event_writer,
+    }
+    .render();
+}
+
+fn render_measurement_window(
+    mut bevy_egui_ctx: EguiContexts,
+    mut measurement: ResMut<rgis_mouse::Measurement>,
+    mut rgis_settings: ResMut<rgis_settings::RgisSettings>,
+) {
+    crate::measurement_window::MeasurementWindow {
+        egui_ctx: bevy_egui_ctx.ctx_mut(),
+        measurement: &mut measurement,
+        rgis_settings: &mut rgis_settings,
+    }
+    .render();
+}
+
+fn current_tool_is_measure(rgis_settings: Res<rgis_settings::RgisSettings>) -> bool {
+    rgis_settings.current_tool == rgis_settings::Tool::Measure
+}
+
+fn render_vertex_editor_window(
+    mut bevy_egui_ctx: EguiContexts,
+    vertex_editor: Res<rgis_mouse::VertexEditor>,
+    layers: Res<rgis_layers::Layers>,
+    mut rgis_settings: ResMut<rgis_settings::RgisSettings>,
+) {
+    crate::vertex_editor_window::VertexEditorWindow {
+        egui_ctx: bevy_egui_ctx.ctx_mut(),
+        vertex_editor: &vertex_editor,
+        layers: &layers,
+        rgis_settings: &mut rgis_settings,
+    }
+    .render();
+}
+
+fn current_tool_is_edit_vertices(rgis_settings: Res<rgis_settings::RgisSettings>) -> bool {
+    rgis_settings.current_tool == rgis_settings::Tool::EditVertices
+}
+
+fn render_new_feature_window(
+    mut bevy_egui_ctx: EguiContexts,
+    mut state: Local<crate::new_feature_window::NewFeatureWindowState>,
+    mut feature_drawn_event_reader: bevy::ecs::event::EventReader<rgis_events::FeatureDrawnEvent>,
+    mut add_feature_event_writer: bevy::ecs::event::EventWriter<rgis_events::AddFeatureEvent>,
+) {
+    state.pending.extend(
+        feature_drawn_event_reader
+            .read()
+            .map(|event| event.0.clone()),
+    );
+    crate::new_feature_window::NewFeatureWindow {
+        egui_ctx: bevy_egui_ctx.ctx_mut(),
+        state: &mut state,
+        add_feature_event_writer: &mut add_feature_event_writer,
     }
     .render();
 }
@@ -85,6 +198,10 @@ impl Default for IsVisible {
 fn render_add_layer_window(
     mut is_visible: Local<IsVisible>,
     mut selected_file: ResMut<crate::add_layer_window::SelectedFile>,
+    mut ogc_api_collections: ResMut<crate::add_layer_window::OgcApiCollections>,
+    mut wfs_capabilities: ResMut<crate::add_layer_window::WfsCapabilities>,
+    visible_extent: Res<rgis_units::VisibleExtent>,
+    rgis_settings: Res<rgis_settings::RgisSettings>,
     mut bevy_egui_ctx: EguiContexts,
     mut job_spawner: bevy_jobs::JobSpawner,
     mut state: Local<crate::add_layer_window::State>,
@@ -102,6 +219,10 @@ fn render_add_layer_window(
     crate::add_layer_window::AddLayerWindow {
         state: &mut state,
         selected_file: &mut selected_file,
+        ogc_api_collections: &mut ogc_api_collections,
+        wfs_capabilities: &mut wfs_capabilities,
+        visible_extent: *visible_extent,
+        target_crs_epsg_code: rgis_settings.target_crs_epsg_code,
         is_visible: &mut (*is_visible).0,
         egui_ctx: bevy_egui_ctx.ctx_mut(),
         job_spawner: &mut job_spawner,
@@ -143,21 +264,40 @@ fn render_feature_properties_window(
     mut render_message_events: ResMut<
         bevy::ecs::event::Events<rgis_events::RenderFeaturePropertiesEvent>,
     >,
+    mut update_feature_properties_event_writer: bevy::ecs::event::EventWriter<
+        rgis_events::UpdateFeaturePropertiesEvent,
+    >,
 ) {
     if let Some(event) = render_message_events.drain().last() {
         state.is_visible = true;
         state.layer_id = Some(event.layer_id);
+        state.layer_name = event.layer_name;
+        state.feature_id = Some(event.feature_id);
+        state.draft = crate::widgets::PropertiesDraft::from_properties(&event.properties);
         state.properties = Some(event.properties);
     }
 
     let Some(layer) = state.layer_id.and_then(|id| layers.get(id)) else {
         return;
     };
+    let Some(feature) = state
+        .feature_id
+        .and_then(|id| layer.unprojected_feature_collection.get(id))
+    else {
+        return;
+    };
+    // Start over from the feature's properties once they changed, like after applying the
+    // draft or undoing
+    if state.properties.as_ref() != Some(&feature.properties) {
+        state.draft = crate::widgets::PropertiesDraft::from_properties(&feature.properties);
+        state.properties = Some(feature.properties.clone());
+    }
 
     crate::feature_properties_window::FeaturePropertiesWindow {
         state: &mut state,
         layer,
         egui_ctx: bevy_egui_ctx.ctx_mut(),
+        update_feature_properties_event_writer: &mut update_feature_properties_event_writer,
     }
     .render();
 }
@@ -185,11 +325,16 @@ fn render_operation_window(
     mut bevy_egui_ctx: EguiContexts,
     create_layer_event_writer: EventWriter<rgis_events::CreateLayerEvent>,
     render_message_event_writer: EventWriter<rgis_events::RenderMessageEvent>,
+    error_notification_event_writer: EventWriter<rgis_events::ErrorNotificationEvent>,
 ) {
     if let Some(event) = events.drain().last() {
         state.is_visible = true;
         state.operation = Some(event.operation);
         state.feature_collection = event.feature_collection; // Should this be `Some()`? Otherwise we'll always have something stored
+        state.operation_name = event.operation_name;
+        state.layer_id = Some(event.layer_id);
+        state.layer_name = event.layer_name;
+        state.crs_epsg_code = event.crs_epsg_code;
     }
 
     crate::operation_window::OperationWindow {
@@ -197,55 +342,63 @@ fn render_operation_window(
         state: &mut state,
         create_layer_event_writer,
         render_message_event_writer,
+        error_notification_event_writer,
     }
     .render();
 }
 
-fn render_in_progress(query: Query<&bevy_jobs::InProgressJob>, mut bevy_egui_ctx: EguiContexts) {
-    let mut in_progress_job_iter: std::iter::Peekable<
-        bevy::ecs::query::QueryIter<'_, '_, &bevy_jobs::InProgressJob, ()>,
-    > = query.iter().peekable();
-
-    if in_progress_job_iter.peek().is_none() {
-        return;
+fn render_jobs_window(
+    mut bevy_egui_ctx: EguiContexts,
+    query: Query<(Entity, &bevy_jobs::InProgressJob)>,
+    mut failed_jobs: ResMut<crate::jobs_window::FailedJobs>,
+    mut commands: Commands,
+    mut load_file_events: ResMut<Events<rgis_events::LoadFileEvent>>,
+    mut retry_page_events: ResMut<Events<rgis_events::RetryPageEvent>>,
+    mut reproject_layer_events: ResMut<Events<rgis_events::ReprojectLayerEvent>>,
+) {
+    crate::jobs_window::JobsWindow {
+        egui_ctx: bevy_egui_ctx.ctx_mut(),
+        in_progress_jobs: query.iter().collect(),
+        failed_jobs: &mut failed_jobs,
+        commands: &mut commands,
+        load_file_events: &mut load_file_events,
+        retry_page_events: &mut retry_page_events,
+        reproject_layer_events: &mut reproject_layer_events,
     }
-
-    egui::Window::new("Running jobs")
-        .open(&mut true)
-        .title_bar(false)
-        .anchor(egui::Align2::RIGHT_BOTTOM, [-5., -5.])
-        .resizable(false)
-        .show(bevy_egui_ctx.ctx_mut(), |ui| {
-            for in_progress_job in in_progress_job_iter {
-                ui.add(InProgressJobWidget { in_progress_job });
-            }
-        });
+    .render();
 }
 
-struct InProgressJobWidget<'a> {
-    in_progress_job: &'a bevy_jobs::InProgressJob,
+fn handle_error_notification_events(
+    mut error_notification_event_reader: EventReader<rgis_events::ErrorNotificationEvent>,
+    mut notifications: ResMut<crate::notifications::Notifications>,
+) {
+    for event in error_notification_event_reader.read() {
+        notifications.push(event.clone());
+    }
 }
 
-impl Widget for InProgressJobWidget<'_> {
-    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
-        let Self { in_progress_job } = self;
-
-        let name = &in_progress_job.name;
-        let progress = in_progress_job.progress;
-
-        ui.horizontal(|ui| {
-            ui.add(egui::Spinner::new());
-            if progress > 0 {
-                egui::ProgressBar::new(f32::from(progress) / 100.)
-                    .desired_width(200.)
-                    .text(format!("Running '{name}'"))
-                    .ui(ui);
-            } else {
-                ui.label(format!("Running '{name}'"));
-            }
-        })
-        .response
+fn render_notification_area(
+    mut bevy_egui_ctx: EguiContexts,
+    mut notifications: ResMut<crate::notifications::Notifications>,
+    layers: Res<rgis_layers::Layers>,
+    top_panel_height: Res<rgis_units::TopPanelHeight>,
+) {
+    crate::notifications::NotificationArea {
+        egui_ctx: bevy_egui_ctx.ctx_mut(),
+        notifications: &mut notifications,
+        layers: &layers,
+        top_panel_height: &top_panel_height,
     }
+    .render();
+}
+
+fn handle_job_failed_events(
+    mut job_failed_event_reader: EventReader<rgis_events::JobFailedEvent>,
+    mut failed_jobs: ResMut<crate::jobs_window::FailedJobs>,
+) {
+    failed_jobs
+        .0
+        .extend(job_failed_event_reader.read().cloned());
 }
 
 fn render_top_panel(
@@ -257,6 +410,13 @@ fn render_top_panel(
     mut is_debug_window_open: ResMut<
         window::IsWindowOpen<crate::debug_window::DebugWindow<'static, 'static>>,
     >,
+    recent_sources: Res<rgis_settings::RecentSources>,
+    mut load_file_event_writer: EventWriter<rgis_events::LoadFileEvent>,
+    mut http_cache: ResMut<rgis_network::HttpCache>,
+    mut clear_http_cache_events: ResMut<Events<rgis_events::ClearHttpCacheEvent>>,
+    history: Res<rgis_layers::History>,
+    mut undo_events: ResMut<Events<rgis_events::UndoEvent>>,
+    mut redo_events: ResMut<Events<rgis_events::RedoEvent>>,
 ) {
     let Ok(mut window) = windows.single_mut() else {
         return;
@@ -269,6 +429,13 @@ fn render_top_panel(
         app_settings: &mut app_settings,
         top_panel_height: &mut top_panel_height,
         is_debug_window_open: &mut is_debug_window_open,
+        recent_sources: &recent_sources,
+        load_file_event_writer: &mut load_file_event_writer,
+        http_cache: &mut http_cache,
+        clear_http_cache_events: &mut clear_http_cache_events,
+        history: &history,
+        undo_events: &mut undo_events,
+        redo_events: &mut redo_events,
     }
     .render();
 }
@@ -317,13 +484,26 @@ pub fn configure(app: &mut App) {
             render_top_panel.in_set(RenderSystemSet::RenderingTopBottomPanels),
             render_bottom_panel.in_set(RenderSystemSet::RenderingTopBottomPanels),
             render_side_panel.in_set(RenderSystemSet::SideBarProgressBar),
-            render_in_progress.in_set(RenderSystemSet::SideBarProgressBar),
+            render_jobs_window.in_set(RenderSystemSet::SideBarProgressBar),
+            handle_job_failed_events,
+            handle_error_notification_events,
+            render_notification_area.in_set(RenderSystemSet::Windows),
             handle_open_file_job,
+            handle_ogc_api_collections_job,
+            handle_wfs_capabilities_job,
             render_manage_layer_window.in_set(RenderSystemSet::Windows),
             render_add_layer_window.in_set(RenderSystemSet::Windows),
             render_change_crs_window.in_set(RenderSystemSet::Windows),
             render_feature_properties_window.in_set(RenderSystemSet::Windows),
             render_operation_window.in_set(RenderSystemSet::Windows),
+            render_attribute_table_window.in_set(RenderSystemSet::Windows),
+            render_measurement_window
+                .run_if(current_tool_is_measure)
+                .in_set(RenderSystemSet::Windows),
+            render_new_feature_window.in_set(RenderSystemSet::Windows),
+            render_vertex_editor_window
+                .run_if(current_tool_is_edit_vertices)
+                .in_set(RenderSystemSet::Windows),
         ),
     );
 
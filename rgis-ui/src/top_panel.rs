@@ -2,17 +2,31 @@ use crate::window;
 use bevy::prelude::*;
 use bevy_egui::egui;
 
-pub(crate) struct TopPanel<'a, 'w, 's> {
+const SELECT_TOOL_HOVER_TEXT: &str = "Selects the features intersecting the shape, or only those \
+    entirely inside it while holding Alt. Hold Shift to add to the selection, or Ctrl to toggle.";
+
+const DRAW_TOOL_HOVER_TEXT: &str = "Draws a feature into the scratch layer. Double-click or \
+    press Enter to finish a line or polygon, and press Esc to cancel it.";
+
+pub(crate) struct TopPanel<'a, 'w1, 's1, 'w2> {
     pub app_exit_events: &'a mut bevy::ecs::event::Events<bevy::app::AppExit>,
     pub egui_ctx: &'a mut bevy_egui::egui::Context,
     pub window: &'a mut Window,
     pub app_settings: &'a mut rgis_settings::RgisSettings,
     pub top_panel_height: &'a mut rgis_units::TopPanelHeight,
     pub is_debug_window_open:
-        &'a mut window::IsWindowOpen<crate::debug_window::DebugWindow<'w, 's>>,
+        &'a mut window::IsWindowOpen<crate::debug_window::DebugWindow<'w1, 's1>>,
+    pub recent_sources: &'a rgis_settings::RecentSources,
+    pub load_file_event_writer:
+        &'a mut bevy::ecs::event::EventWriter<'w2, rgis_events::LoadFileEvent>,
+    pub http_cache: &'a mut rgis_network::HttpCache,
+    pub clear_http_cache_events: &'a mut bevy::ecs::event::Events<rgis_events::ClearHttpCacheEvent>,
+    pub history: &'a rgis_layers::History,
+    pub undo_events: &'a mut bevy::ecs::event::Events<rgis_events::UndoEvent>,
+    pub redo_events: &'a mut bevy::ecs::event::Events<rgis_events::RedoEvent>,
 }
 
-impl TopPanel<'_, '_, '_> {
+impl TopPanel<'_, '_, '_, '_> {
     pub(crate) fn render(&mut self) {
         let inner_response = egui::TopBottomPanel::top("top_panel").show(self.egui_ctx, |ui| {
             egui::menu::bar(ui, |ui| {
@@ -20,10 +34,29 @@ impl TopPanel<'_, '_, '_> {
 
                 ui.label("rgis");
                 ui.menu_button("File", |ui| {
+                    ui.menu_button("Recent", |ui| {
+                        ui.add(crate::recent_sources::RecentSourcesWidget {
+                            recent_sources: self.recent_sources,
+                            load_file_event_writer: self.load_file_event_writer,
+                        });
+                    });
+                    ui.menu_button("Download cache", |ui| {
+                        ui.add(HttpCacheWidget {
+                            http_cache: self.http_cache,
+                            clear_http_cache_events: self.clear_http_cache_events,
+                        });
+                    });
                     ui.add(ExitButton {
                         app_exit_events: self.app_exit_events,
                     });
                 });
+                ui.menu_button("Edit", |ui| {
+                    ui.add(UndoRedoButtons {
+                        history: self.history,
+                        undo_events: self.undo_events,
+                        redo_events: self.redo_events,
+                    });
+                });
                 ui.menu_button("View", |ui| {
                     ui.add(FullScreenButton {
                         window: self.window,
@@ -65,6 +98,71 @@ impl TopPanel<'_, '_, '_> {
                     self.app_settings.current_tool = rgis_settings::Tool::Query;
                 }
 
+                if ui
+                    .add_enabled(
+                        self.app_settings.current_tool != rgis_settings::Tool::SelectRect,
+                        egui::Button::new("⬚ Rectangle Select").selected(
+                            self.app_settings.current_tool == rgis_settings::Tool::SelectRect,
+                        ),
+                    )
+                    .on_hover_text(SELECT_TOOL_HOVER_TEXT)
+                    .clicked()
+                {
+                    self.app_settings.current_tool = rgis_settings::Tool::SelectRect;
+                }
+
+                if ui
+                    .add_enabled(
+                        self.app_settings.current_tool != rgis_settings::Tool::SelectLasso,
+                        egui::Button::new("➰ Lasso Select").selected(
+                            self.app_settings.current_tool == rgis_settings::Tool::SelectLasso,
+                        ),
+                    )
+                    .on_hover_text(SELECT_TOOL_HOVER_TEXT)
+                    .clicked()
+                {
+                    self.app_settings.current_tool = rgis_settings::Tool::SelectLasso;
+                }
+
+                if ui
+                    .add_enabled(
+                        self.app_settings.current_tool != rgis_settings::Tool::Measure,
+                        egui::Button::new("📏 Measure").selected(
+                            self.app_settings.current_tool == rgis_settings::Tool::Measure,
+                        ),
+                    )
+                    .on_hover_text("Measures geodesic lengths and areas on the ellipsoid")
+                    .clicked()
+                {
+                    self.app_settings.current_tool = rgis_settings::Tool::Measure;
+                }
+
+                ui.separator();
+
+                for (tool, label) in [
+                    (rgis_settings::Tool::DrawPoint, "• Point"),
+                    (rgis_settings::Tool::DrawLine, "〰 Line"),
+                    (rgis_settings::Tool::DrawPolygon, "⬟ Polygon"),
+                ] {
+                    if ui
+                        .add_enabled(
+                            self.app_settings.current_tool != tool,
+                            egui::Button::new(label)
+                                .selected(self.app_settings.current_tool == tool),
+                        )
+                        .on_hover_text(DRAW_TOOL_HOVER_TEXT)
+                        .clicked()
+                    {
+                        self.app_settings.current_tool = tool;
+                    }
+                }
+
+                ui.menu_button("🧲 Snapping", |ui| {
+                    ui.add(SnappingSettings {
+                        app_settings: self.app_settings,
+                    });
+                });
+
                 if prev_current_tool == rgis_settings::Tool::Query
                     && self.app_settings.current_tool != rgis_settings::Tool::Query
                 {
@@ -92,6 +190,107 @@ impl egui::Widget for ExitButton<'_> {
     }
 }
 
+struct HttpCacheWidget<'a> {
+    http_cache: &'a mut rgis_network::HttpCache,
+    clear_http_cache_events: &'a mut bevy::ecs::event::Events<rgis_events::ClearHttpCacheEvent>,
+}
+
+impl egui::Widget for HttpCacheWidget<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                ui.label("Size limit:");
+                let mut max_size_mb = self.http_cache.max_size_mb();
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut max_size_mb)
+                            .range(0..=16 * 1024)
+                            .suffix(" MB"),
+                    )
+                    .changed()
+                {
+                    self.http_cache.set_max_size_mb(max_size_mb);
+                }
+            });
+            if ui.button("🗑 Clear cache").clicked() {
+                self.clear_http_cache_events.send_default();
+                ui.close_menu();
+            }
+        })
+        .response
+    }
+}
+
+struct UndoRedoButtons<'a> {
+    history: &'a rgis_layers::History,
+    undo_events: &'a mut bevy::ecs::event::Events<rgis_events::UndoEvent>,
+    redo_events: &'a mut bevy::ecs::event::Events<rgis_events::RedoEvent>,
+}
+
+impl egui::Widget for UndoRedoButtons<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        ui.vertical(|ui| {
+            let undo_description = self.history.undo_description();
+            if ui
+                .add_enabled(
+                    undo_description.is_some(),
+                    egui::Button::new(match undo_description {
+                        Some(description) => format!("⟲ Undo {description}"),
+                        None => "⟲ Undo".into(),
+                    })
+                    .shortcut_text("Ctrl+Z"),
+                )
+                .clicked()
+            {
+                self.undo_events.send_default();
+                ui.close_menu();
+            }
+            let redo_description = self.history.redo_description();
+            if ui
+                .add_enabled(
+                    redo_description.is_some(),
+                    egui::Button::new(match redo_description {
+                        Some(description) => format!("⟳ Redo {description}"),
+                        None => "⟳ Redo".into(),
+                    })
+                    .shortcut_text("Ctrl+Shift+Z"),
+                )
+                .clicked()
+            {
+                self.redo_events.send_default();
+                ui.close_menu();
+            }
+        })
+        .response
+    }
+}
+
+struct SnappingSettings<'a> {
+    app_settings: &'a mut rgis_settings::RgisSettings,
+}
+
+impl egui::Widget for SnappingSettings<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        ui.vertical(|ui| {
+            ui.checkbox(
+                &mut self.app_settings.is_snapping_enabled,
+                "Snap to vertices, intersections and edges",
+            );
+            ui.add_enabled_ui(self.app_settings.is_snapping_enabled, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Tolerance:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.app_settings.snapping_tolerance)
+                            .range(1.0..=50.0)
+                            .suffix(" px"),
+                    );
+                });
+            });
+        })
+        .response
+    }
+}
+
 struct FullScreenButton<'a> {
     window: &'a mut bevy::window::Window,
 }
@@ -0,0 +1,49 @@
+use bevy_egui::egui;
+
+pub(crate) struct VertexEditorWindow<'a> {
+    pub egui_ctx: &'a mut bevy_egui::egui::Context,
+    pub vertex_editor: &'a rgis_mouse::VertexEditor,
+    pub layers: &'a rgis_layers::Layers,
+    pub rgis_settings: &'a mut rgis_settings::RgisSettings,
+}
+
+impl VertexEditorWindow<'_> {
+    pub(crate) fn render(&mut self) {
+        let Some(layer) = self
+            .vertex_editor
+            .layer_id()
+            .and_then(|layer_id| self.layers.get(layer_id))
+        else {
+            // The layer was removed
+            self.rgis_settings.current_tool = rgis_settings::Tool::Pan;
+            return;
+        };
+        let mut is_open = true;
+        egui::Window::new("Edit Vertices")
+            .id(egui::Id::new("Vertex editor window"))
+            .open(&mut is_open)
+            .resizable(false)
+            .show(self.egui_ctx, |ui| {
+                ui.label(format!("Layer: {}", layer.name));
+                ui.separator();
+                if self.vertex_editor.feature_id().is_some() {
+                    ui.label(
+                        "Drag a vertex to move it, drag a segment's midpoint to insert a \
+                         vertex, or right-click a vertex to delete it. Press Esc to cancel a \
+                         drag.",
+                    );
+                } else if layer.selected_feature_ids.len() == 1 {
+                    ui.label("The selected feature's geometry type can't be edited.");
+                } else {
+                    ui.label("Select a single feature of the layer to edit its vertices.");
+                }
+                ui.separator();
+                if ui.button("✔ Done").clicked() {
+                    self.rgis_settings.current_tool = rgis_settings::Tool::Pan;
+                }
+            });
+        if !is_open {
+            self.rgis_settings.current_tool = rgis_settings::Tool::Pan;
+        }
+    }
+}
@@ -1,16 +1,27 @@
 use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
 use bevy_egui::egui;
 use std::marker;
 
 #[derive(SystemParam)]
 pub(crate) struct WelcomeWindow<'w, 's> {
-    _phantom: marker::PhantomData<(&'w (), &'s ())>,
+    recent_sources: Res<'w, rgis_settings::RecentSources>,
+    load_file_event_writer: EventWriter<'w, rgis_events::LoadFileEvent>,
+    _phantom: marker::PhantomData<&'s ()>,
 }
 
 impl<'w, 's> egui::Widget for WelcomeWindow<'w, 's> {
-    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+    fn ui(mut self, ui: &mut egui::Ui) -> egui::Response {
         ui.vertical_centered_justified(|ui| {
             ui.label("Welcome to rgis!");
+            if !self.recent_sources.is_empty() {
+                ui.separator();
+                ui.label("Recent:");
+                ui.add(crate::recent_sources::RecentSourcesWidget {
+                    recent_sources: &self.recent_sources,
+                    load_file_event_writer: &mut self.load_file_event_writer,
+                });
+            }
         })
         .response
     }
@@ -1,2 +1,4 @@
 pub mod crs_input;
 pub use crs_input::CrsInput;
+pub mod properties_editor;
+pub use properties_editor::{PropertiesDraft, PropertiesEditor};
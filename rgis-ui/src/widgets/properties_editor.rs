@@ -0,0 +1,212 @@
+use bevy_egui::egui;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ValueKind {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Date,
+    DateTime,
+    Null,
+    /// Binary, array or object values, which are kept as they are
+    Other,
+}
+
+impl ValueKind {
+    const EDITABLE: [ValueKind; 7] = [
+        ValueKind::String,
+        ValueKind::Integer,
+        ValueKind::Number,
+        ValueKind::Boolean,
+        ValueKind::Date,
+        ValueKind::DateTime,
+        ValueKind::Null,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            ValueKind::String => "String",
+            ValueKind::Integer => "Integer",
+            ValueKind::Number => "Number",
+            ValueKind::Boolean => "Boolean",
+            ValueKind::Date => "Date",
+            ValueKind::DateTime => "Date and time",
+            ValueKind::Null => "Null",
+            ValueKind::Other => "Other",
+        }
+    }
+
+    fn hint(self) -> &'static str {
+        match self {
+            ValueKind::Date => "YYYY-MM-DD",
+            ValueKind::DateTime => "YYYY-MM-DDTHH:MM:SSZ",
+            _ => "",
+        }
+    }
+}
+
+/// A property being edited, with its value kept as text until the form is submitted.
+#[derive(Clone)]
+struct PropertyDraft {
+    key: String,
+    kind: ValueKind,
+    text: String,
+    /// Value of an `Other` property
+    kept: Option<geo_features::Value>,
+}
+
+impl PropertyDraft {
+    fn new(key: String, value: &geo_features::Value) -> Self {
+        let (kind, text) = match value {
+            geo_features::Value::String(s) => (ValueKind::String, s.clone()),
+            geo_features::Value::Integer(i) => (ValueKind::Integer, i.to_string()),
+            geo_features::Value::Number(n) => (ValueKind::Number, n.to_string()),
+            geo_features::Value::Boolean(b) => (ValueKind::Boolean, b.to_string()),
+            geo_features::Value::Date(s) => (ValueKind::Date, s.clone()),
+            geo_features::Value::DateTime(s) => (ValueKind::DateTime, s.clone()),
+            geo_features::Value::Null => (ValueKind::Null, String::new()),
+            other => (ValueKind::Other, other.to_string()),
+        };
+        PropertyDraft {
+            key,
+            kind,
+            text,
+            kept: (kind == ValueKind::Other).then(|| value.clone()),
+        }
+    }
+
+    fn value(&self) -> Result<geo_features::Value, String> {
+        let text = self.text.trim();
+        Ok(match self.kind {
+            ValueKind::String => geo_features::Value::String(self.text.clone()),
+            ValueKind::Integer => geo_features::Value::Integer(
+                text.parse()
+                    .map_err(|_| format!("“{}” is not an integer", self.key))?,
+            ),
+            ValueKind::Number => geo_features::Value::Number(
+                text.parse()
+                    .map_err(|_| format!("“{}” is not a number", self.key))?,
+            ),
+            ValueKind::Boolean => geo_features::Value::Boolean(text == "true"),
+            ValueKind::Date => geo_features::Value::Date(text.to_owned()),
+            ValueKind::DateTime => geo_features::Value::DateTime(text.to_owned()),
+            ValueKind::Null => geo_features::Value::Null,
+            ValueKind::Other => self.kept.clone().unwrap_or(geo_features::Value::Null),
+        })
+    }
+}
+
+/// Properties of a feature being edited in a form.
+#[derive(Clone, Default)]
+pub struct PropertiesDraft(Vec<PropertyDraft>);
+
+impl PropertiesDraft {
+    pub fn from_properties(properties: &geo_features::Properties) -> Self {
+        let mut drafts = properties
+            .iter()
+            .map(|(key, value)| PropertyDraft::new(key.clone(), value))
+            .collect::<Vec<_>>();
+        drafts.sort_unstable_by(|a, b| a.key.cmp(&b.key));
+        PropertiesDraft(drafts)
+    }
+
+    /// The edited properties, or why they're invalid.
+    pub fn to_properties(&self) -> Result<geo_features::Properties, String> {
+        let mut properties = geo_features::Properties::with_capacity(self.0.len());
+        for draft in &self.0 {
+            if draft.key.is_empty() {
+                return Err("Property names can't be empty".into());
+            }
+            if properties
+                .insert(draft.key.clone(), draft.value()?)
+                .is_some()
+            {
+                return Err(format!("“{}” is used more than once", draft.key));
+            }
+        }
+        Ok(properties)
+    }
+}
+
+pub struct PropertiesEditor<'a> {
+    pub draft: &'a mut PropertiesDraft,
+}
+
+impl egui::Widget for PropertiesEditor<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        ui.vertical(|ui| {
+            let mut removed = None;
+            egui::Grid::new("properties_editor_grid")
+                .num_columns(4)
+                .striped(true)
+                .show(ui, |ui| {
+                    for (i, draft) in self.draft.0.iter_mut().enumerate() {
+                        ui.add(egui::TextEdit::singleline(&mut draft.key).hint_text("Name"));
+                        ui.add(ValueKindComboBox { draft, index: i });
+                        ui.add(ValueInput { draft });
+                        if ui.button("🗑").on_hover_text("Remove").clicked() {
+                            removed = Some(i);
+                        }
+                        ui.end_row();
+                    }
+                });
+            if let Some(i) = removed {
+                self.draft.0.remove(i);
+            }
+            if ui.button("➕ Add property").clicked() {
+                self.draft.0.push(PropertyDraft {
+                    key: String::new(),
+                    kind: ValueKind::String,
+                    text: String::new(),
+                    kept: None,
+                });
+            }
+        })
+        .response
+    }
+}
+
+struct ValueKindComboBox<'a> {
+    draft: &'a mut PropertyDraft,
+    index: usize,
+}
+
+impl egui::Widget for ValueKindComboBox<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let prev_kind = self.draft.kind;
+        let response = egui::ComboBox::from_id_salt(("property_kind", self.index))
+            .selected_text(self.draft.kind.name())
+            .show_ui(ui, |ui| {
+                for kind in ValueKind::EDITABLE {
+                    ui.selectable_value(&mut self.draft.kind, kind, kind.name());
+                }
+            })
+            .response;
+        if self.draft.kind != prev_kind && prev_kind == ValueKind::Other {
+            self.draft.kept = None;
+            self.draft.text.clear();
+        }
+        response
+    }
+}
+
+struct ValueInput<'a> {
+    draft: &'a mut PropertyDraft,
+}
+
+impl egui::Widget for ValueInput<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        match self.draft.kind {
+            ValueKind::Boolean => {
+                let mut checked = self.draft.text.trim() == "true";
+                let response = ui.checkbox(&mut checked, "");
+                self.draft.text = checked.to_string();
+                response
+            }
+            ValueKind::Null => ui.label(""),
+            ValueKind::Other => ui.label(egui::RichText::new(&self.draft.text).code()),
+            kind => ui.add(egui::TextEdit::singleline(&mut self.draft.text).hint_text(kind.hint())),
+        }
+    }
+}
@@ -41,6 +41,23 @@ impl ScreenCoord {
             y: pos_wld.y.into(),
         }
     }
+
+    /// Inverse of [`ScreenCoord::to_projected_geo_coord`].
+    pub fn from_projected_geo_coord(
+        coord: ProjectedCoord,
+        transform: &bevy::transform::components::Transform,
+        window: &bevy::prelude::Window,
+    ) -> Self {
+        let size = bevy::math::DVec2::new(f64::from(window.width()), f64::from(window.height()));
+
+        let d_vec = transform.compute_matrix().as_dmat4().inverse()
+            * bevy::math::DVec4::new(coord.x.0, coord.y.0, 0.0, 1.0);
+
+        ScreenCoord {
+            x: d_vec.x + size.x / 2.0,
+            y: size.y / 2.0 - d_vec.y,
+        }
+    }
 }
 
 pub struct MapArea<'a> {
@@ -136,6 +153,10 @@ pub struct TopPanelHeight(pub f32);
 #[derive(Copy, Clone, Resource)]
 pub struct BottomPanelHeight(pub f32);
 
+/// Projected extent of the map area not covered by UI panels, updated by the camera each frame.
+#[derive(Copy, Clone, Default, Resource)]
+pub struct VisibleExtent(pub Option<geo::Rect<ProjectedScalar>>);
+
 #[derive(bevy::ecs::system::SystemParam, Resource)]
 pub struct UiMargins<'w, 's> {
     pub left: Res<'w, SidePanelWidth>,
@@ -143,3 +164,178 @@ pub struct UiMargins<'w, 's> {
     pub bottom: Res<'w, BottomPanelHeight>,
     marker: marker::PhantomData<&'s usize>,
 }
+
+/// Unit used to display geodesic lengths.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum LengthUnit {
+    #[default]
+    Meters,
+    Kilometers,
+    Feet,
+    Miles,
+    NauticalMiles,
+}
+
+impl LengthUnit {
+    pub const ALL: [LengthUnit; 5] = [
+        LengthUnit::Meters,
+        LengthUnit::Kilometers,
+        LengthUnit::Feet,
+        LengthUnit::Miles,
+        LengthUnit::NauticalMiles,
+    ];
+
+    fn meters_per_unit(self) -> f64 {
+        match self {
+            LengthUnit::Meters => 1.,
+            LengthUnit::Kilometers => 1_000.,
+            LengthUnit::Feet => 0.3048,
+            LengthUnit::Miles => 1_609.344,
+            LengthUnit::NauticalMiles => 1_852.,
+        }
+    }
+
+    pub fn from_meters(self, meters: f64) -> f64 {
+        meters / self.meters_per_unit()
+    }
+
+    pub fn to_meters(self, value: f64) -> f64 {
+        value * self.meters_per_unit()
+    }
+
+    /// Also used in property names, since it's ASCII.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            LengthUnit::Meters => "m",
+            LengthUnit::Kilometers => "km",
+            LengthUnit::Feet => "ft",
+            LengthUnit::Miles => "mi",
+            LengthUnit::NauticalMiles => "nmi",
+        }
+    }
+
+    /// Format a length given in meters, e.g. `1.25 km`.
+    pub fn format(self, meters: f64) -> String {
+        format!("{:.2} {}", self.from_meters(meters), self.symbol())
+    }
+}
+
+/// Unit used to display geodesic areas.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum AreaUnit {
+    #[default]
+    SquareMeters,
+    SquareKilometers,
+    Hectares,
+    Acres,
+    SquareMiles,
+}
+
+impl AreaUnit {
+    pub const ALL: [AreaUnit; 5] = [
+        AreaUnit::SquareMeters,
+        AreaUnit::SquareKilometers,
+        AreaUnit::Hectares,
+        AreaUnit::Acres,
+        AreaUnit::SquareMiles,
+    ];
+
+    fn square_meters_per_unit(self) -> f64 {
+        match self {
+            AreaUnit::SquareMeters => 1.,
+            AreaUnit::SquareKilometers => 1_000_000.,
+            AreaUnit::Hectares => 10_000.,
+            AreaUnit::Acres => 4_046.856_422_4,
+            AreaUnit::SquareMiles => 2_589_988.110_336,
+        }
+    }
+
+    pub fn from_square_meters(self, square_meters: f64) -> f64 {
+        square_meters / self.square_meters_per_unit()
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            AreaUnit::SquareMeters => "m²",
+            AreaUnit::SquareKilometers => "km²",
+            AreaUnit::Hectares => "ha",
+            AreaUnit::Acres => "ac",
+            AreaUnit::SquareMiles => "mi²",
+        }
+    }
+
+    /// ASCII name of the unit, for use in property names.
+    pub fn identifier(self) -> &'static str {
+        match self {
+            AreaUnit::SquareMeters => "m2",
+            AreaUnit::SquareKilometers => "km2",
+            AreaUnit::Hectares => "ha",
+            AreaUnit::Acres => "ac",
+            AreaUnit::SquareMiles => "mi2",
+        }
+    }
+
+    /// Format an area given in square meters, e.g. `3.10 ha`.
+    pub fn format(self, square_meters: f64) -> String {
+        format!(
+            "{:.2} {}",
+            self.from_square_meters(square_meters),
+            self.symbol()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() <= expected.abs() * 1e-9,
+            "{actual} != {expected}"
+        );
+    }
+
+    #[test]
+    fn test_length_units() {
+        assert_close(LengthUnit::Kilometers.from_meters(1_500.), 1.5);
+        assert_close(LengthUnit::Feet.from_meters(0.3048), 1.);
+        assert_close(LengthUnit::Miles.from_meters(1_609.344), 1.);
+        assert_close(LengthUnit::NauticalMiles.to_meters(2.), 3_704.);
+        assert_close(LengthUnit::Miles.to_meters(1.), 5_280. * 0.3048);
+        for unit in LengthUnit::ALL {
+            assert_close(unit.to_meters(unit.from_meters(123.456)), 123.456);
+        }
+    }
+
+    #[test]
+    fn test_area_units() {
+        assert_close(AreaUnit::Hectares.from_square_meters(25_000.), 2.5);
+        assert_close(
+            AreaUnit::SquareKilometers.from_square_meters(100. * 10_000.),
+            1.,
+        );
+        let square_foot = 0.3048 * 0.3048;
+        // An acre is 43,560 square feet, and a square mile 640 acres
+        assert_close(
+            AreaUnit::Acres.from_square_meters(43_560. * square_foot),
+            1.,
+        );
+        assert_close(
+            AreaUnit::SquareMiles.from_square_meters(640. * 43_560. * square_foot),
+            1.,
+        );
+        assert_close(
+            AreaUnit::SquareMiles.from_square_meters(1_609.344 * 1_609.344),
+            1.,
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(LengthUnit::Kilometers.format(1_250.), "1.25 km");
+        assert_eq!(LengthUnit::Meters.format(0.004), "0.00 m");
+        assert_eq!(AreaUnit::Hectares.format(31_000.), "3.10 ha");
+        assert_eq!(AreaUnit::SquareMiles.format(0.), "0.00 mi²");
+    }
+}